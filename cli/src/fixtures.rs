@@ -0,0 +1,284 @@
+// A believable dataset for a local validator: campaigns with varied names, goals and categories,
+// throwaway donors donating random amounts to them, and some campaigns finalized. Everything,
+// keypairs included, comes from the seed, so the same seed gives the same addresses and the same
+// manifest every time.
+//
+// The dataset is a list of steps, whoever runs it decides what funding a wallet and waiting mean:
+// `fund-cli seed` asks the faucet and watches the cluster clock, the tests transfer from the payer
+// and move the bank's clock.
+use program::{instruction, CampaignDetails, SOL_CURRENCY, MAX_CATEGORIES};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{keypair_from_seed, Keypair, Signer},
+    system_instruction,
+};
+
+// What the client allocates a campaign account with.
+pub const CAMPAIGN_SPACE: usize = 2_048;
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+// The program's floor on a donation.
+const MIN_DONATION: u64 = 10_000_000;
+// On top of their donations donors pay for their donation records and receipts.
+const DONOR_FEES: u64 = LAMPORTS_PER_SOL / 10;
+// Admins pay for the name record and the content of their campaign.
+const ADMIN_LAMPORTS: u64 = LAMPORTS_PER_SOL;
+// Campaigns that aren't finalized stay open this long.
+const OPEN_CAMPAIGN_SECS: i64 = 30 * 24 * 60 * 60;
+
+const CAUSES: [&str; 8] = [
+    "water wells",
+    "school books",
+    "community library",
+    "rooftop garden",
+    "animal shelter",
+    "food bank",
+    "solar panels",
+    "music lessons",
+];
+const PLACES: [&str; 6] = ["Riverside", "Old Town", "Hillcrest", "Harbor", "Northgate", "Maple Street"];
+
+pub struct SeedOptions {
+    pub campaigns: usize,
+    pub donations: usize,
+    pub seed: u64,
+    // How long after now the campaigns that get finalized end. Their donations have to land first.
+    pub finalize_after_secs: i64,
+}
+
+pub enum Step {
+    // Gives a throwaway wallet lamports.
+    Fund { wallet: Pubkey, lamports: u64 },
+    // Instructions sent in one transaction paid by the payer, signed by it and `signers`.
+    Send { instructions: Vec<Instruction>, signers: Vec<Keypair> },
+    // The steps after it need the cluster clock at this unix timestamp.
+    WaitUntil(i64),
+}
+
+pub struct Dataset {
+    pub steps: Vec<Step>,
+    pub manifest: Manifest,
+}
+
+pub struct Manifest {
+    pub seed: u64,
+    pub program_id: Pubkey,
+    pub campaigns: Vec<CampaignEntry>,
+    pub donors: Vec<Pubkey>,
+}
+
+pub struct CampaignEntry {
+    pub address: Pubkey,
+    pub admin: Pubkey,
+    pub name: String,
+    pub goal: u64,
+    pub category: u8,
+    pub donated: u64,
+    pub donations: u64,
+    // "active", or "successful" / "failed" for the finalized ones.
+    pub state: &'static str,
+}
+
+// xorshift64, the dataset only needs to be reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // xorshift never leaves 0, spread the seed so small seeds don't start close to each other.
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // A number in `low..=high`.
+    fn between(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+
+    fn keypair(&mut self) -> Keypair {
+        let mut seed = [0u8; 32];
+        for chunk in seed.chunks_mut(8) {
+            chunk.copy_from_slice(&self.next().to_le_bytes());
+        }
+        keypair_from_seed(&seed).expect("32 bytes is a valid keypair seed")
+    }
+}
+
+// Builds the dataset for a cluster whose clock is at `now`. `payer` pays for the campaign accounts,
+// `campaign_lamports` each, and `treasury` is the platform treasury from the config.
+pub fn dataset(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    treasury: &Pubkey,
+    campaign_lamports: u64,
+    options: &SeedOptions,
+    now: i64,
+) -> Dataset {
+    let mut rng = Rng::new(options.seed);
+    let mut funding = Vec::new();
+    let mut creates = Vec::new();
+    let mut campaigns = Vec::new();
+    // About one in three campaigns gets finalized, the first one always so there is one.
+    let mut finalized = Vec::new();
+
+    for i in 0..options.campaigns {
+        let admin = rng.keypair();
+        let campaign = rng.keypair();
+        let name = format!(
+            "{} {} #{}",
+            PLACES[rng.between(0, PLACES.len() as u64 - 1) as usize],
+            CAUSES[rng.between(0, CAUSES.len() as u64 - 1) as usize],
+            i + 1
+        );
+        let goal = rng.between(1, 40) * LAMPORTS_PER_SOL / 4;
+        let category = rng.between(0, MAX_CATEGORIES as u64 - 1) as u8;
+        let finalize = i == 0 || rng.next().is_multiple_of(3);
+        let end_timestamp = if finalize {
+            now + options.finalize_after_secs
+        } else {
+            now + OPEN_CAMPAIGN_SECS
+        };
+        let settings = CampaignDetails {
+            admin: admin.pubkey(),
+            name: name.clone(),
+            image_link: format!("https://picsum.photos/seed/{}/640/360", campaign.pubkey()),
+            goal,
+            end_timestamp,
+            category,
+            currency: SOL_CURRENCY,
+            ..Default::default()
+        };
+        let description = format!("Raising {} SOL for the {}.", goal as f64 / LAMPORTS_PER_SOL as f64, name);
+
+        funding.push(Step::Fund { wallet: admin.pubkey(), lamports: ADMIN_LAMPORTS });
+        creates.push(Step::Send {
+            instructions: vec![
+                system_instruction::create_account(
+                    payer,
+                    &campaign.pubkey(),
+                    campaign_lamports,
+                    CAMPAIGN_SPACE as u64,
+                    program_id,
+                ),
+                instruction::create_campaign(program_id, &campaign.pubkey(), treasury, settings, &description),
+            ],
+            signers: vec![admin.insecure_clone(), campaign.insecure_clone()],
+        });
+        if finalize {
+            finalized.push(i);
+        }
+        campaigns.push(CampaignEntry {
+            address: campaign.pubkey(),
+            admin: admin.pubkey(),
+            name,
+            goal,
+            category,
+            donated: 0,
+            donations: 0,
+            state: "active",
+        });
+    }
+
+    let donors: Vec<Keypair> = (0..options.donations.div_ceil(5)).map(|_| rng.keypair()).collect();
+    let mut donor_totals = vec![0u64; donors.len()];
+    // The campaigns that get finalized take their donations first, they end before the others.
+    let mut early = Vec::new();
+    let mut late = Vec::new();
+    if !campaigns.is_empty() {
+        for _ in 0..options.donations {
+            let donor = rng.between(0, donors.len() as u64 - 1) as usize;
+            let target = rng.between(0, campaigns.len() as u64 - 1) as usize;
+            let amount = rng.between(MIN_DONATION / 1_000_000, 2_000) * 1_000_000;
+            let campaign = &mut campaigns[target];
+            let donate = instruction::donate_direct(
+                program_id,
+                &campaign.address,
+                &donors[donor].pubkey(),
+                treasury,
+                campaign.donations,
+                amount,
+                false,
+                0,
+                0,
+            );
+            campaign.donated += amount;
+            campaign.donations += 1;
+            donor_totals[donor] += amount;
+            let step = Step::Send { instructions: vec![donate], signers: vec![donors[donor].insecure_clone()] };
+            if finalized.contains(&target) {
+                early.push(step);
+            } else {
+                late.push(step);
+            }
+        }
+    }
+    for (donor, total) in donors.iter().zip(donor_totals.iter()) {
+        funding.push(Step::Fund { wallet: donor.pubkey(), lamports: total + DONOR_FEES });
+    }
+
+    let mut steps = funding;
+    steps.extend(creates);
+    steps.extend(early);
+    steps.extend(late);
+    if !finalized.is_empty() {
+        steps.push(Step::WaitUntil(now + options.finalize_after_secs));
+        for i in finalized {
+            let campaign = &mut campaigns[i];
+            campaign.state = if campaign.donated >= campaign.goal { "successful" } else { "failed" };
+            steps.push(Step::Send {
+                instructions: vec![instruction::finalize_campaign(program_id, &campaign.address)],
+                signers: Vec::new(),
+            });
+        }
+    }
+
+    Dataset {
+        steps,
+        manifest: Manifest {
+            seed: options.seed,
+            program_id: *program_id,
+            campaigns,
+            donors: donors.iter().map(|donor| donor.pubkey()).collect(),
+        },
+    }
+}
+
+impl Manifest {
+    pub fn to_json(&self) -> String {
+        let campaigns: Vec<String> = self
+            .campaigns
+            .iter()
+            .map(|campaign| {
+                format!(
+                    "    {{\"address\": \"{}\", \"admin\": \"{}\", \"name\": \"{}\", \"goal\": {}, \"category\": {}, \"donated\": {}, \"donations\": {}, \"state\": \"{}\"}}",
+                    campaign.address,
+                    campaign.admin,
+                    json_escape(&campaign.name),
+                    campaign.goal,
+                    campaign.category,
+                    campaign.donated,
+                    campaign.donations,
+                    campaign.state
+                )
+            })
+            .collect();
+        let donors: Vec<String> = self.donors.iter().map(|donor| format!("    \"{}\"", donor)).collect();
+        format!(
+            "{{\n  \"seed\": {},\n  \"program_id\": \"{}\",\n  \"campaigns\": [\n{}\n  ],\n  \"donors\": [\n{}\n  ]\n}}",
+            self.seed,
+            self.program_id,
+            campaigns.join(",\n"),
+            donors.join(",\n")
+        )
+    }
+}
+
+fn json_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
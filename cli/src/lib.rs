@@ -0,0 +1,2 @@
+// Tools for developing against the program off chain, see `fixtures`.
+pub mod fixtures;
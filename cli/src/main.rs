@@ -0,0 +1,146 @@
+// fund-cli seed: fills a local validator with the dataset of `fixtures` and prints its manifest.
+//
+//   fund-cli seed --program-id <ID> [--campaigns 10] [--donations 50] [--seed 0]
+//                 [--url http://127.0.0.1:8899] [--keypair ~/.config/solana/id.json] [--finalize-after 90]
+//
+// The keypair pays for the campaign accounts, donors and admins are funded from the faucet. When
+// the program has no config yet it is initialized with the keypair as authority and treasury.
+use borsh::BorshDeserialize;
+use fund_cli::fixtures::{dataset, SeedOptions, Step, CAMPAIGN_SPACE};
+use program::{instruction, seeds::find_config_address, PlatformConfig};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    account::from_account,
+    clock::Clock,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+    sysvar,
+    transaction::Transaction,
+};
+use std::{env, error::Error, process, str::FromStr, thread, time::Duration};
+
+// Kept in a campaign on top of its rent exemption, see the program's `campaign_min_balance`.
+const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+
+const USAGE: &str = "usage: fund-cli seed --program-id <ID> [--campaigns N] [--donations N] [--seed N] \
+                     [--url URL] [--keypair PATH] [--finalize-after SECS]";
+
+struct Args {
+    program_id: Pubkey,
+    url: String,
+    keypair: String,
+    options: SeedOptions,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, Box<dyn Error>> {
+    if args.next().as_deref() != Some("seed") {
+        return Err(USAGE.into());
+    }
+    let home = env::var("HOME").unwrap_or_default();
+    let mut program_id = None;
+    let mut parsed = Args {
+        program_id: Pubkey::default(),
+        url: "http://127.0.0.1:8899".to_string(),
+        keypair: format!("{}/.config/solana/id.json", home),
+        options: SeedOptions { campaigns: 10, donations: 50, seed: 0, finalize_after_secs: 90 },
+    };
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE))?;
+        match flag.as_str() {
+            "--program-id" => program_id = Some(Pubkey::from_str(&value)?),
+            "--campaigns" => parsed.options.campaigns = value.parse()?,
+            "--donations" => parsed.options.donations = value.parse()?,
+            "--seed" => parsed.options.seed = value.parse()?,
+            "--url" => parsed.url = value,
+            "--keypair" => parsed.keypair = value,
+            "--finalize-after" => parsed.options.finalize_after_secs = value.parse()?,
+            _ => return Err(format!("unknown flag {}\n{}", flag, USAGE).into()),
+        }
+    }
+    parsed.program_id = program_id.ok_or(USAGE)?;
+    Ok(parsed)
+}
+
+fn send(rpc: &RpcClient, payer: &Keypair, step_instructions: &[solana_sdk::instruction::Instruction], signers: &[Keypair]) -> Result<(), Box<dyn Error>> {
+    let mut all_signers: Vec<&Keypair> = vec![payer];
+    all_signers.extend(signers.iter());
+    let transaction = Transaction::new_signed_with_payer(
+        step_instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        rpc.get_latest_blockhash()?,
+    );
+    rpc.send_and_confirm_transaction(&transaction)?;
+    Ok(())
+}
+
+fn cluster_time(rpc: &RpcClient) -> Result<i64, Box<dyn Error>> {
+    let clock: Clock = from_account(&rpc.get_account(&sysvar::clock::id())?).ok_or("can't read the clock sysvar")?;
+    Ok(clock.unix_timestamp)
+}
+
+// The config of the program, initialized with `payer` as authority and treasury when missing.
+fn load_or_initialize_config(rpc: &RpcClient, payer: &Keypair, program_id: &Pubkey) -> Result<PlatformConfig, Box<dyn Error>> {
+    let (config, _) = find_config_address(program_id);
+    if rpc.get_account(&config).is_err() {
+        let initialize = instruction::initialize_config(
+            program_id,
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &payer.pubkey(),
+            0,
+            0,
+            0,
+            false,
+            0,
+            Vec::new(),
+        );
+        send(rpc, payer, &[initialize], &[])?;
+    }
+    let data = rpc.get_account_data(&config)?;
+    Ok(PlatformConfig::deserialize(&mut &data[..])?)
+}
+
+fn seed(args: Args) -> Result<(), Box<dyn Error>> {
+    let rpc = RpcClient::new_with_commitment(args.url.clone(), CommitmentConfig::confirmed());
+    let payer = read_keypair_file(&args.keypair).map_err(|err| format!("can't read {}: {}", args.keypair, err))?;
+    let config = load_or_initialize_config(&rpc, &payer, &args.program_id)?;
+    let campaign_lamports = rpc.get_minimum_balance_for_rent_exemption(CAMPAIGN_SPACE)? + RENT_BUFFER_LAMPORTS;
+
+    let dataset = dataset(
+        &args.program_id,
+        &payer.pubkey(),
+        &config.treasury,
+        campaign_lamports,
+        &args.options,
+        cluster_time(&rpc)?,
+    );
+    for step in dataset.steps {
+        match step {
+            Step::Fund { wallet, lamports } => {
+                let signature = rpc.request_airdrop(&wallet, lamports)?;
+                while !rpc.confirm_transaction(&signature)? {
+                    thread::sleep(Duration::from_millis(200));
+                }
+            }
+            Step::Send { instructions, signers } => send(&rpc, &payer, &instructions, &signers)?,
+            Step::WaitUntil(unix_timestamp) => {
+                eprintln!("waiting for the campaigns to end to finalize them...");
+                while cluster_time(&rpc)? < unix_timestamp {
+                    thread::sleep(Duration::from_secs(1));
+                }
+            }
+        }
+    }
+    println!("{}", dataset.manifest.to_json());
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args(env::args().skip(1)).and_then(seed);
+    if let Err(err) = result {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,111 @@
+// Runs a small seed dataset against program-test and checks the manifest matches what landed.
+use borsh::BorshDeserialize;
+use fund_cli::fixtures::{dataset, Dataset, SeedOptions, Step, CAMPAIGN_SPACE};
+use program::{instruction, CampaignDetails, CampaignState};
+use solana_program_test::{processor, tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    clock::Clock,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+
+fn options(seed: u64) -> SeedOptions {
+    SeedOptions { campaigns: 3, donations: 8, seed, finalize_after_secs: 90 }
+}
+
+fn small_dataset(seed: u64, treasury: &Pubkey) -> Dataset {
+    let program_id = Pubkey::new_from_array([0xf0; 32]);
+    dataset(&program_id, &Pubkey::new_from_array([1; 32]), treasury, 1, &options(seed), 1_700_000_000)
+}
+
+async fn send(context: &mut ProgramTestContext, instructions: &[Instruction], signers: &[&Keypair]) {
+    let payer = context.payer.insecure_clone();
+    let mut all_signers = vec![&payer];
+    all_signers.extend_from_slice(signers);
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+    context.banks_client.process_transaction(transaction).await.unwrap();
+}
+
+#[test]
+fn same_seed_same_dataset() {
+    let treasury = Pubkey::new_unique();
+    let first = small_dataset(7, &treasury).manifest.to_json();
+    assert_eq!(first, small_dataset(7, &treasury).manifest.to_json());
+    assert_ne!(first, small_dataset(8, &treasury).manifest.to_json());
+}
+
+#[tokio::test]
+async fn seeds_program_test() {
+    let program_id = Pubkey::new_from_array([0xf0; 32]);
+    let mut context = ProgramTest::new("program", program_id, processor!(program::process_instruction))
+        .start_with_context()
+        .await;
+    let payer = context.payer.insecure_clone();
+    let initialize = instruction::initialize_config(
+        &program_id,
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &payer.pubkey(),
+        0,
+        0,
+        0,
+        false,
+        0,
+        Vec::new(),
+    );
+    send(&mut context, &[initialize], &[]).await;
+
+    let rent = context.banks_client.get_rent().await.unwrap();
+    let mut clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    let dataset = dataset(
+        &program_id,
+        &payer.pubkey(),
+        &payer.pubkey(),
+        rent.minimum_balance(CAMPAIGN_SPACE) + RENT_BUFFER_LAMPORTS,
+        &options(42),
+        clock.unix_timestamp,
+    );
+    for step in dataset.steps {
+        match step {
+            Step::Fund { wallet, lamports } => {
+                send(&mut context, &[system_instruction::transfer(&payer.pubkey(), &wallet, lamports)], &[]).await
+            }
+            Step::Send { instructions, signers } => {
+                let signers: Vec<&Keypair> = signers.iter().collect();
+                send(&mut context, &instructions, &signers).await
+            }
+            Step::WaitUntil(unix_timestamp) => {
+                clock.unix_timestamp = unix_timestamp;
+                context.set_sysvar(&clock);
+            }
+        }
+    }
+
+    let manifest = dataset.manifest;
+    assert_eq!(manifest.campaigns.len(), 3);
+    assert_eq!(manifest.campaigns.iter().map(|campaign| campaign.donations).sum::<u64>(), 8);
+    for entry in manifest.campaigns.iter() {
+        let account = context.banks_client.get_account(entry.address).await.unwrap().unwrap();
+        let campaign = CampaignDetails::deserialize(&mut &account.data[..]).unwrap();
+        assert_eq!(campaign.admin, entry.admin);
+        assert_eq!(campaign.name, entry.name);
+        assert_eq!(campaign.goal, entry.goal);
+        assert_eq!(campaign.category, entry.category);
+        assert_eq!(campaign.amount_donated, entry.donated);
+        let state = match campaign.state {
+            CampaignState::Active => "active",
+            CampaignState::Successful => "successful",
+            CampaignState::Failed => "failed",
+            state => panic!("unexpected state {:?}", state),
+        };
+        assert_eq!(state, entry.state);
+    }
+    assert!(manifest.campaigns.iter().any(|campaign| campaign.state != "active"));
+    assert!(manifest.to_json().contains(&manifest.campaigns[0].address.to_string()));
+}
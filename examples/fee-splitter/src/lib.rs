@@ -0,0 +1,195 @@
+// An example of another program donating to a campaign through CPI: a payment splitter sending
+// part of every payment to a campaign, like "donate 1% of every swap to campaign X".
+//
+// It depends on the fundraising program with `no-entrypoint`, builds the donation with
+// `program::instruction::donate_direct` and invokes it with the accounts it was given. The
+// builder lists the accounts in the order donate_direct reads them, so this breaks as soon as
+// that order or who has to sign changes.
+//
+// 0 for pay: the payer sends `amount`, `campaign_bps` of it goes to the campaign as the payer's
+//   donation and the rest to the merchant. The payer's signature carries through the CPI.
+// 1 for donate_from_vault: the splitter donates from its vault PDA, signing for it with
+//   `invoke_signed`. The vault is a plain system account anyone can fund.
+//
+// Accounts: the payer (or the vault), the merchant (pay only), the fundraising program, then the
+// accounts donate_direct takes after the donor: campaign, donation record, system program,
+// receipt, blocklist, config, treasury and global stats.
+use borsh::{BorshDeserialize, BorshSerialize};
+use program::CampaignDetails;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+};
+
+pub const VAULT_SEED: &[u8] = b"vault";
+
+pub fn find_vault_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED], program_id)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct PayRequest {
+    pub amount: u64,
+    pub campaign_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct DonateFromVaultRequest {
+    pub amount: u64,
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    match instruction_data.split_first() {
+        Some((0, request)) => pay(accounts, request),
+        Some((1, request)) => donate_from_vault(program_id, accounts, request),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn pay(accounts: &[AccountInfo], request: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let payer = next_account_info(accounts_iter)?;
+    let merchant = next_account_info(accounts_iter)?;
+    let fund_program = next_account_info(accounts_iter)?;
+    let donate_accounts = accounts_iter.as_slice();
+
+    let request = PayRequest::try_from_slice(request).map_err(|_| ProgramError::InvalidInstructionData)?;
+    if request.campaign_bps > 10_000 {
+        msg!("campaign_bps can't be more then 10000");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let donation = (request.amount as u128 * request.campaign_bps as u128 / 10_000) as u64;
+
+    invoke(
+        &system_instruction::transfer(payer.key, merchant.key, request.amount - donation),
+        &[payer.clone(), merchant.clone()],
+    )?;
+    if donation > 0 {
+        donate(fund_program, payer, donate_accounts, donation, &[])?;
+    }
+    Ok(())
+}
+
+fn donate_from_vault(program_id: &Pubkey, accounts: &[AccountInfo], request: &[u8]) -> ProgramResult {
+    let accounts_iter = &mut accounts.iter();
+    let vault = next_account_info(accounts_iter)?;
+    let fund_program = next_account_info(accounts_iter)?;
+    let donate_accounts = accounts_iter.as_slice();
+
+    let request = DonateFromVaultRequest::try_from_slice(request).map_err(|_| ProgramError::InvalidInstructionData)?;
+    let (vault_key, bump) = find_vault_address(program_id);
+    if vault_key != *vault.key {
+        msg!("vault isn't the vault PDA of the splitter");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    donate(fund_program, vault, donate_accounts, request.amount, &[&[VAULT_SEED, &[bump]]])
+}
+
+// Donates `amount` from `donor` with donate_direct, signing with `signer_seeds` when the donor is a PDA.
+fn donate<'a>(
+    fund_program: &AccountInfo<'a>,
+    donor: &AccountInfo<'a>,
+    donate_accounts: &[AccountInfo<'a>],
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let campaign = donate_accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let treasury = donate_accounts.get(6).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    // The receipt of the donation is derived from the campaign's sequence.
+    let sequence = CampaignDetails::deserialize(&mut &campaign.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?
+        .donation_sequence;
+
+    let instruction = program::instruction::donate_direct(
+        fund_program.key,
+        campaign.key,
+        donor.key,
+        treasury.key,
+        sequence,
+        amount,
+        false,
+        0,
+        0,
+    );
+    let mut account_infos = vec![donor.clone(), fund_program.clone()];
+    account_infos.extend_from_slice(donate_accounts);
+    invoke_signed(&instruction, &account_infos, signer_seeds)
+}
+
+// Builders for clients of the splitter, the fundraising accounts are derived like its own builders do.
+pub mod instruction {
+    use super::*;
+    use program::seeds::*;
+    use solana_program::{
+        instruction::{AccountMeta, Instruction},
+        system_program,
+    };
+
+    fn donate_accounts(
+        fund_program: &Pubkey,
+        campaign: &Pubkey,
+        donor: &Pubkey,
+        treasury: &Pubkey,
+        sequence: u64,
+    ) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(*fund_program, false),
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(find_donation_record_address(fund_program, campaign, donor).0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(find_receipt_address(fund_program, campaign, sequence).0, false),
+            AccountMeta::new_readonly(find_blocklist_address(fund_program, campaign, donor).0, false),
+            AccountMeta::new_readonly(find_config_address(fund_program).0, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(find_global_stats_address(fund_program).0, false),
+        ]
+    }
+
+    // `sequence` is the campaign's `donation_sequence`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn pay(
+        program_id: &Pubkey,
+        fund_program: &Pubkey,
+        payer: &Pubkey,
+        merchant: &Pubkey,
+        campaign: &Pubkey,
+        treasury: &Pubkey,
+        sequence: u64,
+        amount: u64,
+        campaign_bps: u16,
+    ) -> Instruction {
+        let mut accounts = vec![AccountMeta::new(*payer, true), AccountMeta::new(*merchant, false)];
+        accounts.extend(donate_accounts(fund_program, campaign, payer, treasury, sequence));
+        let mut data = vec![0];
+        data.extend(PayRequest { amount, campaign_bps }.try_to_vec().unwrap());
+        Instruction { program_id: *program_id, accounts, data }
+    }
+
+    pub fn donate_from_vault(
+        program_id: &Pubkey,
+        fund_program: &Pubkey,
+        campaign: &Pubkey,
+        treasury: &Pubkey,
+        sequence: u64,
+        amount: u64,
+    ) -> Instruction {
+        let (vault, _) = find_vault_address(program_id);
+        let mut accounts = vec![AccountMeta::new(vault, false)];
+        accounts.extend(donate_accounts(fund_program, campaign, &vault, treasury, sequence));
+        let mut data = vec![1];
+        data.extend(DonateFromVaultRequest { amount }.try_to_vec().unwrap());
+        Instruction { program_id: *program_id, accounts, data }
+    }
+}
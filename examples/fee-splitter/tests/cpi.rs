@@ -0,0 +1,163 @@
+// Runs the fee splitter and the fundraising program in one bank: donations through CPI, signed by
+// the payer or by the splitter's vault PDA, land in the campaign like direct ones.
+use borsh::BorshDeserialize;
+use fee_splitter::find_vault_address;
+use program::{instruction, CampaignDetails, DonationRecord, SOL_CURRENCY};
+use solana_program_test::{processor, tokio, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+const CAMPAIGN_SPACE: usize = 2_048;
+const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+
+struct Env {
+    context: ProgramTestContext,
+    fund_program: Pubkey,
+    splitter: Pubkey,
+    treasury: Pubkey,
+    campaign: Pubkey,
+}
+
+impl Env {
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) {
+        let payer = self.context.payer.insecure_clone();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash);
+        self.context.banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    async fn fund(&mut self, wallet: &Pubkey, lamports: u64) {
+        let payer = self.context.payer.pubkey();
+        self.process(&[system_instruction::transfer(&payer, wallet, lamports)], &[]).await;
+    }
+
+    async fn balance(&mut self, key: &Pubkey) -> u64 {
+        self.context.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    async fn campaign(&mut self) -> CampaignDetails {
+        let account = self.context.banks_client.get_account(self.campaign).await.unwrap().unwrap();
+        CampaignDetails::deserialize(&mut &account.data[..]).unwrap()
+    }
+
+    async fn record(&mut self, donor: &Pubkey) -> DonationRecord {
+        let (record, _) = program::seeds::find_donation_record_address(&self.fund_program, &self.campaign, donor);
+        let account = self.context.banks_client.get_account(record).await.unwrap().unwrap();
+        DonationRecord::deserialize(&mut &account.data[..]).unwrap()
+    }
+}
+
+// Both programs deployed, the config initialized without fees and one open campaign.
+async fn start() -> Env {
+    let fund_program = Pubkey::new_unique();
+    let splitter = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("program", fund_program, processor!(program::process_instruction));
+    program_test.add_program("fee_splitter", splitter, processor!(fee_splitter::process_instruction));
+    let context = program_test.start_with_context().await;
+    let mut env = Env { context, fund_program, splitter, treasury: Pubkey::new_unique(), campaign: Pubkey::default() };
+
+    let authority = env.context.payer.insecure_clone();
+    let treasury = env.treasury;
+    env.fund(&treasury, LAMPORTS_PER_SOL).await;
+    let initialize = instruction::initialize_config(
+        &fund_program,
+        &authority.pubkey(),
+        &authority.pubkey(),
+        &treasury,
+        0,
+        0,
+        0,
+        false,
+        0,
+        Vec::new(),
+    );
+    env.process(&[initialize], &[]).await;
+
+    let admin = Keypair::new();
+    env.fund(&admin.pubkey(), LAMPORTS_PER_SOL).await;
+    let campaign = Keypair::new();
+    let rent = env.context.banks_client.get_rent().await.unwrap();
+    let clock: solana_sdk::clock::Clock = env.context.banks_client.get_sysvar().await.unwrap();
+    let settings = CampaignDetails {
+        admin: admin.pubkey(),
+        name: "Splitter campaign".to_string(),
+        goal: 10 * LAMPORTS_PER_SOL,
+        end_timestamp: clock.unix_timestamp + 86_400,
+        currency: SOL_CURRENCY,
+        ..Default::default()
+    };
+    let create = [
+        system_instruction::create_account(
+            &authority.pubkey(),
+            &campaign.pubkey(),
+            rent.minimum_balance(CAMPAIGN_SPACE) + RENT_BUFFER_LAMPORTS,
+            CAMPAIGN_SPACE as u64,
+            &fund_program,
+        ),
+        instruction::create_campaign(&fund_program, &campaign.pubkey(), &treasury, settings, ""),
+    ];
+    env.process(&create, &[&admin, &campaign]).await;
+    env.campaign = campaign.pubkey();
+    env
+}
+
+#[tokio::test]
+async fn payment_donates_its_share_as_the_payer() {
+    let mut env = start().await;
+    let payer = Keypair::new();
+    let merchant = Pubkey::new_unique();
+    env.fund(&payer.pubkey(), 3 * LAMPORTS_PER_SOL).await;
+    env.fund(&merchant, LAMPORTS_PER_SOL).await;
+
+    let sequence = env.campaign().await.donation_sequence;
+    let pay = fee_splitter::instruction::pay(
+        &env.splitter,
+        &env.fund_program,
+        &payer.pubkey(),
+        &merchant,
+        &env.campaign,
+        &env.treasury,
+        sequence,
+        2 * LAMPORTS_PER_SOL,
+        100,
+    );
+    env.process(&[pay], &[&payer]).await;
+
+    // 1% of 2 SOL to the campaign, the rest to the merchant.
+    assert_eq!(env.balance(&merchant).await, LAMPORTS_PER_SOL + 2 * LAMPORTS_PER_SOL - 20_000_000);
+    assert_eq!(env.campaign().await.amount_donated, 20_000_000);
+    let record = env.record(&payer.pubkey()).await;
+    assert_eq!(record.donor, payer.pubkey());
+    assert_eq!(record.amount, 20_000_000);
+}
+
+#[tokio::test]
+async fn vault_donates_with_the_splitters_signature() {
+    let mut env = start().await;
+    let (vault, _) = find_vault_address(&env.splitter);
+    env.fund(&vault, LAMPORTS_PER_SOL).await;
+
+    let sequence = env.campaign().await.donation_sequence;
+    let donate = fee_splitter::instruction::donate_from_vault(
+        &env.splitter,
+        &env.fund_program,
+        &env.campaign,
+        &env.treasury,
+        sequence,
+        LAMPORTS_PER_SOL / 10,
+    );
+    env.process(&[donate], &[]).await;
+
+    assert_eq!(env.campaign().await.amount_donated, LAMPORTS_PER_SOL / 10);
+    let record = env.record(&vault).await;
+    assert_eq!(record.donor, vault);
+    assert_eq!(record.amount, LAMPORTS_PER_SOL / 10);
+}
@@ -0,0 +1,48 @@
+// Checks on how an instruction is being called, using the instructions sysvar.
+//
+// They are opt-in: an instruction is only guarded when it calls `check_caller` itself, so
+// donating through CPI (a DAO treasury donating with `donate_direct`) keeps working.
+// The guarded instructions take the instructions sysvar right after their fixed accounts:
+//
+// - `withdraw_many` (33): takes the platform fee from many campaigns in one go.
+// - `donate_split` (32): splits one donation over many campaigns.
+//
+// Both run when they are a top-level instruction of the transaction, or when a program on the
+// config's `cpi_callers` allowlist invokes them. Every other instruction is unguarded.
+use crate::logging::Instruction;
+use solana_program::{
+    account_info::AccountInfo,
+    instruction::{get_stack_height, TRANSACTION_LEVEL_STACK_HEIGHT},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+// The program behind an inner instruction is the program of the top-level instruction
+// that is running. Deeper call chains report that outermost program, so allowlisting a
+// program also allows whatever it calls through.
+fn top_level_program(instructions_sysvar: &AccountInfo) -> Result<Pubkey, ProgramError> {
+    let index = load_current_index_checked(instructions_sysvar)?;
+    let instruction = load_instruction_at_checked(index as usize, instructions_sysvar)?;
+    Ok(instruction.program_id)
+}
+
+// Fails unless the instruction runs at the top level of the transaction, or the transaction's
+// current top-level instruction belongs to one of `allowed_callers`.
+pub fn check_caller(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    instructions_sysvar: &AccountInfo,
+    allowed_callers: &[Pubkey],
+) -> Result<(), ProgramError> {
+    let caller = top_level_program(instructions_sysvar)?;
+    if get_stack_height() == TRANSACTION_LEVEL_STACK_HEIGHT && caller == *program_id {
+        return Ok(());
+    }
+    if allowed_callers.contains(&caller) {
+        return Ok(());
+    }
+    log_event!(instruction, "{} isn't allowed to invoke this instruction", caller);
+    Err(ProgramError::IncorrectProgramId)
+}
@@ -0,0 +1,1048 @@
+// Builders for the instructions of the program, for Rust clients, tests and programs invoking us
+// through CPI (with the `no-entrypoint` feature). Each one puts the tag in front of the Borsh
+// request and lists the accounts in the order the handler reads them, deriving the PDAs with the
+// `seeds` module. Optional accounts a handler can take are left out unless the builder has a
+// parameter for them.
+use crate::seeds::*;
+use crate::*;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    sysvar,
+};
+
+fn instruction_data<T: BorshSerialize>(tag: u8, request: &T) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend(request.try_to_vec().expect("Error serializing instruction"));
+    data
+}
+
+// 0, the campaign account has to be created by the client first, owned by the program.
+pub fn create_campaign(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    treasury: &Pubkey,
+    settings: CampaignDetails,
+    description: &str,
+) -> Instruction {
+    let admin = settings.admin;
+    let (config, _) = find_config_address(program_id);
+    let (name_record, _) = find_campaign_name_address(program_id, &admin, &campaign_name_hash(&settings.name));
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let (content, _) = find_campaign_content_address(program_id, campaign);
+    let request = CreateCampaignRequest { campaign: settings, description: description.to_string() };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(admin, true),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(name_record, false),
+            AccountMeta::new(global_stats, false),
+            AccountMeta::new(content, false),
+        ],
+        data: instruction_data(0, &request),
+    }
+}
+
+// 1, to the admin.
+pub fn withdraw(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, treasury: &Pubkey, amount: u64) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let (insurance_pool, _) = find_insurance_pool_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(insurance_pool, false),
+        ],
+        data: instruction_data(1, &WithdrawRequest { amount }),
+    }
+}
+
+// Sends a withdraw built by `withdraw` to `recipient` instead of the admin, it has to be one of the
+// campaign's withdraw recipients.
+pub fn with_recipient(mut withdraw: Instruction, recipient: &Pubkey) -> Instruction {
+    withdraw.accounts.push(AccountMeta::new(*recipient, false));
+    withdraw
+}
+
+// 2, the legacy staging account path. `sequence` is the campaign's `donation_sequence`, the
+// receipt of this donation derives from it. The global stats come after the blocklist, clients
+// built before the category stats counted every donation path have to add it.
+#[allow(clippy::too_many_arguments)]
+pub fn donate(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    staging: &Pubkey,
+    donor: &Pubkey,
+    sequence: u64,
+    amount: u64,
+    close_after: bool,
+    seed: &str,
+) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (config, _) = find_config_address(program_id);
+    let (receipt, _) = find_receipt_address(program_id, campaign, sequence);
+    let (blocklist, _) = find_blocklist_address(program_id, campaign, donor);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let request = DonateRequest { amount, close_after, seed: seed.to_string() };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*staging, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new_readonly(blocklist, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: instruction_data(2, &request),
+    }
+}
+
+// 3, the caller becomes the platform authority.
+#[allow(clippy::too_many_arguments)]
+pub fn initialize_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    dispute_resolver: &Pubkey,
+    treasury: &Pubkey,
+    fee_bps: u16,
+    insurance_bps: u16,
+    creation_fee: u64,
+    refund_fees: bool,
+    refund_window_secs: u64,
+    fee_tiers: Vec<FeeTier>,
+) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let (insurance_pool, _) = find_insurance_pool_address(program_id);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let request = InitializeConfigRequest {
+        dispute_resolver: *dispute_resolver,
+        treasury: *treasury,
+        fee_bps,
+        insurance_bps,
+        creation_fee,
+        refund_fees,
+        refund_window_secs,
+        fee_tiers,
+    };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(insurance_pool, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: instruction_data(3, &request),
+    }
+}
+
+// 4, signed by the dispute resolver, blocks withdraws and optionally donations.
+pub fn open_dispute(program_id: &Pubkey, campaign: &Pubkey, resolver: &Pubkey, pause_donations: bool) -> Instruction {
+    dispute_instruction(program_id, campaign, resolver, instruction_data(4, &OpenDisputeRequest { pause_donations }))
+}
+
+// 5, signed by the dispute resolver, lifts the dispute.
+pub fn resolve_dispute(program_id: &Pubkey, campaign: &Pubkey, resolver: &Pubkey) -> Instruction {
+    dispute_instruction(program_id, campaign, resolver, vec![5])
+}
+
+// 12, signed by the dispute resolver, fails a disputed campaign so its donors can claim refunds.
+pub fn liquidate_campaign(program_id: &Pubkey, campaign: &Pubkey, resolver: &Pubkey) -> Instruction {
+    dispute_instruction(program_id, campaign, resolver, vec![12])
+}
+
+// The dispute instructions take the same accounts.
+fn dispute_instruction(program_id: &Pubkey, campaign: &Pubkey, resolver: &Pubkey, data: Vec<u8>) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*resolver, true),
+        ],
+        data,
+    }
+}
+
+// 6, anyone can send it once the campaign ended. The global stats, counting successful campaigns
+// per category, are the third account since the category stats: clients sending only the campaign
+// and the config get NotEnoughAccountKeys.
+pub fn finalize_campaign(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: vec![6],
+    }
+}
+
+// 7, signed by the donor, or by anyone when the donor set a `refund_recipient`: the refund is then
+// paid to it and it has to be passed.
+pub fn claim_refund(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    donor: &Pubkey,
+    refund_recipient: Option<&Pubkey>,
+) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (refund_recipient_account, _) = find_refund_recipient_address(program_id, campaign, donor);
+    let mut accounts = vec![
+        AccountMeta::new(*campaign, false),
+        AccountMeta::new(donation_record, false),
+        AccountMeta::new(*donor, refund_recipient.is_none()),
+        AccountMeta::new_readonly(refund_recipient_account, false),
+    ];
+    if let Some(recipient) = refund_recipient {
+        accounts.push(AccountMeta::new(*recipient, false));
+    }
+    Instruction { program_id: *program_id, accounts, data: vec![7] }
+}
+
+// 8, the admin starts a new cycle of a finalized campaign.
+pub fn renew_campaign(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, new_goal: u64, new_end_timestamp: i64) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(config, false),
+        ],
+        data: instruction_data(8, &RenewCampaignRequest { new_goal, new_end_timestamp }),
+    }
+}
+
+// 9, the sponsor locks `amount` in the voucher of `code_hash`, the sha256 of the secret code.
+pub fn create_voucher(program_id: &Pubkey, sponsor: &Pubkey, code_hash: [u8; 32], amount: u64, expires_at: i64) -> Instruction {
+    let (voucher, _) = find_voucher_address(program_id, &code_hash);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(voucher, false),
+            AccountMeta::new(*sponsor, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(9, &CreateVoucherRequest { amount, code_hash, expires_at }),
+    }
+}
+
+// 10, donates the voucher of `code` to the campaign in the redeemer's name. `sequence` is the
+// campaign's `donation_sequence`. The global stats come last, after the config.
+pub fn redeem_voucher(program_id: &Pubkey, campaign: &Pubkey, redeemer: &Pubkey, sequence: u64, code: &[u8]) -> Instruction {
+    let (voucher, _) = find_voucher_address(program_id, &hash(code).to_bytes());
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, redeemer);
+    let (receipt, _) = find_receipt_address(program_id, campaign, sequence);
+    let (blocklist, _) = find_blocklist_address(program_id, campaign, redeemer);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(voucher, false),
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*redeemer, true),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new_readonly(blocklist, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(find_global_stats_address(program_id).0, false),
+        ],
+        data: instruction_data(10, &RedeemVoucherRequest { code: code.to_vec() }),
+    }
+}
+
+// 11, the sponsor closes a redeemed or expired voucher, getting back its rent and what is left in it.
+pub fn reclaim_voucher(program_id: &Pubkey, sponsor: &Pubkey, code_hash: [u8; 32]) -> Instruction {
+    let (voucher, _) = find_voucher_address(program_id, &code_hash);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(voucher, false), AccountMeta::new(*sponsor, true)],
+        data: vec![11],
+    }
+}
+
+// 13, signed by the platform authority, pays a donor of a liquidated campaign from the insurance pool.
+pub fn pay_insurance_claim(program_id: &Pubkey, authority: &Pubkey, campaign: &Pubkey, donor: &Pubkey, amount: u64) -> Instruction {
+    let (insurance_pool, _) = find_insurance_pool_address(program_id);
+    let (config, _) = find_config_address(program_id);
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(insurance_pool, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new(*donor, false),
+        ],
+        data: instruction_data(13, &PayInsuranceClaimRequest { amount }),
+    }
+}
+
+// 14, creates the sponsor account of the wallet.
+pub fn register_sponsor(program_id: &Pubkey, wallet: &Pubkey, name: &str) -> Instruction {
+    let (sponsor, _) = find_sponsor_address(program_id, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(sponsor, false),
+            AccountMeta::new(*wallet, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(14, &SponsorNameRequest { name: name.to_string() }),
+    }
+}
+
+// 15, signed by the sponsor's wallet.
+pub fn update_sponsor_name(program_id: &Pubkey, wallet: &Pubkey, name: &str) -> Instruction {
+    let (sponsor, _) = find_sponsor_address(program_id, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(sponsor, false), AccountMeta::new_readonly(*wallet, true)],
+        data: instruction_data(15, &SponsorNameRequest { name: name.to_string() }),
+    }
+}
+
+// 16, signed by the platform authority.
+pub fn flag_sponsor(program_id: &Pubkey, wallet: &Pubkey, authority: &Pubkey, flagged: bool) -> Instruction {
+    let (sponsor, _) = find_sponsor_address(program_id, wallet);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(sponsor, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: instruction_data(16, &FlagSponsorRequest { flagged }),
+    }
+}
+
+// 17, commits or tops up `amount` to match donations to the campaign 1:1.
+pub fn commit_match(program_id: &Pubkey, campaign: &Pubkey, wallet: &Pubkey, amount: u64) -> Instruction {
+    let (match_commitment, _) = find_match_address(program_id, campaign, wallet);
+    let (sponsor, _) = find_sponsor_address(program_id, wallet);
+    let request = CommitMatchRequest { amount, schedule: Vec::new() };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(match_commitment, false),
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(sponsor, false),
+            AccountMeta::new(*wallet, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(17, &request),
+    }
+}
+
+// 18, closes the commitment once the campaign is finalized.
+pub fn release_match(program_id: &Pubkey, campaign: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let (match_commitment, _) = find_match_address(program_id, campaign, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(match_commitment, false),
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*wallet, true),
+        ],
+        data: vec![18],
+    }
+}
+
+// Lets the sponsor of `wallet` match a donation built by `donate` or `donate_direct`.
+pub fn with_match(mut donation: Instruction, campaign: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let (match_commitment, _) = find_match_address(&donation.program_id, campaign, wallet);
+    let (sponsor, _) = find_sponsor_address(&donation.program_id, wallet);
+    donation.accounts.push(AccountMeta::new(match_commitment, false));
+    donation.accounts.push(AccountMeta::new(sponsor, false));
+    donation
+}
+
+// 19, the admin appends a message to the campaign's update feed, paying for it to grow.
+pub fn post_update(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, message: &str) -> Instruction {
+    let (update_feed, _) = find_update_feed_address(program_id, campaign);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(update_feed, false),
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(19, &PostUpdateRequest { message: message.to_string() }),
+    }
+}
+
+// 20, signed by the platform authority.
+pub fn set_fee_exempt(program_id: &Pubkey, campaign: &Pubkey, authority: &Pubkey, fee_exempt: bool) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: instruction_data(20, &SetFeeExemptRequest { fee_exempt }),
+    }
+}
+
+// 21, signed by the platform authority, who pays for the charity PDA of the wallet.
+pub fn add_charity(program_id: &Pubkey, wallet: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (charity, _) = find_charity_address(program_id, wallet);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(charity, false),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: vec![21],
+    }
+}
+
+// 22, signed by the platform authority, who gets the rent of the charity PDA back.
+pub fn remove_charity(program_id: &Pubkey, wallet: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (charity, _) = find_charity_address(program_id, wallet);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(charity, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*authority, true),
+        ],
+        data: vec![22],
+    }
+}
+
+// 23, anyone can send the fees reserved by a successful campaign to the treasury.
+pub fn collect_fees(program_id: &Pubkey, campaign: &Pubkey, treasury: &Pubkey) -> Instruction {
+    treasury_instruction(program_id, campaign, treasury, 23)
+}
+
+// 27, anyone can send what is left of a failed campaign to the treasury once the refund window closed.
+pub fn sweep_unclaimed_refunds(program_id: &Pubkey, campaign: &Pubkey, treasury: &Pubkey) -> Instruction {
+    treasury_instruction(program_id, campaign, treasury, 27)
+}
+
+// The instructions paying the treasury from a campaign take the same accounts.
+fn treasury_instruction(program_id: &Pubkey, campaign: &Pubkey, treasury: &Pubkey, tag: u8) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*treasury, false),
+        ],
+        data: vec![tag],
+    }
+}
+
+// 24, logs the program version.
+pub fn version(program_id: &Pubkey) -> Instruction {
+    Instruction { program_id: *program_id, accounts: vec![], data: vec![24] }
+}
+
+// 25, fails unless the campaign is active and not disputed or paused.
+pub fn health_check(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    campaign_query(program_id, campaign, 25)
+}
+
+// 26, what the admin could withdraw right now as return data.
+pub fn get_withdrawable(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    campaign_query(program_id, campaign, 26)
+}
+
+// 44, signed by the platform authority, closes the campaigns that are empty and ended with their
+// name and content PDAs. Each campaign comes with its creator and its `name_hash`.
+pub fn close_expired_campaigns(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    campaigns: &[(Pubkey, Pubkey, [u8; 32])],
+) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let mut accounts = vec![AccountMeta::new_readonly(config, false), AccountMeta::new_readonly(*authority, true)];
+    for (campaign, creator, name_hash) in campaigns {
+        accounts.push(AccountMeta::new(*campaign, false));
+        accounts.push(AccountMeta::new(*creator, false));
+        accounts.push(AccountMeta::new(find_campaign_name_address(program_id, creator, name_hash).0, false));
+        accounts.push(AccountMeta::new(find_campaign_content_address(program_id, campaign).0, false));
+    }
+    Instruction { program_id: *program_id, accounts, data: vec![44] }
+}
+
+// 50, see `get_campaign_summary`.
+pub fn get_campaign_summary(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    campaign_query(program_id, campaign, 50)
+}
+
+// 61, amount_donated and goal as return data.
+pub fn get_progress(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    campaign_query(program_id, campaign, 61)
+}
+
+// The read-only queries only take the campaign.
+fn campaign_query(program_id: &Pubkey, campaign: &Pubkey, tag: u8) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(*campaign, false)],
+        data: vec![tag],
+    }
+}
+
+// 28, the admin moves `amount` of their campaign into `partner`, as a donation of the campaign.
+pub fn redirect_funds(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, partner: &Pubkey, amount: u64) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, partner, campaign);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(*partner, false),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(global_stats, false),
+            AccountMeta::new_readonly(config, false),
+        ],
+        data: instruction_data(28, &RedirectFundsRequest { amount }),
+    }
+}
+
+// 29, signed by the platform authority.
+pub fn set_refund_root(program_id: &Pubkey, campaign: &Pubkey, authority: &Pubkey, root: [u8; 32]) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: instruction_data(29, &SetRefundRootRequest { root }),
+    }
+}
+
+// 30, `amount` is the donor's leaf in the refund tree. The donor's donation record is passed
+// whether it exists or not.
+pub fn claim_refund_with_proof(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    donor: &Pubkey,
+    amount: u64,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (refund_claim, _) = find_refund_claim_address(program_id, campaign, donor);
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(refund_claim, false),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(30, &ClaimRefundWithProofRequest { amount, proof }),
+    }
+}
+
+// 31, the admin sets the soft goal, 0 removes it.
+pub fn set_soft_goal(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, soft_goal: u64) -> Instruction {
+    admin_instruction(program_id, campaign, admin, instruction_data(31, &SetSoftGoalRequest { soft_goal }))
+}
+
+// 32, one donation split over several campaigns. Each target is a campaign, its
+// `donation_sequence` and the amount donated to it. The global stats come before the targets.
+pub fn donate_split(program_id: &Pubkey, donor: &Pubkey, targets: &[(Pubkey, u64, u64)]) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*donor, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new(global_stats, false),
+    ];
+    for (campaign, sequence, _) in targets {
+        accounts.push(AccountMeta::new(*campaign, false));
+        accounts.push(AccountMeta::new(find_donation_record_address(program_id, campaign, donor).0, false));
+        accounts.push(AccountMeta::new(find_receipt_address(program_id, campaign, *sequence).0, false));
+        accounts.push(AccountMeta::new_readonly(find_blocklist_address(program_id, campaign, donor).0, false));
+    }
+    let amounts = targets.iter().map(|(_, _, amount)| *amount).collect();
+    Instruction { program_id: *program_id, accounts, data: instruction_data(32, &DonateSplitRequest { amounts }) }
+}
+
+// 33, withdraws `amounts` from the campaigns of the admin in one go. Unless `strict`, a campaign
+// that can't be withdrawn from is skipped.
+pub fn withdraw_many(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    treasury: &Pubkey,
+    campaigns: &[Pubkey],
+    amounts: Vec<u64>,
+    strict: bool,
+) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let (insurance_pool, _) = find_insurance_pool_address(program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*admin, true),
+        AccountMeta::new_readonly(config, false),
+        AccountMeta::new(*treasury, false),
+        AccountMeta::new(insurance_pool, false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+    ];
+    accounts.extend(campaigns.iter().map(|campaign| AccountMeta::new(*campaign, false)));
+    Instruction { program_id: *program_id, accounts, data: instruction_data(33, &WithdrawManyRequest { amounts, strict }) }
+}
+
+// 34, `old_name` is the current name of the campaign, its name record is closed for the new one.
+pub fn rename_campaign(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, old_name: &str, name: &str) -> Instruction {
+    let (old_name_record, _) = find_campaign_name_address(program_id, admin, &campaign_name_hash(old_name));
+    let (new_name_record, _) = find_campaign_name_address(program_id, admin, &campaign_name_hash(name));
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new(old_name_record, false),
+            AccountMeta::new(new_name_record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(34, &RenameCampaignRequest { name: name.to_string() }),
+    }
+}
+
+// 35, straight from the donor's wallet. `sequence` is the campaign's `donation_sequence`.
+#[allow(clippy::too_many_arguments)]
+pub fn donate_direct(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    donor: &Pubkey,
+    treasury: &Pubkey,
+    sequence: u64,
+    amount: u64,
+    non_refundable: bool,
+    tip_bps: u16,
+    tip_lamports: u64,
+) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (receipt, _) = find_receipt_address(program_id, campaign, sequence);
+    let (blocklist, _) = find_blocklist_address(program_id, campaign, donor);
+    let (config, _) = find_config_address(program_id);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let request = DonateDirectRequest { amount, non_refundable, tip_bps, tip_lamports };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new_readonly(blocklist, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: instruction_data(35, &request),
+    }
+}
+
+// 36, signed by the platform authority.
+pub fn set_legacy_donate_disabled(program_id: &Pubkey, authority: &Pubkey, disabled: bool) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(36, &SetLegacyDonateDisabledRequest { disabled }))
+}
+
+// 37, the admin replaces the description in the content PDA.
+pub fn update_campaign(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, description: &str) -> Instruction {
+    let (content, _) = find_campaign_content_address(program_id, campaign);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(content, false),
+        ],
+        data: instruction_data(37, &UpdateCampaignRequest { description: description.to_string() }),
+    }
+}
+
+// 38, copies the description of a v1 campaign into its content PDA, the payer pays for it.
+pub fn migrate_campaign_content(program_id: &Pubkey, campaign: &Pubkey, payer: &Pubkey) -> Instruction {
+    let (content, _) = find_campaign_content_address(program_id, campaign);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(content, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: vec![38],
+    }
+}
+
+// 39, `pause_until` 0 pauses until the admin resumes.
+pub fn pause_campaign(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, paused: bool, pause_until: i64) -> Instruction {
+    admin_instruction(program_id, campaign, admin, instruction_data(39, &PauseCampaignRequest { paused, pause_until }))
+}
+
+// 40, queues a withdraw of a timelocked campaign.
+pub fn request_withdraw(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, amount: u64) -> Instruction {
+    admin_instruction(program_id, campaign, admin, instruction_data(40, &RequestWithdrawRequest { amount }))
+}
+
+// 41, the oldest queued withdraw, to the admin.
+pub fn execute_withdraw(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, treasury: &Pubkey) -> Instruction {
+    let mut instruction = withdraw(program_id, campaign, admin, treasury, 0);
+    instruction.data = vec![41];
+    instruction
+}
+
+// 42, `index` is the position of the request in the queue.
+pub fn cancel_withdraw_request(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, index: u8) -> Instruction {
+    admin_instruction(program_id, campaign, admin, instruction_data(42, &CancelWithdrawRequest { index }))
+}
+
+// 43, anyone can record the hash of the campaign at the current slot.
+pub fn snapshot_campaign(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    Instruction { program_id: *program_id, accounts: vec![AccountMeta::new(*campaign, false)], data: vec![43] }
+}
+
+// 45, signed by the platform authority, replaces the programs allowed to invoke the guarded instructions.
+pub fn set_cpi_callers(program_id: &Pubkey, authority: &Pubkey, cpi_callers: Vec<Pubkey>) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(45, &SetCpiCallersRequest { cpi_callers }))
+}
+
+// 46, the donor closes the receipt of the donation numbered `sequence` once the campaign is closed.
+pub fn close_receipt(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey, sequence: u64) -> Instruction {
+    let (receipt, _) = find_receipt_address(program_id, campaign, sequence);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(receipt, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new_readonly(*campaign, false),
+        ],
+        data: vec![46],
+    }
+}
+
+// 47, the admin pays for the blocklist PDA of the wallet.
+pub fn block_donor(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let (blocklist, _) = find_blocklist_address(program_id, campaign, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new(blocklist, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: vec![47],
+    }
+}
+
+// 48, the admin gets the rent of the blocklist PDA back.
+pub fn unblock_donor(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, wallet: &Pubkey) -> Instruction {
+    let (blocklist, _) = find_blocklist_address(program_id, campaign, wallet);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(*wallet, false),
+            AccountMeta::new(blocklist, false),
+        ],
+        data: vec![48],
+    }
+}
+
+// 49, signed by the platform authority.
+pub fn correct_accounting(
+    program_id: &Pubkey,
+    campaign: &Pubkey,
+    authority: &Pubkey,
+    new_amount_donated: u64,
+    reason_code: u16,
+) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: instruction_data(49, &CorrectAccountingRequest { new_amount_donated, reason_code }),
+    }
+}
+
+// 51, anyone can confirm the donations that waited long enough.
+pub fn confirm_donations(program_id: &Pubkey, campaign: &Pubkey) -> Instruction {
+    Instruction { program_id: *program_id, accounts: vec![AccountMeta::new(*campaign, false)], data: vec![51] }
+}
+
+// 52, the admin changes the image link.
+pub fn set_image(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, image_link: &str) -> Instruction {
+    admin_instruction(program_id, campaign, admin, instruction_data(52, &SetImageRequest { image_link: image_link.to_string() }))
+}
+
+// 53, the donor takes back their pending donations nobody confirmed.
+pub fn reclaim_pending(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: vec![53],
+    }
+}
+
+// 54, signed by the platform authority, who pays for the extra bytes.
+pub fn realloc_config(program_id: &Pubkey, authority: &Pubkey, new_size: u32) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(config, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(54, &ReallocRequest { new_size }),
+    }
+}
+
+// 55, same for the global stats.
+pub fn realloc_global_stats(program_id: &Pubkey, authority: &Pubkey, new_size: u32) -> Instruction {
+    let (global_stats, _) = find_global_stats_address(program_id);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(global_stats, false),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(55, &ReallocRequest { new_size }),
+    }
+}
+
+// 56, what the donor gave in the current cycle and their tier as return data.
+pub fn get_donor_standing(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*campaign, false),
+            AccountMeta::new_readonly(*donor, false),
+            AccountMeta::new_readonly(donation_record, false),
+            AccountMeta::new_readonly(config, false),
+        ],
+        data: vec![56],
+    }
+}
+
+// 57, signed by the platform authority.
+pub fn set_sunset(program_id: &Pubkey, authority: &Pubkey, sunset: bool) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(57, &SetSunsetRequest { sunset }))
+}
+
+// 58, signed by the admin, or by the platform authority with `by_authority`, which passes the config.
+pub fn archive_campaign(program_id: &Pubkey, campaign: &Pubkey, signer: &Pubkey, by_authority: bool, archived: bool) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*campaign, false), AccountMeta::new_readonly(*signer, true)];
+    if by_authority {
+        accounts.push(AccountMeta::new_readonly(find_config_address(program_id).0, false));
+    }
+    Instruction { program_id: *program_id, accounts, data: instruction_data(58, &ArchiveCampaignRequest { archived }) }
+}
+
+// 59, signed by the platform authority.
+pub fn set_rounding(program_id: &Pubkey, authority: &Pubkey, rounding: Rounding) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(59, &SetRoundingRequest { rounding }))
+}
+
+// 60, signed by the platform authority.
+pub fn set_tip_loyalty(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    suggested_tip_bps: u16,
+    loyalty_min_donated: u64,
+    loyalty_discount_bps: u16,
+) -> Instruction {
+    let request = SetTipLoyaltyRequest { suggested_tip_bps, loyalty_min_donated, loyalty_discount_bps };
+    config_instruction(program_id, authority, instruction_data(60, &request))
+}
+
+// The platform settings only take the config and the authority.
+fn config_instruction(program_id: &Pubkey, authority: &Pubkey, data: Vec<u8>) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(config, false), AccountMeta::new_readonly(*authority, true)],
+        data,
+    }
+}
+
+// The campaign settings only take the campaign and its admin.
+fn admin_instruction(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, data: Vec<u8>) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*campaign, false), AccountMeta::new_readonly(*admin, true)],
+        data,
+    }
+}
+
+// 62, signed by the donor, sends their refunds from the campaign to `recipient`.
+pub fn set_refund_recipient(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey, recipient: &Pubkey) -> Instruction {
+    let (refund_recipient, _) = find_refund_recipient_address(program_id, campaign, donor);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*donor, true),
+            AccountMeta::new(refund_recipient, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(62, &SetRefundRecipientRequest { recipient: *recipient }),
+    }
+}
+
+// 63, the admin changes the goal and withdraw policy of the current cycle.
+pub fn set_goal(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, goal: u64, withdraw_policy: WithdrawPolicy) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new_readonly(config, false),
+        ],
+        data: instruction_data(63, &SetGoalRequest { goal, withdraw_policy }),
+    }
+}
+
+// 64, gives the donor back what they staged.
+pub fn reclaim_staged(program_id: &Pubkey, staging: &Pubkey, donor: &Pubkey, seed: &str) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*staging, false), AccountMeta::new(*donor, true)],
+        data: instruction_data(64, &ReclaimStagedRequest { seed: seed.to_string() }),
+    }
+}
+
+// 65, runs the checks of `create_campaign` on the same request without creating anything.
+pub fn validate_create(program_id: &Pubkey, settings: CampaignDetails, description: &str) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    let request = CreateCampaignRequest { campaign: settings, description: description.to_string() };
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(config, false)],
+        data: instruction_data(65, &request),
+    }
+}
+
+// 66, the admin pays the treasury to feature the campaign for `duration_secs` more.
+pub fn feature_campaign(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, treasury: &Pubkey, duration_secs: u64) -> Instruction {
+    let (config, _) = find_config_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new(*admin, true),
+            AccountMeta::new_readonly(config, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(66, &FeatureCampaignRequest { duration_secs }),
+    }
+}
+
+// 67, signed by the platform authority.
+pub fn set_feature_fee(program_id: &Pubkey, authority: &Pubkey, feature_fee_per_day: u64) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(67, &SetFeatureFeeRequest { feature_fee_per_day }))
+}
+
+// 68, the admin moves the campaign to another category before its first donation.
+pub fn set_category(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, category: u8) -> Instruction {
+    let (global_stats, _) = find_global_stats_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: instruction_data(68, &SetCategoryRequest { category }),
+    }
+}
+
+// 69, the admin gives the donor back what they donated in the current cycle.
+pub fn return_donation(program_id: &Pubkey, campaign: &Pubkey, admin: &Pubkey, donor: &Pubkey) -> Instruction {
+    let (donation_record, _) = find_donation_record_address(program_id, campaign, donor);
+    let (global_stats, _) = find_global_stats_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*campaign, false),
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(donation_record, false),
+            AccountMeta::new(*donor, false),
+            AccountMeta::new(global_stats, false),
+        ],
+        data: instruction_data(69, &ReturnDonationRequest { donor: *donor }),
+    }
+}
+
+// 70, signed by the platform authority.
+pub fn set_min_goal(program_id: &Pubkey, authority: &Pubkey, min_goal_lamports: u64) -> Instruction {
+    config_instruction(program_id, authority, instruction_data(70, &SetMinGoalRequest { min_goal_lamports }))
+}
+
+// 71, signed by the platform authority.
+pub fn set_donation_limits(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    min_donation_lamports: u64,
+    max_donations_per_window: u32,
+    donation_window_secs: u64,
+) -> Instruction {
+    let request = SetDonationLimitsRequest { min_donation_lamports, max_donations_per_window, donation_window_secs };
+    config_instruction(program_id, authority, instruction_data(71, &request))
+}
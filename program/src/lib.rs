@@ -1,223 +1,7678 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+#[macro_use]
+pub mod logging;
+mod guards;
+pub mod instruction;
+pub mod seeds;
+use seeds::*;
+use logging::Instruction;
+use std::convert::TryFrom;
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
-    entrypoint,
+    clock::Clock,
     entrypoint::ProgramResult,
     msg,
+    hash::{hash, hashv},
+    log::sol_log_data,
+    program::{invoke, invoke_signed, set_return_data},
     program_error::ProgramError,
     pubkey::Pubkey,
     rent::Rent,
+    entrypoint::MAX_PERMITTED_DATA_INCREASE,
+    system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
+    system_program,
     sysvar::Sysvar,
 };
 
 // Every solana program has one entry point
 // And it is convention to name it `process_instruction`.
 // It should take in program_id, accounts, instruction_data as parameter.
-fn process_instruction(
+// It runs the instruction with `dispatch` and logs how it ended, see the `logging` module.
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let result = dispatch(program_id, accounts, instruction_data);
+    let tag = instruction_data.first().copied();
+    match (tag.and_then(Instruction::from_tag), &result) {
+        (Some(instruction), Ok(())) => log_event!(instruction, "ok"),
+        (Some(instruction), Err(err)) => log_err!(instruction, err.clone()),
+        (None, Err(err)) => log_err!(if tag.is_some() { "unknown" } else { "empty" }, err.clone()),
+        // Only an instruction we know can succeed.
+        (None, Ok(())) => {}
+    }
+    result
+}
+
+fn dispatch(
     // program id is noting but the id of this program(smart contract) on the solana network 
     program_id: &Pubkey,
-    // array of account that is going to be used to process instruction
-    // As you can see it is a array of AccountInfo.
-    // We can provide as many as we want.
+    // array of account that is going to be used to process instruction
+    // As you can see it is a array of AccountInfo.
+    // We can provide as many as we want.
+    accounts: &[AccountInfo],
+    // This is the data we want to process our instuction for.
+    // It is a list of 8 bitunsinged integers(0..255).
+    instruction_data: &[u8],
+) -> ProgramResult {
+
+    // We check if we have a instruction_data len greater then 0, if it is not, we do not want to procced.
+    // So we return Error with InvalidInstructionData Message.
+    if instruction_data.is_empty() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    // As we know that solana program have only one entrypoint, but we want three entry point for your program.
+    // In order to fix this, we are going to take advantage of the fact that there is no limit to the instruction_data array 😜
+    // we use the first element of the instruction_data array to know what entry point we want to call.
+    // Now we just check and call the funciton for each of them.
+    // 0 for create_campaign,
+    // 1 for withdraw
+    // 2 for donate
+    // 3 for initialize_config
+    // 4 for open_dispute
+    // 5 for resolve_dispute
+    // 6 for finalize_campaign
+    // 7 for claim_refund
+    // 8 for renew_campaign
+    // 9 for create_voucher
+    // 10 for redeem_voucher
+    // 11 for reclaim_voucher
+    // 12 for liquidate_campaign
+    // 13 for pay_insurance_claim
+    // 14 for register_sponsor
+    // 15 for update_sponsor_name
+    // 16 for flag_sponsor
+    // 17 for commit_match
+    // 18 for release_match
+    // 19 for post_update
+    // 20 for set_fee_exempt
+    // 21 for add_charity
+    // 22 for remove_charity
+    // 23 for collect_fees
+    // 24 for version
+    // 25 for health_check
+    // 26 for get_withdrawable
+    // 27 for sweep_unclaimed_refunds
+    // 28 for redirect_funds
+    // 29 for set_refund_root
+    // 30 for claim_refund_with_proof
+    // 31 for set_soft_goal
+    // 32 for donate_split
+    // 33 for withdraw_many
+    // 34 for rename_campaign
+    // 35 for donate_direct
+    // 36 for set_legacy_donate_disabled
+    // 37 for update_campaign
+    // 38 for migrate_campaign_content
+    // 39 for pause_campaign
+    // 40 for request_withdraw
+    // 41 for execute_withdraw
+    // 42 for cancel_withdraw_request
+    // 43 for snapshot_campaign
+    // 44 for close_expired_campaigns
+    // 45 for set_cpi_callers
+    // 46 for close_receipt
+    // 47 for block_donor
+    // 48 for unblock_donor
+    // 49 for correct_accounting
+    // 50 for get_campaign_summary
+    // 51 for confirm_donations
+    // 52 for set_image
+    // 53 for reclaim_pending
+    // 54 for realloc_config
+    // 55 for realloc_global_stats
+    // 56 for get_donor_standing
+    // 57 for set_sunset
+    // 58 for archive_campaign
+    // 59 for set_rounding
+    // 60 for set_tip_loyalty
+    // 61 for get_progress
+    // 62 for set_refund_recipient
+    // 63 for set_goal
+    // 64 for reclaim_staged
+    // 65 for validate_create
+    // 66 for feature_campaign
+    // 67 for set_feature_fee
+    // 68 for set_category
+    // 69 for return_donation
+    // 70 for set_min_goal
+    // 71 for set_donation_limits.
+
+    // The read-only queries are checked first, so they stay cheap no matter how many
+    // instructions come before them in the list.
+    if instruction_data[0] == 24 {
+        return version();
+    } else if instruction_data[0] == 25 {
+        return health_check(program_id, accounts);
+    } else if instruction_data[0] == 26 {
+        return get_withdrawable(program_id, accounts);
+    } else if instruction_data[0] == 50 {
+        return get_campaign_summary(program_id, accounts);
+    } else if instruction_data[0] == 56 {
+        return get_donor_standing(program_id, accounts);
+    } else if instruction_data[0] == 61 {
+        return get_progress(program_id, accounts);
+    } else if instruction_data[0] == 65 {
+        return validate_create(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    }
+
+    if instruction_data[0] == 0 {
+        return create_campaign(
+            program_id,
+            accounts,
+            // Notice we pass program_id and accounts as they were,
+            // but we pass a reference to silce of [instruction_data].
+            // we do not want the first element in any of our function
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 1 {
+        return withdraw(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] ==2 {
+        return donate(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 3 {
+        return initialize_config(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 4 {
+        return open_dispute(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 5 {
+        return resolve_dispute(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 6 {
+        return finalize_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 7 {
+        return claim_refund(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 8 {
+        return renew_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 9 {
+        return create_voucher(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 10 {
+        return redeem_voucher(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 11 {
+        return reclaim_voucher(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 12 {
+        return liquidate_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 13 {
+        return pay_insurance_claim(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 14 {
+        return register_sponsor(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 15 {
+        return update_sponsor_name(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 16 {
+        return flag_sponsor(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 17 {
+        return commit_match(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 18 {
+        return release_match(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 19 {
+        return post_update(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 20 {
+        return set_fee_exempt(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 21 {
+        return add_charity(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 22 {
+        return remove_charity(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 23 {
+        return collect_fees(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 27 {
+        return sweep_unclaimed_refunds(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 28 {
+        return redirect_funds(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 29 {
+        return set_refund_root(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 30 {
+        return claim_refund_with_proof(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 31 {
+        return set_soft_goal(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 32 {
+        return donate_split(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 33 {
+        return withdraw_many(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 34 {
+        return rename_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 35 {
+        return donate_direct(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 36 {
+        return set_legacy_donate_disabled(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 37 {
+        return update_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 38 {
+        return migrate_campaign_content(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 39 {
+        return pause_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 40 {
+        return request_withdraw(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 41 {
+        return execute_withdraw(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 42 {
+        return cancel_withdraw_request(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 43 {
+        return snapshot_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 44 {
+        return close_expired_campaigns(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 45 {
+        return set_cpi_callers(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 46 {
+        return close_receipt(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 47 {
+        return block_donor(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 48 {
+        return unblock_donor(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 49 {
+        return correct_accounting(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 51 {
+        return confirm_donations(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 52 {
+        return set_image(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 53 {
+        return reclaim_pending(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 54 {
+        return realloc_config(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 55 {
+        return realloc_global_stats(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 57 {
+        return set_sunset(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 58 {
+        return archive_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 59 {
+        return set_rounding(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 60 {
+        return set_tip_loyalty(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 62 {
+        return set_refund_recipient(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 63 {
+        return set_goal(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 64 {
+        return reclaim_staged(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 66 {
+        return feature_campaign(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 67 {
+        return set_feature_fee(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 68 {
+        return set_category(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 69 {
+        return return_donation(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 70 {
+        return set_min_goal(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    } else if instruction_data[0] == 71 {
+        return set_donation_limits(
+            program_id,
+            accounts,
+            &instruction_data[1..instruction_data.len()],
+        );
+    }
+
+    // If instruction data doesn't match we give an error.
+    log_event!("unknown", "Didn't find the required entrypoint");
+    Err(ProgramError::InvalidInstructionData)
+}
+
+// Then we call the entry point macro to add `process_instruction` as our entrypoint to our program.
+// Programs depending on us to build CPIs turn on `no-entrypoint`, they have their own.
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+// Errors specific to our program. They are returned as ProgramError::Custom,
+// the code is the position in this enum so new errors always go at the end.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FundError {
+    EpochWithdrawLimitReached,
+    WithdrawCooldownActive,
+    RefundWindowClosed,
+    InvalidInstructionData,
+    HardCapReached,
+    GoalNotReached,
+    DonationTooLarge,
+    RefundRequiresDeadline,
+    LegacyPathDisabled,
+    MixedRefundability,
+    WithdrawNotMatured,
+    WithdrawQueueFull,
+    FeeCollectorNotRentExempt,
+    SnapshotTooSoon,
+    DonationVelocityExceeded,
+    DonorBlocked,
+    DuplicateAccount,
+    TooManyEntries,
+    MetadataLocked,
+    ProgramSunset,
+    TrailingInstructionData,
+    AccountTooLarge,
+    CampaignNotInitialized,
+    GoalLockedAfterDonations,
+    FundingModelLocked,
+    DonorLimitReached,
+    UpdateCooldown,
+    CategoryLocked,
+    NotRentExempt,
+    MilestoneReleased,
+    GoalBelowMinimum,
+    DonationTooSmall,
+}
+
+impl From<FundError> for ProgramError {
+    fn from(e: FundError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+// Failed instructions can leave a small error payload in the return data, so clients can show
+// more then the error code from a simulated transaction. It is ERROR_RETURN_DATA_MARKER, the error
+// code as u64 and then the context values as u64, all little endian. Most errors have no context.
+pub const ERROR_RETURN_DATA_MARKER: u8 = 0xEE;
+
+fn error_with_context(err: ProgramError, context: &[u64]) -> ProgramError {
+    let mut data = Vec::with_capacity(1 + 8 * (1 + context.len()));
+    data.push(ERROR_RETURN_DATA_MARKER);
+    data.extend_from_slice(&u64::from(err.clone()).to_le_bytes());
+    for value in context {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    set_return_data(&data);
+    err
+}
+
+// InsufficientFunds with the lamports that were required and the lamports that were available.
+fn insufficient_funds(required: u64, available: u64) -> ProgramError {
+    error_with_context(ProgramError::InsufficientFunds, &[required, available])
+}
+
+// Every instruction payload is parsed with this instead of `expect`, so a list whose length prefix
+// doesn't match the data is a clean error and not a panic. Bytes left after the payload are
+// rejected too: a client appending garbage should hear about it now, not once a later version
+// gives those bytes a meaning.
+fn parse_instruction<T: BorshDeserialize>(instruction: Instruction, instruction_data: &[u8]) -> Result<T, ProgramError> {
+    let mut remaining = instruction_data;
+    let parsed = T::deserialize(&mut remaining).map_err(|_| {
+        log_event!(instruction, "Malformed instruction data");
+        ProgramError::from(FundError::InvalidInstructionData)
+    })?;
+    if !remaining.is_empty() {
+        log_event!(instruction, "{} unexpected bytes after the instruction data", remaining.len());
+        return Err(FundError::TrailingInstructionData.into());
+    }
+    Ok(parsed)
+}
+
+// Here I have created the function for every action we want to do in our program.
+// They take same parameter as in process_instruction and same return type
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct CampaignDetails {
+    // A single key. There are no multisig admins with a threshold, a team wanting one makes a
+    // multisig wallet the admin.
+    pub admin: Pubkey,
+    // `campaign_name_hash` of the name, always computed by the program. It sits right after the admin
+    // so clients can find a campaign by admin and name with a memcmp filter.
+    pub name_hash: [u8; 32],
+    // Sequential id from GlobalStats, for URLs and support tickets. 0 for campaigns created before ids.
+    pub campaign_id: u64,
+    pub name: String,
+    pub image_link: String,
+    pub amount_donated: u64,
+    // Set by the dispute resolver when a campaign gets reported.
+    // While it is set the admin can't withdraw.
+    pub disputed: bool,
+    // The resolver can also decide to stop new donations while the dispute is open.
+    pub donations_paused: bool,
+    // Amount the campaign wants to raise, reaching it makes the campaign fully funded.
+    // Unless there is a soft goal, it also decides if the campaign succeeds or fails.
+    pub goal: u64,
+    // Unix timestamp after which the campaign can be finalized, 0 means it never ends.
+    pub end_timestamp: i64,
+    pub state: CampaignState,
+    // Number of different donors in the current cycle.
+    pub donor_count: u32,
+    // Part of amount_donated that was paid back to donors after the campaign failed.
+    pub total_refunded: u64,
+    // A campaign can be renewed, every renewal starts a new cycle.
+    // Donation records remember the cycle they were made in.
+    pub cycle: u32,
+    // Display unit for front-ends, e.g. "SOL" or "USDC", ASCII padded with zeros.
+    // It doesn't change how the campaign works: donations are always lamports, the program
+    // never holds SPL tokens, so there is no token withdraw path (or associated token account
+    // to check) to go with it. Amounts are always lamports too, so there are no mint decimals
+    // to store: front-ends render them with SOL's 9.
+    pub currency: [u8; 8],
+    // Part of amount_donated that came from sponsors matching donations in the current cycle.
+    pub amount_matched: u64,
+    // At most this much can be withdrawn during one epoch, 0 means there is no limit.
+    pub max_withdraw_per_epoch: u64,
+    // What was withdrawn during `withdraw_epoch`, reset when a new epoch starts.
+    pub withdrawn_this_epoch: u64,
+    pub withdraw_epoch: u64,
+    // Minimum number of seconds between two withdrawals, 0 means no cooldown.
+    pub withdraw_cooldown_secs: u64,
+    // When funds last left the campaign. It is set when a withdraw is executed,
+    // so any future delayed/timelocked withdraw also counts from its execution, not its request.
+    pub last_withdraw_ts: i64,
+    // Verified charities don't pay platform fees. Only the platform authority can set it.
+    pub fee_exempt: bool,
+    // Treasury share of the withdraw fees kept in the campaign until it succeeds,
+    // when the platform refunds fees to donors of failed campaigns (see `collect_fees`).
+    pub fees_reserved: u64,
+    // Everything withdrawn from the campaign so far, the fee tiers apply to this running total.
+    pub fee_assessed_volume: u64,
+    // After this unix timestamp donors of a failed campaign can't claim refunds anymore
+    // and what is left can be swept. 0 means refunds never expire.
+    pub refund_deadline: i64,
+    // Hash of an attestation signed off-chain by an identity or KYC provider, linking the campaign
+    // to a verified beneficiary without putting personal data on-chain. Set at creation and never
+    // changed, all zeros means there is none.
+    pub attestation: [u8; 32],
+    // Snapshot taken when the campaign fails or is liquidated: the lamports available for refunds
+    // and the donations they have to cover. When the pool is short every donor gets the same share,
+    // so it doesn't matter who claims first. Neither changes until the campaign is renewed.
+    pub refund_pool: u64,
+    pub refundable_total: u64,
+    // Everything this campaign moved to partner campaigns with `redirect_funds`.
+    pub total_redirected: u64,
+    // Merkle root of (donor, refund amount) pairs posted by the platform authority for large
+    // failed campaigns, donors then claim with a proof instead of a donation record.
+    // All zeros means there is none.
+    pub refund_root: [u8; 32],
+    // The campaign never takes more then this, 0 means there is no cap.
+    pub hard_cap: u64,
+    // With it a donation crossing the cap is cut down to what is left below the cap and the
+    // rest goes back to the donor. Without it such a donation fails.
+    pub fill_to_cap: bool,
+    // The minimum viable amount, at most the goal and 0 when there is none. When it is set,
+    // reaching it is enough for the campaign to succeed and to unlock GoalOnly withdrawals.
+    pub soft_goal: u64,
+    pub withdraw_policy: WithdrawPolicy,
+    // A single donation can't be more then this, 0 means there is no limit.
+    // It guards against fat-fingered transfers, it doesn't limit what a donor gives in total.
+    pub max_donation_per_tx: u64,
+    // Donations of the current cycle whose donors gave up their refund. They are left out of
+    // `refundable_total` and belong to the campaign whatever happens.
+    pub non_refundable_total: u64,
+    // What the admin can still withdraw from a failed campaign: the part of the balance left
+    // once the refundable donations are covered, set with the refund snapshot.
+    pub failed_withdrawable: u64,
+    // The CampaignContent PDA holding the description. Every donation rewrites this account,
+    // so the long-form text lives there instead of here.
+    pub content: Pubkey,
+    // The admin can pause donations too, separately from the resolver's `donations_paused`.
+    pub admin_paused: bool,
+    // When an admin pause ends by itself, 0 means it lasts until the admin resumes.
+    pub pause_until: i64,
+    // When it isn't 0 withdrawals are timelocked: the admin queues them with `request_withdraw`
+    // and can execute each one this many seconds later. Set at creation.
+    pub withdraw_delay_secs: u64,
+    // Latest `snapshot_campaign`: the slot it was taken at and the hash of the campaign then.
+    pub snapshot_slot: u64,
+    pub snapshot_hash: [u8; 32],
+    // Number of donations the campaign ever received, the next donation gets this as its receipt sequence.
+    pub donation_sequence: u64,
+    // Donations since `donation_window_start`, to limit how fast donations can come in.
+    pub donations_in_window: u32,
+    pub donation_window_start: i64,
+    // When it isn't 0 donations wait this many slots in `pending_donations` before they count
+    // in `amount_donated`, so a reorg can't leave the campaign counting a donation that's gone.
+    // Set at creation.
+    pub confirmation_slots: u64,
+    // Set at creation to freeze the name, image and description once the first donation arrives,
+    // so donors know what they backed won't change.
+    pub metadata_locked: bool,
+    // Hidden from default listings, see `archive_campaign`.
+    pub archived: bool,
+    // Membership style campaigns take at most this many different donors per cycle, donors already
+    // in can still top up. 0 means there is no limit. Set at creation.
+    pub max_donors: u32,
+    // With a `max_donors`, the campaign only succeeds (and a GoalOnly one only unlocks withdrawals)
+    // once every spot is taken, on top of raising its goal.
+    pub require_full_donor_count: bool,
+    // Front-ends show the campaign as featured until this timestamp, see `feature_campaign`.
+    pub featured_until: i64,
+    // Index into the platform's per-category stats, below MAX_CATEGORIES. What each index means
+    // is up to the front-ends, 0 is uncategorized. See `set_category`.
+    pub category: u8,
+    // Lamports the admin gave back to donors with `return_donation`, over the campaign's lifetime.
+    pub returned_amount: u64,
+    // Addresses withdrawals can be sent to, set at creation. Empty means only the admin.
+    pub withdraw_recipients: Vec<Pubkey>,
+    // Timelocked withdrawals waiting to be executed, oldest first.
+    pub withdraw_queue: Vec<QueuedWithdraw>,
+    // Every time the platform authority corrected `amount_donated`, oldest first.
+    pub corrections: Vec<AccountingCorrection>,
+    // Donations waiting for `confirmation_slots`, oldest first.
+    pub pending_donations: Vec<PendingDonation>,
+    // Tranches funds unlock in, increasing `amount` order. Set at creation, empty means
+    // everything raised can be withdrawn.
+    pub milestones: Vec<Milestone>,
+    // Totals of the cycles before the current one, oldest first.
+    // It stays the last field so it can grow into the spare room at the end of the account.
+    pub previous_cycles: Vec<CycleSummary>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum CampaignState {
+    #[default]
+    Active,
+    Successful,
+    Failed,
+    // The dispute resolver found the campaign fraudulent. It is frozen for good,
+    // donors get refunds and can claim from the insurance pool for what is missing.
+    Liquidated,
+    // The refund window of a failed or liquidated campaign ended and the unclaimed refunds were swept.
+    Closed,
+}
+
+// When the admin can take funds out of the campaign, set at creation.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Default)]
+pub enum WithdrawPolicy {
+    // Whenever there is something to withdraw.
+    #[default]
+    Anytime,
+    // Only once the campaign raised its soft goal, or its goal when there is no soft goal.
+    GoalOnly,
+}
+
+// The currency label campaigns get when they don't set one.
+pub const SOL_CURRENCY: [u8; 8] = *b"SOL\0\0\0\0\0";
+
+impl CampaignDetails {
+    // A new active campaign raising `goal` SOL until `end_timestamp` (0 for no end date), with every
+    // other setting at its default. The name, description and image link are trimmed, the name can't
+    // be empty, the description can't be longer then MAX_DESCRIPTION_LEN and an end date has to be
+    // in the future. The description isn't part of the campaign, it is returned for the
+    // CampaignContent PDA. It doesn't log, the instruction calling it logs why it failed.
+    pub fn new(
+        admin: Pubkey,
+        name: &str,
+        description: &str,
+        image_link: &str,
+        goal: u64,
+        end_timestamp: i64,
+    ) -> Result<(Self, String), ProgramError> {
+        let name = trimmed_text(name).ok_or(ProgramError::InvalidInstructionData)?;
+        if name.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let description = trimmed_description(description).ok_or(ProgramError::InvalidInstructionData)?;
+        // A campaign with an end date has to end in the future.
+        if end_timestamp != 0 && end_timestamp <= Clock::get()?.unix_timestamp {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let campaign = CampaignDetails {
+            admin,
+            name_hash: campaign_name_hash(&name),
+            name,
+            image_link: trimmed_text(image_link).ok_or(ProgramError::InvalidInstructionData)?,
+            goal,
+            end_timestamp,
+            currency: SOL_CURRENCY,
+            ..Default::default()
+        };
+        Ok((campaign, description))
+    }
+
+    // Donations still waiting for their confirmation slots.
+    pub fn pending_amount(&self) -> u64 {
+        self.pending_donations.iter().map(|pending| pending.amount).sum()
+    }
+
+    // Part of `amount_donated` a milestone campaign can't withdraw yet: what was raised past the
+    // last milestone reached. Once the last milestone is reached nothing is held back.
+    pub fn milestone_locked(&self) -> u64 {
+        match self.milestones.last() {
+            Some(last) if self.amount_donated < last.amount => {
+                let unlocked = self
+                    .milestones
+                    .iter()
+                    .filter(|milestone| milestone.amount <= self.amount_donated)
+                    .map(|milestone| milestone.amount)
+                    .max()
+                    .unwrap_or(0);
+                self.amount_donated - unlocked
+            }
+            _ => 0,
+        }
+    }
+
+    // Whether the campaign got all the donors it requires, true when it doesn't require any.
+    pub fn donor_count_reached(&self) -> bool {
+        !self.require_full_donor_count || self.donor_count >= self.max_donors
+    }
+
+    // What the campaign has to raise to succeed: the soft goal if it has one, otherwise the goal.
+    pub fn funded_threshold(&self) -> u64 {
+        if self.soft_goal != 0 {
+            self.soft_goal
+        } else {
+            self.goal
+        }
+    }
+
+    // Raised the whole goal, what front-ends show as fully funded.
+    pub fn fully_funded(&self) -> bool {
+        self.amount_donated >= self.goal
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct CycleSummary {
+    pub raised: u64,
+    pub donors: u32,
+    pub ended_at: i64,
+}
+
+// Audit entry of a `correct_accounting`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct AccountingCorrection {
+    pub old_amount_donated: u64,
+    pub new_amount_donated: u64,
+    // Why it was corrected, the codes are kept by the platform (e.g. 1 for rent counted as a donation).
+    pub reason_code: u16,
+    pub corrected_at: i64,
+}
+
+// A donation waiting for the campaign's confirmation slots.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct PendingDonation {
+    pub donor: Pubkey,
+    pub amount: u64,
+    // Slot the donation was made in.
+    pub slot: u64,
+}
+
+// A tranche of a milestone campaign. `amount` is cumulative: the campaign can withdraw up to it
+// once `amount_donated` reaches it. `released` is set by the first withdraw after it was reached.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct Milestone {
+    pub amount: u64,
+    pub released: bool,
+}
+
+// A withdraw the admin requested on a timelocked campaign. Executed requests are removed from
+// the queue, so every entry in it is still pending.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+pub struct QueuedWithdraw {
+    pub amount: u64,
+    pub requested_at: i64,
+}
+
+// We keep at most this many previous cycles, renewing past it drops the oldest one.
+// The client allocates room for MAX_PREVIOUS_CYCLES CycleSummary on top of the campaign itself,
+// that is why campaign data is read with `deserialize` which doesn't mind the unused bytes
+// at the end of the account.
+const MAX_PREVIOUS_CYCLES: usize = 8;
+// A campaign can be created with at most this many withdraw recipients.
+const MAX_WITHDRAW_RECIPIENTS: usize = 8;
+// A timelocked campaign can have this many withdrawals queued at once.
+const MAX_QUEUED_WITHDRAWS: usize = 4;
+// `amount_donated` of a campaign can be corrected this many times.
+const MAX_CORRECTIONS: usize = 4;
+// A campaign with a confirmation threshold keeps at most this many donations pending, past it the
+// oldest one is counted early.
+const MAX_PENDING_DONATIONS: usize = 8;
+// Longest confirmation threshold a campaign can ask for, about ten minutes of slots.
+const MAX_CONFIRMATION_SLOTS: u64 = 1_500;
+// A donation still pending this many slots after it was made, about an hour, can be taken back
+// by its donor with `reclaim_pending`.
+const PENDING_RECLAIM_SLOTS: u64 = 9_000;
+// A campaign can release its funds in at most this many milestones.
+const MAX_MILESTONES: usize = 8;
+// Largest campaign account the program accepts. A full campaign with every list at its maximum
+// is under 2 KiB, the rest leaves room for long names and links. The client allocates exactly
+// what a campaign needs, a bigger account only costs its creator rent and slows every scan.
+// The PDAs the program creates itself are always sized exactly from their structs.
+pub const MAX_CAMPAIGN_LEN: usize = 10_240;
+
+// Every list stored in an account has a maximum length, so nobody can grow an account past what
+// its rent and the compute to read it were sized for.
+fn check_entries(instruction: Instruction, len: usize, max: usize, what: &str) -> ProgramResult {
+    if len > max {
+        log_event!(instruction, "There can be at most {} {}, got {}", max, what, len);
+        return Err(FundError::TooManyEntries.into());
+    }
+    Ok(())
+}
+
+// Room the campaign account needs past its current data for the lists that can still grow.
+fn campaign_growth_room(campaign_data: &CampaignDetails) -> usize {
+    (MAX_PREVIOUS_CYCLES - campaign_data.previous_cycles.len()) * serialized_len::<CycleSummary>()
+        + (MAX_QUEUED_WITHDRAWS - campaign_data.withdraw_queue.len()) * serialized_len::<QueuedWithdraw>()
+        + (MAX_CORRECTIONS - campaign_data.corrections.len()) * serialized_len::<AccountingCorrection>()
+        + (MAX_PENDING_DONATIONS - campaign_data.pending_donations.len()) * serialized_len::<PendingDonation>()
+}
+
+// Builds a new campaign and its description from what a client sent, create_campaign and
+// validate_create both go through it. Only what a client can choose is taken from `settings`,
+// everything the program keeps track of itself starts at its default.
+fn new_campaign(
+    instruction: Instruction,
+    settings: CampaignDetails,
+    description: &str,
+) -> Result<(CampaignDetails, String), ProgramError> {
+    let (defaults, description) = CampaignDetails::new(
+        settings.admin,
+        &settings.name,
+        description,
+        &settings.image_link,
+        settings.goal,
+        settings.end_timestamp,
+    )
+    .inspect_err(|_| {
+        log_event!(
+            instruction,
+            "The name can't be empty, the description can be at most {} bytes, the end has to be in the future \
+             and text can't have control characters",
+            MAX_DESCRIPTION_LEN
+        )
+    })?;
+    let campaign = CampaignDetails {
+        currency: settings.currency,
+        max_withdraw_per_epoch: settings.max_withdraw_per_epoch,
+        withdraw_cooldown_secs: settings.withdraw_cooldown_secs,
+        attestation: settings.attestation,
+        hard_cap: settings.hard_cap,
+        fill_to_cap: settings.fill_to_cap,
+        soft_goal: settings.soft_goal,
+        withdraw_policy: settings.withdraw_policy,
+        max_donation_per_tx: settings.max_donation_per_tx,
+        withdraw_delay_secs: settings.withdraw_delay_secs,
+        confirmation_slots: settings.confirmation_slots,
+        metadata_locked: settings.metadata_locked,
+        max_donors: settings.max_donors,
+        require_full_donor_count: settings.require_full_donor_count,
+        category: settings.category,
+        withdraw_recipients: settings.withdraw_recipients,
+        milestones: settings
+            .milestones
+            .into_iter()
+            .map(|milestone| Milestone { amount: milestone.amount, released: false })
+            .collect(),
+        ..defaults
+    };
+
+    if !is_valid_currency_label(&campaign.currency) {
+        log_event!(instruction, "currency should be ASCII padded with zeros");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if campaign.soft_goal > campaign.goal {
+        log_event!(instruction, "soft_goal can't be more then the goal");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_goal_only_deadline(instruction, &campaign.withdraw_policy, campaign.end_timestamp)?;
+
+    if campaign.hard_cap != 0 && campaign.hard_cap < campaign.goal {
+        log_event!(instruction, "hard_cap can't be less then the goal");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    check_entries(instruction, campaign.withdraw_recipients.len(), MAX_WITHDRAW_RECIPIENTS, "withdraw recipients")?;
+    check_entries(instruction, campaign.milestones.len(), MAX_MILESTONES, "milestones")?;
+    let mut previous_milestone = 0;
+    for milestone in campaign.milestones.iter() {
+        if milestone.amount <= previous_milestone {
+            log_event!(instruction, "Milestone amounts should be more then zero and increasing");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        previous_milestone = milestone.amount;
+    }
+
+    if campaign.require_full_donor_count && campaign.max_donors == 0 {
+        log_event!(instruction, "require_full_donor_count needs a max_donors");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if campaign.confirmation_slots > MAX_CONFIRMATION_SLOTS {
+        log_event!(instruction, "confirmation_slots can be at most {}", MAX_CONFIRMATION_SLOTS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_category(instruction, campaign.category)?;
+    Ok((campaign, description))
+}
+
+fn create_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CreateCampaign;
+
+    // We create a iterator an accounts
+    // account parameter is the array of accounts related to this entrypoint
+    let accounts_iter = &mut accounts.iter();
+
+    // writing accounts or we can call it program account
+    // This is an account we will create in our front-end.
+    // This account should be owned by the solana program
+    let writing_account = next_account_info(accounts_iter)?;
+    
+    // Accounts of the person creating the campaign, signer
+    let creator_account = next_account_info(accounts_iter)?;
+
+    // The creator pays the platform a creation fee, unless they are on the charity allowlist
+    // and pass their charity entry as the last account.
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    // PDA from the admin and the name hash, an admin can't have two campaigns with the same name.
+    let name_account = next_account_info(accounts_iter)?;
+    // Hands out the campaign id.
+    let global_stats_account = next_account_info(accounts_iter)?;
+    // PDA from the campaign, holds the description.
+    let content_account = next_account_info(accounts_iter)?;
+    let charity_account = accounts_iter.next();
+
+    // Now to allow transcation we want the creator account to sign the transcation.
+    if !creator_account.is_signer {
+        log_event!(instruction, "creator_account should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, creator_account)?;
+
+    // We want to write in this account so we want it is owned by the program.
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing_accounts isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // By deriving the trait BorshDeserializer in our CampaignDetails struct we have added a method `try_from_slice` which take in the parameter array of u8 and create
+    // an object of CampaignDetails with it.
+    log_phase!(instruction, "deserialize");
+    let request: CreateCampaignRequest = parse_instruction(instruction, instruction_data)?;
+
+    // Validating that only admin can create campaign
+    if request.campaign.admin != *creator_account.key {
+        log_event!(instruction, "Invalid instruction data");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    log_phase!(instruction, "validate");
+    if writing_account.data_len() > MAX_CAMPAIGN_LEN {
+        log_event!(
+            instruction,
+            "writing_account is {} bytes, it can be at most {}",
+            writing_account.data_len(),
+            MAX_CAMPAIGN_LEN
+        );
+        return Err(FundError::AccountTooLarge.into());
+    }
+    // let try to make our program rent exempet, with the buffer on top
+    let min_balance = campaign_min_balance(writing_account.data_len())?;
+    if **writing_account.lamports.borrow() < min_balance {
+        log_event!(
+            instruction,
+            "The balance of writing_account should be at least {} (rent exemption and buffer)",
+            min_balance
+        );
+        return Err(insufficient_funds(min_balance, writing_account.lamports()));
+    }
+
+    let (mut input_data, description) = new_campaign(instruction, request.campaign, &request.description)?;
+
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    input_data.campaign_id = global_stats.next_campaign_id;
+    global_stats.next_campaign_id = global_stats
+        .next_campaign_id
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let category_stats = &mut global_stats.categories[input_data.category as usize];
+    category_stats.campaigns_created = category_stats
+        .campaigns_created
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+
+    register_campaign_name(
+        instruction,
+        program_id,
+        writing_account,
+        creator_account,
+        name_account,
+        system_program,
+        &input_data.name_hash,
+    )?;
+
+    log_phase!(instruction, "cpi");
+    input_data.content = create_campaign_content(
+        instruction,
+        program_id,
+        writing_account,
+        creator_account,
+        content_account,
+        system_program,
+        description,
+    )?;
+
+    let config = load_config(instruction, program_id, config_account)?;
+    check_not_sunset(instruction, &config)?;
+    check_min_goal(instruction, &config, &input_data.withdraw_policy, input_data.goal)?;
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Campaigns of allowlisted charities are fee exempt from the start.
+    input_data.fee_exempt = match charity_account {
+        Some(charity_account) => is_allowlisted_charity(program_id, charity_account, creator_account.key),
+        None => false,
+    };
+    if !input_data.fee_exempt && config.creation_fee > 0 {
+        check_fee_collector(instruction, treasury_account)?;
+        invoke(
+            &system_instruction::transfer(creator_account.key, treasury_account.key, config.creation_fee),
+            &[
+                creator_account.clone(),
+                treasury_account.clone(),
+                system_program.clone(),
+            ],
+        )?;
+    }
+
+    // The account needs room for the previous cycles the campaign can collect when it gets renewed,
+    // and for a full withdraw queue.
+    let required_len = checked_account_len(
+        instruction,
+        input_data.try_to_vec()?.len(),
+        campaign_growth_room(&input_data),
+    )?;
+    if writing_account.data_len() < required_len {
+        log_event!(
+            instruction,
+            "writing_account should have room for {} previous cycles and {} queued withdrawals",
+            MAX_PREVIOUS_CYCLES,
+            MAX_QUEUED_WITHDRAWS
+        );
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    log_phase!(instruction, "serialize");
+    // writing into CampaignDetails
+    input_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+
+    // The client gets the addresses it would otherwise derive again, and the id it can't know.
+    let result = CreateCampaignResult {
+        campaign: *writing_account.key,
+        content: input_data.content,
+        name_record: *name_account.key,
+        campaign_id: input_data.campaign_id,
+    };
+    set_return_data(&result.try_to_vec()?);
+
+    Ok(())
+}
+
+// Return data of create_campaign.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreateCampaignResult {
+    pub campaign: Pubkey,
+    // The CampaignContent PDA holding the description.
+    pub content: Pubkey,
+    // The PDA reserving the campaign's name for its admin.
+    pub name_record: Pubkey,
+    pub campaign_id: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CreateCampaignRequest {
+    pub campaign: CampaignDetails,
+    pub description: String,
+}
+
+// Return data of validate_create.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CreatePreview {
+    // Bytes the campaign account has to be created with.
+    pub required_len: u64,
+    // Lamports the campaign account needs, rent exemption and buffer.
+    pub min_balance: u64,
+    // Charged by create_campaign unless the creator is an allowlisted charity.
+    pub creation_fee: u64,
+    // The PDA reserving the name, create_campaign fails if it already exists.
+    pub name_record: Pubkey,
+}
+
+// Runs the checks of create_campaign on a payload without creating anything, so a front-end
+// can simulate it and show the error before asking the creator to sign.
+// Takes the config account, and the same data as create_campaign.
+// The admin isn't required to sign here, and the name isn't checked to be free.
+fn validate_create(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ValidateCreate;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+
+    log_phase!(instruction, "deserialize");
+    let request: CreateCampaignRequest = parse_instruction(instruction, instruction_data)?;
+
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    check_not_sunset(instruction, &config)?;
+
+    let (input_data, _) = new_campaign(instruction, request.campaign, &request.description)?;
+    check_min_goal(instruction, &config, &input_data.withdraw_policy, input_data.goal)?;
+
+    let required_len = checked_account_len(
+        instruction,
+        input_data.try_to_vec()?.len(),
+        campaign_growth_room(&input_data),
+    )?;
+    if required_len > MAX_CAMPAIGN_LEN {
+        log_event!(instruction, "The campaign needs {} bytes, it can be at most {}", required_len, MAX_CAMPAIGN_LEN);
+        return Err(FundError::AccountTooLarge.into());
+    }
+
+    let preview = CreatePreview {
+        required_len: required_len as u64,
+        min_balance: campaign_min_balance(required_len)?,
+        creation_fee: config.creation_fee,
+        name_record: find_campaign_name_address(program_id, &input_data.admin, &input_data.name_hash).0,
+    };
+    log_phase!(instruction, "serialize");
+    set_return_data(&preview.try_to_vec()?);
+    Ok(())
+}
+
+// Fails once a campaign that locks its metadata got a donation.
+fn check_metadata_editable(instruction: Instruction, campaign_data: &CampaignDetails) -> ProgramResult {
+    if campaign_data.metadata_locked && campaign_data.donation_sequence > 0 {
+        log_event!(instruction, "Campaign metadata is locked since its first donation");
+        return Err(FundError::MetadataLocked.into());
+    }
+    Ok(())
+}
+
+// A program owned campaign can't sign, so passing it in the signer slot is always a client bug.
+// It gets its own error instead of whatever check would happen to fail next.
+fn check_distinct_signer(
+    instruction: Instruction,
+    writing_account: &AccountInfo,
+    signer: &AccountInfo,
+) -> ProgramResult {
+    if writing_account.key == signer.key {
+        log_event!(instruction, "The campaign account can't also be the signer");
+        return Err(FundError::DuplicateAccount.into());
+    }
+    Ok(())
+}
+
+// User supplied text is stored trimmed. Control characters (tabs and newlines included)
+// are rejected, front-ends only ever show these strings on one line or as plain paragraphs.
+fn clean_text(instruction: Instruction, text: &str) -> Result<String, ProgramError> {
+    trimmed_text(text).ok_or_else(|| {
+        log_event!(instruction, "Text can't contain control characters");
+        ProgramError::InvalidInstructionData
+    })
+}
+
+// `clean_text` without the log, None for text with control characters.
+fn trimmed_text(text: &str) -> Option<String> {
+    if text.chars().any(|c| c.is_control()) {
+        return None;
+    }
+    Some(text.trim().to_string())
+}
+
+// Invisible characters that make two names look the same while their bytes differ.
+fn is_zero_width(c: char) -> bool {
+    matches!(c, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}')
+}
+
+// What two campaign names are compared by: lowercased, zero-width characters dropped and
+// every run of whitespace collapsed to a single space, with none at the ends.
+// There are no unicode normalization tables on-chain, so an accented letter sent precomposed
+// and the same letter sent decomposed still compare different. Clients should send NFC.
+pub fn name_key(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| !is_zero_width(*c))
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// A currency label is printable ASCII followed by zero padding, like b"SOL\0\0\0\0\0".
+fn is_valid_currency_label(label: &[u8; 8]) -> bool {
+    let len = label.iter().position(|c| *c == 0).unwrap_or(label.len());
+    label[..len].iter().all(|c| c.is_ascii_graphic()) && label[len..].iter().all(|c| *c == 0)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct WithdrawRequest {
+    pub amount: u64,
+}
+fn withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::Withdraw;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    // The platform takes a fee on every withdraw, part of it funds the insurance pool.
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let insurance_pool_account = next_account_info(accounts_iter)?;
+    // Where the funds go, the admin when it isn't passed.
+    let recipient_account = next_account_info(accounts_iter).unwrap_or(admin_account);
+
+    // Admin accounts should be the signer in this transaction
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId)
+    }
+
+    let config = load_config(instruction, program_id, config_account)?;
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut insurance_pool = load_insurance_pool(instruction, program_id, insurance_pool_account)?;
+
+    let input_data: WithdrawRequest = parse_instruction(instruction, instruction_data)?;
+
+    withdraw_from_campaign(
+        instruction,
+        program_id,
+        writing_account,
+        admin_account,
+        recipient_account,
+        treasury_account,
+        insurance_pool_account,
+        &config,
+        &mut insurance_pool,
+        Some(input_data.amount),
+        false,
+    )?;
+
+    insurance_pool.serialize(&mut &mut insurance_pool_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Moves `amount` out of one campaign to the recipient, taking the platform fee.
+// Without an amount everything available is withdrawn. A timelocked campaign only pays out
+// `from_queue`, when `execute_withdraw` runs a matured request.
+// Every check comes before any lamports move or the campaign is written, so when it fails
+// the campaign is left as it was. The caller saves the insurance pool.
+#[allow(clippy::too_many_arguments)]
+fn withdraw_from_campaign<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo<'a>,
+    admin_account: &AccountInfo<'a>,
+    recipient_account: &AccountInfo<'a>,
+    treasury_account: &AccountInfo<'a>,
+    insurance_pool_account: &AccountInfo<'a>,
+    config: &PlatformConfig,
+    insurance_pool: &mut InsurancePool,
+    amount: Option<u64>,
+    from_queue: bool,
+) -> ProgramResult {
+    // we check if writing program is owned by program
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+    // Funds stay locked while someone has reported the campaign, the counter block tells
+    // before the campaign is decoded.
+    if campaign_counters(&writing_account.data.borrow())?.disputed != 0 {
+        log_event!(instruction, "Campaign is under dispute, withdraw is blocked");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    // Then we check if the admin_account's public key is equal to 
+    // the public key we have stored in our campaing_data.
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can withdraw");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if campaign_data.withdraw_delay_secs != 0 && !from_queue {
+        log_event!(instruction, "Withdrawals of this campaign are timelocked, use request_withdraw");
+        return Err(FundError::WithdrawNotMatured.into());
+    }
+
+    check_withdrawals_open(instruction, &campaign_data)?;
+    let failed = campaign_data.state == CampaignState::Failed;
+
+    // A campaign can only send funds to the addresses it was created with,
+    // so a stolen admin key can't send them anywhere else.
+    let allowed_recipient = if campaign_data.withdraw_recipients.is_empty() {
+        recipient_account.key == admin_account.key
+    } else {
+        campaign_data.withdraw_recipients.contains(recipient_account.key)
+    };
+    if !allowed_recipient {
+        log_event!(instruction, "{} isn't an allowed withdraw recipient", recipient_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    check_campaign_rent(instruction, writing_account)?;
+    let available = withdrawable_balance(writing_account, &campaign_data)?;
+    let amount = amount.unwrap_or(available);
+    if available < amount {
+        log_event!(instruction, "Insufficent balance");
+        return Err(insufficient_funds(amount, available));
+    }
+
+    // The donors of a failed campaign left this part to the admin whether the goal was reached or not.
+    if !failed {
+        check_withdraw_policy(instruction, &campaign_data)?;
+    }
+    check_withdraw_limits(instruction, &mut campaign_data, amount)?;
+    if failed {
+        campaign_data.failed_withdrawable -= amount;
+    }
+    let amount_donated = campaign_data.amount_donated;
+    for milestone in campaign_data.milestones.iter_mut() {
+        if milestone.amount <= amount_donated {
+            milestone.released = true;
+        }
+    }
+
+    // The fee is taken out of the withdrawn amount, and the insurance slice out of the fee.
+    // Fee exempt campaigns skip the treasury split entirely.
+    let fee_assessed_volume = campaign_data
+        .fee_assessed_volume
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let fee = if campaign_data.fee_exempt {
+        0
+    } else {
+        withdraw_fee(config, campaign_data.fee_assessed_volume, amount)
+    };
+    campaign_data.fee_assessed_volume = fee_assessed_volume;
+    let insurance = round_div(fee as u128 * config.insurance_bps as u128, 10_000, config.rounding) as u64;
+
+    // When fees are refundable the treasury share stays in the campaign until it succeeds,
+    // so it is still there to refund donors if the campaign fails.
+    let reserve_fees = config.refund_fees && campaign_data.end_timestamp != 0 && !failed;
+    if !reserve_fees && fee > insurance {
+        check_fee_collector(instruction, treasury_account)?;
+    }
+
+    log_phase!(instruction, "transfer");
+    // Transfer balance
+    // we will decrease the balance of the program account, and increase the admin_account balance.
+    // The debits and credits below have to add up: the runtime fails any instruction that changes
+    // the total lamports of its accounts (UnbalancedInstruction), so a wrong sum can't mint or burn any.
+    **writing_account.try_borrow_mut_lamports()? -= amount; //  we can only decrease the balance of a program-owned account.
+    **recipient_account.try_borrow_mut_lamports()? += amount - fee;
+    **insurance_pool_account.try_borrow_mut_lamports()? += insurance;
+
+    if reserve_fees {
+        **writing_account.try_borrow_mut_lamports()? += fee - insurance;
+        campaign_data.fees_reserved += fee - insurance;
+    } else {
+        **treasury_account.try_borrow_mut_lamports()? += fee - insurance;
+    }
+
+    log_phase!(instruction, "serialize");
+    insurance_pool.total_in = insurance_pool
+        .total_in
+        .checked_add(insurance)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Fees reach the treasury as small lamport credits. A treasury below rent exemption could be
+// reaped together with the fees it collected, so it has to be rent exempt before it gets any.
+fn check_fee_collector(instruction: Instruction, treasury_account: &AccountInfo) -> ProgramResult {
+    let rent = Rent::get()?;
+    if !rent.is_exempt(treasury_account.lamports(), treasury_account.data_len()) {
+        log_event!(
+            instruction,
+            "Treasury has {} lamports, it needs {} to be rent exempt before collecting fees",
+            treasury_account.lamports(),
+            rent.minimum_balance(treasury_account.data_len())
+        );
+        return Err(FundError::FeeCollectorNotRentExempt.into());
+    }
+    Ok(())
+}
+
+// What is left in a failed campaign belongs to the donors, except what covers
+// the non-refundable donations.
+fn check_withdrawals_open(instruction: Instruction, campaign_data: &CampaignDetails) -> ProgramResult {
+    let failed = campaign_data.state == CampaignState::Failed;
+    if (failed && campaign_data.failed_withdrawable == 0) || campaign_data.state == CampaignState::Liquidated {
+        log_event!(instruction, "Campaign failed, funds can only be refunded");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// Lamports kept in a campaign on top of its rent exemption, so a small rent change later
+// can't leave it below the minimum and get it reaped.
+const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+
+// What a campaign account of `data_len` bytes has to hold at creation and keep after withdrawals.
+fn campaign_min_balance(data_len: usize) -> Result<u64, ProgramError> {
+    Ok(Rent::get()?.minimum_balance(data_len) + RENT_BUFFER_LAMPORTS)
+}
+
+// What the admin could take out of the campaign right now. Every part of the balance that isn't
+// theirs is held back here and nowhere else, so withdraw, withdraw_many, request_withdraw,
+// redirect_funds and get_withdrawable always agree: the rent and its buffer, the reserved fees,
+// pending donations their donors can still reclaim, and then either what the donors of a failed
+// campaign left to the admin or what the milestones haven't unlocked yet. Disputed and liquidated
+// campaigns have nothing to withdraw.
+fn withdrawable_balance(writing_account: &AccountInfo, campaign_data: &CampaignDetails) -> Result<u64, ProgramError> {
+    if campaign_data.disputed || campaign_data.state == CampaignState::Liquidated {
+        return Ok(0);
+    }
+    let mut available = writing_account
+        .lamports()
+        .saturating_sub(campaign_min_balance(writing_account.data_len())?)
+        .saturating_sub(campaign_data.fees_reserved)
+        .saturating_sub(campaign_data.pending_amount());
+    if campaign_data.state == CampaignState::Failed {
+        available = available.min(campaign_data.failed_withdrawable);
+    } else {
+        available = available.saturating_sub(campaign_data.milestone_locked());
+    }
+    Ok(available)
+}
+
+// The rent can go up after a campaign was funded, and then its balance is below what
+// `campaign_min_balance` asks for. Nothing can be withdrawn until someone tops it up, and the admin
+// gets told that instead of an insufficient balance, with the required and available lamports as context.
+fn check_campaign_rent(instruction: Instruction, writing_account: &AccountInfo) -> ProgramResult {
+    let required = campaign_min_balance(writing_account.data_len())?;
+    let available = writing_account.lamports();
+    if available < required {
+        log_event!(instruction, "Campaign holds {} lamports, it needs {} to stay rent exempt", available, required);
+        return Err(error_with_context(FundError::NotRentExempt.into(), &[required, available]));
+    }
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateRequest {
+    // How much of the staged lamports to donate.
+    pub amount: u64,
+    // Empties the staging account, what wasn't donated goes back to the donator. Without it the rest
+    // stays staged for a later donation, and the staging account has to stay rent exempt.
+    pub close_after: bool,
+    // Seed the staging account was created with, see `check_staging_account`.
+    pub seed: String,
+}
+
+// Legacy escrow path: the donator first funds a staging account owned by the program,
+// this instruction drains it into the campaign.
+//
+// Migration: new clients use `donate_direct`, which moves the lamports with a system transfer
+// and needs no staging account. A new config starts with `legacy_donate_disabled` set and this
+// path fails with LegacyPathDisabled, so a client still staging lamports gets a specific error
+// before anything is moved. The authority only turns it back on while old clients move over,
+// and even then a staging account has to pass `check_staging_account`.
+fn donate(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::Donate;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donator_program_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    // PDA keeping track of how much this donator gave, it's created on the first donation.
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    // Tells if this path is still enabled.
+    let config_account = next_account_info(accounts_iter)?;
+    // Receipt PDA of this donation, see `create_donation_receipt`.
+    let receipt_account = next_account_info(accounts_iter)?;
+    // Blocklist PDA of the donator on this campaign, see `check_donor_not_blocked`.
+    let blocklist_account = next_account_info(accounts_iter)?;
+    // Counts the donation in the campaign's category.
+    let global_stats_account = next_account_info(accounts_iter)?;
+    // Optionally a sponsor's matching commitment for this campaign and the sponsor account,
+    // when they are passed the sponsor matches the donation.
+    let match_accounts = match (accounts_iter.next(), accounts_iter.next()) {
+        (Some(match_commitment_account), Some(sponsor_account)) => Some((match_commitment_account, sponsor_account)),
+        _ => None,
+    };
+
+    let config = load_config(instruction, program_id, config_account)?;
+    check_not_sunset(instruction, &config)?;
+    if config.legacy_donate_disabled {
+        log_event!(instruction, "Donating through a staging account is disabled, use donate_direct");
+        return Err(FundError::LegacyPathDisabled.into());
+    }
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing_account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    check_counters_can_donate(instruction, campaign_counters(&writing_account.data.borrow())?)?;
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    check_can_donate(instruction, &campaign_data)?;
+    check_donor_not_blocked(instruction, program_id, writing_account, donator, blocklist_account)?;
+
+    let staged = **donator_program_account.lamports.borrow();
+    if staged == 0 {
+        log_event!(instruction, "Nothing is staged, it was donated or reclaimed already");
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // Older clients sent no data at all, or only the amount. Neither says which wallet the staging
+    // account belongs to, so they get a specific error instead of having their lamports moved.
+    if instruction_data.is_empty() || instruction_data.len() == 8 {
+        log_event!(instruction, "donate needs a DonateRequest with the staging account's seed");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let input_data: DonateRequest = parse_instruction(instruction, instruction_data)?;
+    check_staging_account(instruction, program_id, donator_program_account, donator, &input_data.seed)?;
+    if input_data.amount == 0 {
+        log_event!(instruction, "Donation amount should be more then zero");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if input_data.amount > staged {
+        log_event!(instruction, "Donation amount is more then the staged balance");
+        return Err(insufficient_funds(input_data.amount, staged));
+    }
+    // A staging account that stays open must keep its rent, or the runtime reaps it with the
+    // lamports still staged in it.
+    if !input_data.close_after {
+        let rent_exemption = Rent::get()?.minimum_balance(donator_program_account.data_len());
+        let max_donation = staged.saturating_sub(rent_exemption);
+        if input_data.amount > max_donation {
+            log_event!(
+                instruction,
+                "At most {} lamports can be donated while the staging account stays open",
+                max_donation
+            );
+            return Err(insufficient_funds(input_data.amount, max_donation));
+        }
+    }
+    let close_after = input_data.close_after;
+    let requested = input_data.amount;
+    check_donation_size(instruction, &config, &campaign_data, requested)?;
+    let amount = accepted_donation(instruction, &campaign_data, requested)?;
+
+    log_phase!(instruction, "cpi");
+    let mut donation_record = load_or_create_donation_record(
+        instruction,
+        program_id,
+        writing_account,
+        donator,
+        donator,
+        donation_record_account,
+        system_program,
+    )?;
+    create_donation_receipt(
+        instruction,
+        program_id,
+        writing_account,
+        &mut campaign_data,
+        donator,
+        receipt_account,
+        system_program,
+        amount,
+    )?;
+
+    // A closed staging account is emptied, what wasn't donated goes back to the donator.
+    **writing_account.try_borrow_mut_lamports()? += amount;
+    if close_after {
+        **donator.try_borrow_mut_lamports()? += staged - amount;
+        **donator_program_account.try_borrow_mut_lamports()? = 0;
+    } else {
+        **donator_program_account.try_borrow_mut_lamports()? -= amount;
+    }
+
+    log_phase!(instruction, "serialize");
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    record_donation(
+        instruction,
+        program_id,
+        writing_account,
+        &mut campaign_data,
+        &mut global_stats,
+        donation_record_account,
+        &mut donation_record,
+        match_accounts,
+        &config,
+        amount,
+        requested,
+        false,
+    )?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Legacy clients created the staging account with `create_account_with_seed`, the donator's
+// wallet as the base and a one byte data. Nothing else the program owns is that small.
+const STAGING_ACCOUNT_LEN: usize = 1;
+
+// Every other account the program owns (campaigns, the config, the pools, vouchers...) holds
+// lamports that aren't the donator's, so a staging account is only taken as one when it is that
+// small and its address derives from the donator's wallet and the seed. Checked before any
+// lamports of it move.
+fn check_staging_account(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    donator_program_account: &AccountInfo,
+    donator: &AccountInfo,
+    seed: &str,
+) -> ProgramResult {
+    if donator_program_account.owner != program_id {
+        log_event!(instruction, "donator_program_account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if donator_program_account.data_len() != STAGING_ACCOUNT_LEN {
+        log_event!(instruction, "donator_program_account isn't a staging account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let staging_key = Pubkey::create_with_seed(donator.key, seed, program_id)?;
+    if staging_key != *donator_program_account.key {
+        log_event!(instruction, "Staging account wasn't created from this donator's wallet");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ReclaimStagedRequest {
+    // Seed the staging account was created with.
+    pub seed: String,
+}
+
+// A donator gets back what they staged when the donate transaction never landed. The seed proves
+// the staging account was created from their wallet. Whichever of this and a `donate` with
+// `close_after` runs first empties the account, the other one finds nothing staged.
+fn reclaim_staged(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ReclaimStaged;
+    let accounts_iter = &mut accounts.iter();
+    let donator_program_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: ReclaimStagedRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    check_staging_account(instruction, program_id, donator_program_account, donator, &input_data.seed)?;
+
+    let staged = donator_program_account.lamports();
+    log_phase!(instruction, "cpi");
+    **donator.try_borrow_mut_lamports()? += staged;
+    **donator_program_account.try_borrow_mut_lamports()? = 0;
+    log_event!(instruction, "Reclaimed {} staged lamports", staged);
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateDirectRequest {
+    pub amount: u64,
+    // Keep the donation in the campaign even if it fails, see DonationRecord.
+    pub non_refundable: bool,
+    // Optional tip to the platform on top of the donation, in basis points of the donated amount.
+    pub tip_bps: u16,
+    // A fixed tip in lamports, added to the tip_bps part.
+    pub tip_lamports: u64,
+}
+
+// Donates straight from the donator's wallet with a system transfer, there is no staging account.
+// Only the accepted amount is transferred, so what the hard cap cuts off never leaves the wallet.
+// There are no recurring pledges: every donation is one transfer the donor signs, so nothing
+// keeps pulling from a wallet and there is no pledge to cancel.
+fn donate_direct(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::DonateDirect;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+    let blocklist_account = next_account_info(accounts_iter)?;
+    // The config tells whether the program is winding down. The tip goes to the platform
+    // treasury, which is only checked when there is a tip. The global stats count the donation
+    // in the campaign's category, and the tip.
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+    let match_accounts = match (accounts_iter.next(), accounts_iter.next()) {
+        (Some(match_commitment_account), Some(sponsor_account)) => Some((match_commitment_account, sponsor_account)),
+        _ => None,
+    };
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing_account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: DonateDirectRequest = parse_instruction(instruction, instruction_data)?;
+    if input_data.amount == 0 {
+        log_event!(instruction, "Donation amount should be more then zero");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if input_data.tip_bps > 10_000 {
+        log_event!(instruction, "tip_bps can't be more then 10000");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    check_counters_can_donate(instruction, campaign_counters(&writing_account.data.borrow())?)?;
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    let config = load_config(instruction, program_id, config_account)?;
+    check_not_sunset(instruction, &config)?;
+    check_can_donate(instruction, &campaign_data)?;
+    check_donor_not_blocked(instruction, program_id, writing_account, donator, blocklist_account)?;
+    check_donation_size(instruction, &config, &campaign_data, input_data.amount)?;
+    let amount = accepted_donation(instruction, &campaign_data, input_data.amount)?;
+
+    // The tip is on top of what the campaign accepted, the campaign always gets the base amount.
+    // It never counts in amount_donated or the donation record, so refunds never pay it back.
+    let tip = (round_div(amount as u128 * input_data.tip_bps as u128, 10_000, config.rounding) as u64)
+        .checked_add(input_data.tip_lamports)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if tip > 0 {
+        if config.treasury != *treasury_account.key {
+            log_event!(instruction, "treasury account isn't the platform treasury");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_fee_collector(instruction, treasury_account)?;
+    }
+
+    log_phase!(instruction, "cpi");
+    let mut donation_record = load_or_create_donation_record(
+        instruction,
+        program_id,
+        writing_account,
+        donator,
+        donator,
+        donation_record_account,
+        system_program,
+    )?;
+    create_donation_receipt(
+        instruction,
+        program_id,
+        writing_account,
+        &mut campaign_data,
+        donator,
+        receipt_account,
+        system_program,
+        amount,
+    )?;
+
+    invoke(
+        &system_instruction::transfer(donator.key, writing_account.key, amount),
+        &[donator.clone(), writing_account.clone(), system_program.clone()],
+    )?;
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    if tip > 0 {
+        invoke(
+            &system_instruction::transfer(donator.key, treasury_account.key, tip),
+            &[donator.clone(), treasury_account.clone(), system_program.clone()],
+        )?;
+        log_event!(instruction, "Tipped the platform {} lamports", tip);
+
+        global_stats.total_tips = global_stats
+            .total_tips
+            .checked_add(tip)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+    }
+    // Tips are voluntary, a loyal donor tipping over the discounted suggestion is fine. The discount
+    // they were entitled to goes in the event so analytics can check what the front-end suggested.
+    let donated_before = if donation_record.cycle == campaign_data.cycle { donation_record.amount } else { 0 };
+    let tip_discount = if tip > 0 { tip_discount_bps(&config, donated_before) } else { 0 };
+    if tip_discount > 0 {
+        log_event!(
+            instruction,
+            "Loyalty discount of {} bps, suggested tip is {} bps",
+            tip_discount,
+            discounted_tip_bps(&config, tip_discount)
+        );
+    }
+    sol_log_data(&[
+        b"donation",
+        writing_account.key.as_ref(),
+        donator.key.as_ref(),
+        &amount.to_le_bytes(),
+        &tip.to_le_bytes(),
+        &tip_discount.to_le_bytes(),
+    ]);
+
+    log_phase!(instruction, "serialize");
+    record_donation(
+        instruction,
+        program_id,
+        writing_account,
+        &mut campaign_data,
+        &mut global_stats,
+        donation_record_account,
+        &mut donation_record,
+        match_accounts,
+        &config,
+        amount,
+        input_data.amount,
+        input_data.non_refundable,
+    )?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Common end of both donate paths, once the lamports are in the campaign: credits the donation,
+// lets a sponsor match it and saves the campaign and the record. The caller saves the global stats.
+#[allow(clippy::too_many_arguments)]
+fn record_donation<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo<'a>,
+    campaign_data: &mut CampaignDetails,
+    global_stats: &mut GlobalStats,
+    donation_record_account: &AccountInfo<'a>,
+    donation_record: &mut DonationRecord,
+    match_accounts: Option<(&AccountInfo<'a>, &AccountInfo<'a>)>,
+    config: &PlatformConfig,
+    amount: u64,
+    requested: u64,
+    non_refundable: bool,
+) -> ProgramResult {
+    credit_donation(instruction, config, campaign_data, global_stats, donation_record, amount, non_refundable)?;
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+
+    if let Some((match_commitment_account, sponsor_account)) = match_accounts {
+        apply_match(
+            instruction,
+            program_id,
+            writing_account,
+            campaign_data,
+            global_stats,
+            match_commitment_account,
+            sponsor_account,
+            amount,
+            config.rounding,
+        )?;
+    }
+
+    // Clients can tell from the return data whether the donation was cut down by the cap.
+    log_event!(instruction, "Donation accepted {} of {} requested lamports", amount, requested);
+    let mut return_data = [0u8; 16];
+    return_data[..8].copy_from_slice(&amount.to_le_bytes());
+    return_data[8..].copy_from_slice(&requested.to_le_bytes());
+    set_return_data(&return_data);
+
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn check_donation_size(
+    instruction: Instruction,
+    config: &PlatformConfig,
+    campaign_data: &CampaignDetails,
+    amount: u64,
+) -> ProgramResult {
+    if amount < config.min_donation_lamports {
+        log_event!(instruction, "A donation should be at least {} lamports", config.min_donation_lamports);
+        return Err(FundError::DonationTooSmall.into());
+    }
+    if campaign_data.max_donation_per_tx != 0 && amount > campaign_data.max_donation_per_tx {
+        log_event!(instruction, "A single donation can be at most {} lamports", campaign_data.max_donation_per_tx);
+        return Err(FundError::DonationTooLarge.into());
+    }
+    Ok(())
+}
+
+// How much of a `requested` donation the campaign takes with its hard cap.
+fn accepted_donation(
+    instruction: Instruction,
+    campaign_data: &CampaignDetails,
+    requested: u64,
+) -> Result<u64, ProgramError> {
+    if campaign_data.hard_cap == 0 {
+        return Ok(requested);
+    }
+    let room = campaign_data
+        .hard_cap
+        .saturating_sub(campaign_data.amount_donated)
+        .saturating_sub(campaign_data.pending_amount());
+    if requested <= room {
+        Ok(requested)
+    } else if campaign_data.fill_to_cap && room > 0 {
+        Ok(room)
+    } else {
+        log_event!(instruction, "Donation would cross the hard cap, only {} lamports are left", room);
+        Err(FundError::HardCapReached.into())
+    }
+}
+
+// Checks that the campaign can take a donation right now.
+// A GoalOnly campaign that misses its goal only gives the donors their refunds once it fails,
+// and only a campaign with an end date can fail. Without one the donations would be locked for good.
+fn check_goal_only_deadline(
+    instruction: Instruction,
+    withdraw_policy: &WithdrawPolicy,
+    end_timestamp: i64,
+) -> ProgramResult {
+    if *withdraw_policy == WithdrawPolicy::GoalOnly && end_timestamp <= 0 {
+        log_event!(instruction, "A GoalOnly campaign needs an end_timestamp so donors can get refunds");
+        return Err(FundError::RefundRequiresDeadline.into());
+    }
+    Ok(())
+}
+
+// Campaigns with a tiny goal succeed for next to nothing and pad the successful campaign stats,
+// so every goal a campaign gets is checked against the config's minimum. A GoalOnly campaign
+// needs a goal even when there is no minimum, with a zero goal it succeeds without raising anything.
+fn check_min_goal(
+    instruction: Instruction,
+    config: &PlatformConfig,
+    withdraw_policy: &WithdrawPolicy,
+    goal: u64,
+) -> ProgramResult {
+    if *withdraw_policy == WithdrawPolicy::GoalOnly && goal == 0 {
+        log_event!(instruction, "A GoalOnly campaign needs a goal");
+        return Err(FundError::GoalBelowMinimum.into());
+    }
+    if goal < config.min_goal_lamports {
+        log_event!(instruction, "goal should be at least {} lamports", config.min_goal_lamports);
+        return Err(FundError::GoalBelowMinimum.into());
+    }
+    Ok(())
+}
+
+fn check_withdraw_policy(instruction: Instruction, campaign_data: &CampaignDetails) -> ProgramResult {
+    if campaign_data.withdraw_policy == WithdrawPolicy::GoalOnly
+        && campaign_data.amount_donated < campaign_data.funded_threshold()
+    {
+        log_event!(
+            instruction,
+            "Withdrawals unlock at {} lamports raised, {} raised so far",
+            campaign_data.funded_threshold(),
+            campaign_data.amount_donated
+        );
+        return Err(FundError::GoalNotReached.into());
+    }
+    if campaign_data.withdraw_policy == WithdrawPolicy::GoalOnly && !campaign_data.donor_count_reached() {
+        log_event!(
+            instruction,
+            "Withdrawals unlock at {} donors, {} so far",
+            campaign_data.max_donors,
+            campaign_data.donor_count
+        );
+        return Err(FundError::GoalNotReached.into());
+    }
+    Ok(())
+}
+
+// Enforces the campaign's withdraw cooldown and epoch limit for `amount` leaving it,
+// and records it against both.
+fn check_withdraw_limits(instruction: Instruction, campaign_data: &mut CampaignDetails, amount: u64) -> ProgramResult {
+    let now = Clock::get()?.unix_timestamp;
+
+    // Campaigns can ask for a minimum time between two withdrawals.
+    if campaign_data.withdraw_cooldown_secs != 0 && campaign_data.last_withdraw_ts != 0 {
+        let elapsed = (now - campaign_data.last_withdraw_ts).max(0) as u64;
+        if elapsed < campaign_data.withdraw_cooldown_secs {
+            log_event!(
+                instruction,
+                "Withdraw cooldown active, {} seconds left",
+                campaign_data.withdraw_cooldown_secs - elapsed
+            );
+            return Err(FundError::WithdrawCooldownActive.into());
+        }
+    }
+    campaign_data.last_withdraw_ts = now;
+
+    // Campaigns can limit how much leaves them during one epoch.
+    if campaign_data.max_withdraw_per_epoch != 0 {
+        let epoch = Clock::get()?.epoch;
+        if campaign_data.withdraw_epoch != epoch {
+            campaign_data.withdraw_epoch = epoch;
+            campaign_data.withdrawn_this_epoch = 0;
+        }
+        let withdrawn = campaign_data
+            .withdrawn_this_epoch
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidInstructionData)?;
+        if withdrawn > campaign_data.max_withdraw_per_epoch {
+            log_event!(
+                instruction,
+                "Epoch withdraw limit reached, {} of {} already withdrawn this epoch",
+                campaign_data.withdrawn_this_epoch,
+                campaign_data.max_withdraw_per_epoch
+            );
+            return Err(FundError::EpochWithdrawLimitReached.into());
+        }
+        campaign_data.withdrawn_this_epoch = withdrawn;
+    }
+    Ok(())
+}
+
+// An admin pause with a `pause_until` in the past counts as resumed, nobody has to clear it.
+fn admin_pause_active(admin_paused: bool, pause_until: i64, now: i64) -> bool {
+    admin_paused && (pause_until == 0 || now < pause_until)
+}
+
+fn check_can_donate(instruction: Instruction, campaign_data: &CampaignDetails) -> ProgramResult {
+    // The client creates the campaign account before create_campaign fills it. Zeroed data reads as
+    // an active campaign, but it has no admin to ever withdraw or refund what it is given.
+    if campaign_data.admin == Pubkey::default() {
+        log_event!(instruction, "Campaign account was never initialized");
+        return Err(FundError::CampaignNotInitialized.into());
+    }
+    if campaign_data.archived {
+        log_event!(instruction, "Campaign is archived");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if admin_pause_active(campaign_data.admin_paused, campaign_data.pause_until, Clock::get()?.unix_timestamp) {
+        log_event!(instruction, "Campaign is paused by its admin until {}", campaign_data.pause_until);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_counters_can_donate(instruction, &CampaignCounters::from(campaign_data))
+}
+
+// The part of `check_can_donate` the counter block answers. The donate paths run it on the
+// account data before decoding the campaign, so a donation to a paused, closed or ended campaign
+// fails without paying for the Borsh decode.
+fn check_counters_can_donate(instruction: Instruction, counters: &CampaignCounters) -> ProgramResult {
+    if counters.donations_paused != 0 {
+        log_event!(instruction, "Donations to this campaign are paused");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if counters.state != CampaignState::Active as u8 {
+        log_event!(instruction, "Campaign isn't active anymore");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let end_timestamp = counters.end_timestamp;
+    if end_timestamp != 0 && Clock::get()?.unix_timestamp >= end_timestamp {
+        log_event!(instruction, "Campaign has ended");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// Window a new config starts with: a campaign takes at most MAX_DONATIONS_PER_WINDOW donations
+// every DONATION_WINDOW_SECS. Bursts of tiny donations are how wash funding shows up, this slows
+// them down without getting in the way of a normal campaign.
+const MAX_DONATIONS_PER_WINDOW: u32 = 50;
+const DONATION_WINDOW_SECS: u64 = 60;
+
+// Counts a donation against the campaign's current window, starting a new one when it has elapsed.
+fn check_donation_velocity(
+    instruction: Instruction,
+    config: &PlatformConfig,
+    campaign_data: &mut CampaignDetails,
+) -> ProgramResult {
+    if config.max_donations_per_window == 0 || config.donation_window_secs == 0 {
+        return Ok(());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now >= campaign_data.donation_window_start.saturating_add(config.donation_window_secs as i64) {
+        campaign_data.donation_window_start = now;
+        campaign_data.donations_in_window = 0;
+    }
+    if campaign_data.donations_in_window >= config.max_donations_per_window {
+        log_event!(
+            instruction,
+            "Campaign took {} donations in the last {} seconds, try again later",
+            campaign_data.donations_in_window,
+            config.donation_window_secs
+        );
+        return Err(FundError::DonationVelocityExceeded.into());
+    }
+    campaign_data.donations_in_window += 1;
+    Ok(())
+}
+
+// Adds `amount` to the campaign totals and to the donor's record.
+// Moving the lamports is left to the caller.
+// Every donation writes the campaign account, so the runtime never runs two of them on the same
+// campaign at once, even in the same slot. Each one reads the totals the previous one saved.
+#[allow(clippy::too_many_arguments)]
+fn credit_donation(
+    instruction: Instruction,
+    config: &PlatformConfig,
+    campaign_data: &mut CampaignDetails,
+    global_stats: &mut GlobalStats,
+    donation_record: &mut DonationRecord,
+    amount: u64,
+    non_refundable: bool,
+) -> ProgramResult {
+    // A record left over from an earlier cycle starts again from zero.
+    if donation_record.cycle != campaign_data.cycle {
+        donation_record.cycle = campaign_data.cycle;
+        donation_record.amount = 0;
+        donation_record.refunded = false;
+        donation_record.insurance_paid = 0;
+        donation_record.refund_paid = 0;
+        donation_record.non_refundable = false;
+    }
+    if donation_record.amount > 0 && donation_record.non_refundable != non_refundable {
+        log_event!(instruction, "A donor can't mix refundable and non-refundable donations to a campaign");
+        return Err(FundError::MixedRefundability.into());
+    }
+    check_donation_velocity(instruction, config, campaign_data)?;
+    if donation_record.amount == 0 && amount > 0 {
+        if campaign_data.max_donors != 0 && campaign_data.donor_count >= campaign_data.max_donors {
+            log_event!(instruction, "Campaign already has its {} donors", campaign_data.max_donors);
+            return Err(FundError::DonorLimitReached.into());
+        }
+        campaign_data.donor_count += 1;
+    }
+    donation_record.non_refundable = non_refundable;
+    donation_record.amount += amount;
+    if campaign_data.confirmation_slots == 0 {
+        campaign_data.amount_donated += amount;
+    } else {
+        let slot = Clock::get()?.slot;
+        confirm_pending_donations(campaign_data, slot, false);
+        // Refusing donations once the queue is full would let anyone block the campaign with a few
+        // small ones, the oldest pending donation counts early instead.
+        if campaign_data.pending_donations.len() >= MAX_PENDING_DONATIONS {
+            let oldest = campaign_data.pending_donations.remove(0);
+            log_event!(instruction, "Pending donations are full, counting {} lamports early", oldest.amount);
+            campaign_data.amount_donated = campaign_data
+                .amount_donated
+                .checked_add(oldest.amount)
+                .ok_or(ProgramError::InvalidAccountData)?;
+        }
+        campaign_data.pending_donations.push(PendingDonation {
+            donor: donation_record.donor,
+            amount,
+            slot,
+        });
+    }
+    if non_refundable {
+        campaign_data.non_refundable_total += amount;
+    }
+    count_raised(global_stats, campaign_data.category, amount)
+}
+
+// The platform config lives in a PDA derived from `CONFIG_SEED` alone, so there is exactly one of it
+// and nobody can pass us a look-alike account they created themselves.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct PlatformConfig {
+    // Account allowed to change the config.
+    pub authority: Pubkey,
+    // Account allowed to open and resolve disputes on campaigns.
+    pub dispute_resolver: Pubkey,
+    // Account receiving the platform fees.
+    pub treasury: Pubkey,
+    // Fee taken on every withdraw, in basis points of the withdrawn amount.
+    pub fee_bps: u16,
+    // Part of the fee going to the insurance pool instead of the treasury, in basis points of the fee.
+    pub insurance_bps: u16,
+    // Flat fee in lamports paid when a campaign is created.
+    pub creation_fee: u64,
+    // When set, withdraw fees of campaigns with an end date are reserved in the campaign
+    // and only paid to the treasury once it succeeds. If it fails they are refunded to the donors.
+    pub refund_fees: bool,
+    // How long donors of a failed campaign have to claim their refunds, 0 means forever.
+    pub refund_window_secs: u64,
+    // Turns the staging account donate path off, clients use donate_direct. Set in a new config,
+    // the authority can clear it for old clients.
+    pub legacy_donate_disabled: bool,
+    // Marginal fee tiers, like tax brackets, in increasing `up_to_amount` order.
+    // When there are none the flat `fee_bps` applies.
+    pub fee_tiers: Vec<FeeTier>,
+    // Programs allowed to invoke the guarded instructions through CPI, see the `guards` module.
+    pub cpi_callers: Vec<Pubkey>,
+    // Set by the authority to wind the program down before a successor replaces it: no new
+    // campaigns or donations, withdrawals, refunds and closes keep working.
+    pub sunset: bool,
+    // How fees, their insurance slice, matches and tips are rounded to whole lamports.
+    pub rounding: Rounding,
+    // Tip the front-ends suggest, in basis points of the donation. 0 means no suggestion.
+    pub suggested_tip_bps: u16,
+    // Donors who gave a campaign at least this much get `loyalty_discount_bps` off the suggested
+    // tip on their next donations to it. 0 turns the discount off.
+    pub loyalty_min_donated: u64,
+    pub loyalty_discount_bps: u16,
+    // Price of featuring a campaign, in lamports per day. 0 means campaigns can't be featured.
+    pub feature_fee_per_day: u64,
+    // Smallest goal a campaign can have, see `check_min_goal`. 0 turns the check off.
+    pub min_goal_lamports: u64,
+    // Smallest donation a campaign takes, on every donate path. 0 turns the floor off.
+    pub min_donation_lamports: u64,
+    // A campaign takes at most `max_donations_per_window` donations every `donation_window_secs`,
+    // see `check_donation_velocity`. 0 in either turns the limit off.
+    pub max_donations_per_window: u32,
+    pub donation_window_secs: u64,
+}
+
+// Rounding of a share computed in basis points. Every share is taken out of a known total and
+// the rest of the total is what's left of it, so no policy creates or loses lamports.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Copy, PartialEq, Default)]
+pub enum Rounding {
+    #[default]
+    Floor,
+    Ceil,
+    // Halves round up.
+    Nearest,
+}
+
+// Discount off the suggested tip for a donor who already gave the campaign `donated`.
+// There are no platform wide donor totals, so loyalty is counted per campaign with the donor's
+// record, and it restarts with the record when the campaign renews.
+fn tip_discount_bps(config: &PlatformConfig, donated: u64) -> u16 {
+    if config.loyalty_min_donated > 0 && donated >= config.loyalty_min_donated {
+        config.loyalty_discount_bps
+    } else {
+        0
+    }
+}
+
+// The suggested tip once `discount_bps` is taken off it.
+fn discounted_tip_bps(config: &PlatformConfig, discount_bps: u16) -> u16 {
+    (config.suggested_tip_bps as u32 * (10_000 - discount_bps as u32) / 10_000) as u16
+}
+
+fn round_div(numerator: u128, denominator: u128, rounding: Rounding) -> u128 {
+    match rounding {
+        Rounding::Floor => numerator / denominator,
+        Rounding::Ceil => numerator.div_ceil(denominator),
+        Rounding::Nearest => (numerator + denominator / 2) / denominator,
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct FeeTier {
+    // This tier applies to the part of a campaign's total withdrawals up to this amount.
+    // Whatever goes over the last tier pays the last tier's rate.
+    pub up_to_amount: u64,
+    pub fee_bps: u16,
+}
+
+const MAX_FEE_TIERS: usize = 4;
+const MAX_CPI_CALLERS: usize = 4;
+
+// Size of PlatformConfig with room for all the fee tiers and CPI callers, it's read with
+// `deserialize` because the lists don't always fill the account.
+fn platform_config_len() -> usize {
+    serialized_len::<PlatformConfig>() + MAX_FEE_TIERS * serialized_len::<FeeTier>() + MAX_CPI_CALLERS * 32
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct InitializeConfigRequest {
+    pub dispute_resolver: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_bps: u16,
+    pub insurance_bps: u16,
+    pub creation_fee: u64,
+    pub refund_fees: bool,
+    pub refund_window_secs: u64,
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+fn initialize_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::InitializeConfig;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    // Whoever initializes the config becomes its authority and pays for the account.
+    let authority_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    // The insurance pool and the global stats are created together with the config.
+    let insurance_pool_account = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+
+    if !authority_account.is_signer {
+        log_event!(instruction, "authority should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (config_key, bump) = find_config_address(program_id);
+    if config_key != *config_account.key {
+        log_event!(instruction, "config account isn't the config PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    // The config can only be created once.
+    if config_account.owner == program_id {
+        log_event!(instruction, "config is already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: InitializeConfigRequest = parse_instruction(instruction, instruction_data)?;
+
+    log_phase!(instruction, "validate");
+    if input_data.fee_bps > 10_000 || input_data.insurance_bps > 10_000 {
+        log_event!(instruction, "fee_bps and insurance_bps can't be more then 10000");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_entries(instruction, input_data.fee_tiers.len(), MAX_FEE_TIERS, "fee tiers")?;
+    let mut previous_up_to = 0;
+    for tier in input_data.fee_tiers.iter() {
+        if tier.fee_bps > 10_000 || tier.up_to_amount <= previous_up_to {
+            log_event!(instruction, "Fee tiers should have increasing amounts and fee_bps up to 10000");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        previous_up_to = tier.up_to_amount;
+    }
+
+    let (insurance_pool_key, insurance_pool_bump) = find_insurance_pool_address(program_id);
+    if insurance_pool_key != *insurance_pool_account.key {
+        log_event!(instruction, "insurance pool account isn't the insurance pool PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let (global_stats_key, global_stats_bump) = find_global_stats_address(program_id);
+    if global_stats_key != *global_stats_account.key {
+        log_event!(instruction, "global stats account isn't the global stats PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        authority_account,
+        config_account,
+        system_program,
+        platform_config_len(),
+        &[CONFIG_SEED, &[bump]],
+    )?;
+    create_pda_account(
+        instruction,
+        program_id,
+        authority_account,
+        insurance_pool_account,
+        system_program,
+        serialized_len::<InsurancePool>(),
+        &[INSURANCE_POOL_SEED, &[insurance_pool_bump]],
+    )?;
+    create_pda_account(
+        instruction,
+        program_id,
+        authority_account,
+        global_stats_account,
+        system_program,
+        serialized_len::<GlobalStats>(),
+        &[GLOBAL_STATS_SEED, &[global_stats_bump]],
+    )?;
+
+    let config = PlatformConfig {
+        authority: *authority_account.key,
+        dispute_resolver: input_data.dispute_resolver,
+        treasury: input_data.treasury,
+        fee_bps: input_data.fee_bps,
+        insurance_bps: input_data.insurance_bps,
+        creation_fee: input_data.creation_fee,
+        refund_fees: input_data.refund_fees,
+        refund_window_secs: input_data.refund_window_secs,
+        legacy_donate_disabled: true,
+        fee_tiers: input_data.fee_tiers,
+        cpi_callers: Vec::new(),
+        sunset: false,
+        rounding: Rounding::Floor,
+        suggested_tip_bps: 0,
+        loyalty_min_donated: 0,
+        loyalty_discount_bps: 0,
+        feature_fee_per_day: 0,
+        min_goal_lamports: 0,
+        min_donation_lamports: 0,
+        max_donations_per_window: MAX_DONATIONS_PER_WINDOW,
+        donation_window_secs: DONATION_WINDOW_SECS,
+    };
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+
+    let insurance_pool = InsurancePool {
+        total_in: 0,
+        total_paid: 0,
+    };
+    insurance_pool.serialize(&mut &mut insurance_pool_account.data.borrow_mut()[..])?;
+
+    // Campaign ids start at 1, 0 is left for campaigns created before there were ids.
+    let global_stats = GlobalStats {
+        next_campaign_id: 1,
+        total_tips: 0,
+        categories: Default::default(),
+    };
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Reads the platform config and makes sure it really is the config PDA.
+fn load_config(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+) -> Result<PlatformConfig, ProgramError> {
+    let (config_key, _) = find_config_address(program_id);
+    if config_key != *config_account.key || config_account.owner != program_id {
+        log_event!(instruction, "config account isn't the config PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let config = deserialize_padded::<PlatformConfig>(instruction, &config_account.data.borrow())?;
+    Ok(config)
+}
+
+// Fee owed on everything a campaign withdrew so far, multiplied by 10000.
+// Each tier's rate applies to the part of `volume` that falls in the tier.
+fn tiered_fee_scaled(config: &PlatformConfig, volume: u64) -> u128 {
+    if config.fee_tiers.is_empty() {
+        return volume as u128 * config.fee_bps as u128;
+    }
+    let mut fee: u128 = 0;
+    let mut lower = 0;
+    for tier in config.fee_tiers.iter() {
+        if volume <= lower {
+            break;
+        }
+        let in_tier = volume.min(tier.up_to_amount) - lower;
+        fee += in_tier as u128 * tier.fee_bps as u128;
+        lower = tier.up_to_amount;
+    }
+    // Over the last tier the last rate keeps applying.
+    if let Some(last) = config.fee_tiers.last() {
+        if volume > last.up_to_amount {
+            fee += (volume - last.up_to_amount) as u128 * last.fee_bps as u128;
+        }
+    }
+    fee
+}
+
+// Fee on withdrawing `amount` from a campaign that already withdrew `volume_before`.
+// We round the fee on the running total and charge the difference, so the sum of every
+// fee a campaign paid is exactly the rounded fee on its total: the rounding never adds up
+// over many small withdrawals, whatever the config's rounding is.
+fn withdraw_fee(config: &PlatformConfig, volume_before: u64, amount: u64) -> u64 {
+    let before = round_div(tiered_fee_scaled(config, volume_before), 10_000, config.rounding);
+    let after = round_div(tiered_fee_scaled(config, volume_before + amount), 10_000, config.rounding);
+    (after - before) as u64
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct OpenDisputeRequest {
+    // Whether donations should also stop while the dispute is open.
+    pub pause_donations: bool,
+}
+
+fn open_dispute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::OpenDispute;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let resolver_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !resolver_account.is_signer {
+        log_event!(instruction, "dispute resolver should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.dispute_resolver != *resolver_account.key {
+        log_event!(instruction, "Only the dispute resolver can open a dispute");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: OpenDisputeRequest = parse_instruction(instruction, instruction_data)?;
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    campaign_data.disputed = true;
+    campaign_data.donations_paused = input_data.pause_donations;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn resolve_dispute(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ResolveDispute;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let resolver_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !resolver_account.is_signer {
+        log_event!(instruction, "dispute resolver should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.dispute_resolver != *resolver_account.key {
+        log_event!(instruction, "Only the dispute resolver can resolve a dispute");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if !campaign_data.disputed {
+        log_event!(instruction, "Campaign isn't under dispute");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Resolving the dispute unblocks withdraw and donations again.
+    campaign_data.disputed = false;
+    campaign_data.donations_paused = false;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Serialized size of a state struct, taken from its default value so it can't fall behind
+// when fields are added. For structs with a String or a Vec it only counts the length prefix,
+// their `*_len` helpers add the room for the contents.
+fn serialized_len<T: BorshSerialize + Default>() -> usize {
+    T::default().try_to_vec().map(|data| data.len()).unwrap_or(0)
+}
+
+// The config and the global stats are PDAs that stay around while their structs grow, so an account
+// created before a field was added is shorter than the struct. It reads as if the missing bytes were
+// zeros, which gives the new fields their zero default. Saving the new fields needs the room, the
+// authority grows the account with `realloc_config` or `realloc_global_stats`. The account's size
+// tells which fields it really holds.
+fn deserialize_padded<T: BorshDeserialize + BorshSerialize + Default>(
+    instruction: Instruction,
+    data: &[u8],
+) -> Result<T, ProgramError> {
+    let len = serialized_len::<T>();
+    let result = if data.len() >= len {
+        T::deserialize(&mut &data[..])
+    } else {
+        let mut padded = data.to_vec();
+        padded.resize(len, 0);
+        T::deserialize(&mut &padded[..])
+    };
+    result.map_err(|_| {
+        log_event!(instruction, "Error deserializing account data");
+        ProgramError::InvalidAccountData
+    })
+}
+
+// Account sizes are `usize` in the program but `u64` in system instructions.
+// We never cast them blindly, and reject sizes the runtime would never allocate anyway
+// instead of letting a wrapped or truncated value through.
+fn account_space(instruction: Instruction, len: usize) -> Result<u64, ProgramError> {
+    match u64::try_from(len) {
+        Ok(space) if space <= MAX_PERMITTED_DATA_LENGTH => Ok(space),
+        _ => {
+            log_event!(instruction, "Requested account size {} is too large", len);
+            Err(ProgramError::InvalidArgument)
+        }
+    }
+}
+
+// Adds up two parts of an account size, checking the total is still a size we can allocate.
+fn checked_account_len(instruction: Instruction, len: usize, extra: usize) -> Result<usize, ProgramError> {
+    let total = len.checked_add(extra).ok_or_else(|| {
+        log_event!(instruction, "Requested account size overflows");
+        ProgramError::InvalidArgument
+    })?;
+    account_space(instruction, total)?;
+    Ok(total)
+}
+
+// A PDA has no private key, so the program has to create it by signing with the seeds.
+// The payer funds the rent exemption of the new account.
+fn create_pda_account<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    new_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent_exemption = Rent::get()?.minimum_balance(space);
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            new_account.key,
+            rent_exemption,
+            account_space(instruction, space)?,
+            program_id,
+        ),
+        &[payer.clone(), new_account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+// Every donator gets one record per campaign, derived from `DONATION_SEED` and the two keys.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct DonationRecord {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    // Cycle of the campaign the amount below belongs to.
+    pub cycle: u32,
+    pub amount: u64,
+    pub refunded: bool,
+    // Paid to the donor by the insurance pool, a refund only covers what is left.
+    pub insurance_paid: u64,
+    // What the refund paid, less then `amount` when the refund pool was short.
+    pub refund_paid: u64,
+    // The donor chose to keep the donation in the campaign even if it fails. It can't be undone
+    // and a record can't mix both kinds, a donor tops up with the same choice or not at all.
+    pub non_refundable: bool,
+}
+
+// Loads the donation record of `donor` for `campaign`, creating an empty one if this is
+// the first time they donate. The payer pays for the record, it is the donor on every path but
+// `redirect_funds`, where the donor is a campaign and its admin pays.
+fn load_or_create_donation_record<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    campaign: &AccountInfo<'a>,
+    donor: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    donation_record_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> Result<DonationRecord, ProgramError> {
+    let (record_key, bump) = find_donation_record_address(program_id, campaign.key, donor.key);
+    if record_key != *donation_record_account.key {
+        log_event!(instruction, "donation record isn't the donation PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    if donation_record_account.owner == program_id {
+        let record = DonationRecord::try_from_slice(*donation_record_account.data.borrow())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        return Ok(record);
+    }
+
+    create_pda_account(
+        instruction,
+        program_id,
+        payer,
+        donation_record_account,
+        system_program,
+        serialized_len::<DonationRecord>(),
+        &[DONATION_SEED, campaign.key.as_ref(), donor.key.as_ref(), &[bump]],
+    )?;
+
+    Ok(DonationRecord {
+        campaign: *campaign.key,
+        donor: *donor.key,
+        cycle: 0,
+        amount: 0,
+        refunded: false,
+        insurance_paid: 0,
+        refund_paid: 0,
+        non_refundable: false,
+    })
+}
+
+// Once the end date has passed anyone can finalize the campaign.
+// It is successful if it reached its soft goal (or its goal without one),
+// otherwise it failed and donors can get refunds.
+fn finalize_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::FinalizeCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    // The config decides how long donors have to claim their refunds.
+    let config_account = next_account_info(accounts_iter)?;
+    // Counts the successful campaigns per category.
+    let global_stats_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.state != CampaignState::Active {
+        log_event!(instruction, "Campaign is already finalized");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.end_timestamp == 0 {
+        log_event!(instruction, "Campaign has no end date");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now < campaign_data.end_timestamp {
+        log_event!(instruction, "Campaign hasn't ended yet");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // Donations that waited their confirmation slots count toward the goal, whether or not
+    // somebody ran confirm_donations on them.
+    confirm_pending_donations(&mut campaign_data, Clock::get()?.slot, false);
+
+    if campaign_data.amount_donated >= campaign_data.funded_threshold() && campaign_data.donor_count_reached() {
+        campaign_data.state = CampaignState::Successful;
+
+        let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+        let category_stats = &mut global_stats.categories[campaign_data.category as usize];
+        category_stats.successful_campaigns = category_stats
+            .successful_campaigns
+            .checked_add(1)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    } else {
+        campaign_data.state = CampaignState::Failed;
+        // The reserved fees go back to the donors with their refunds.
+        campaign_data.fees_reserved = 0;
+        campaign_data.refund_deadline = refund_deadline(&config, now);
+        snapshot_refund_pool(&mut campaign_data, writing_account)?;
+    }
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Donors of a failed campaign take back what they gave in the current cycle.
+// The donor's refund recipient PDA always comes after their account, set or not. When the donor set
+// a recipient it is passed next, the refund goes to it and doesn't need the donor's signature, so it
+// can be claimed after the donor lost their wallet, and can't be taken by whoever has it now.
+fn claim_refund(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ClaimRefund;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let refund_recipient_account = next_account_info(accounts_iter)?;
+    let recipient = accounts_iter.next();
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donator.key);
+    if record_key != *donation_record_account.key || donation_record_account.owner != program_id {
+        log_event!(instruction, "donation record account isn't the donation record PDA of this donator");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    log_phase!(instruction, "deserialize");
+    let payee = match load_refund_recipient(
+        instruction,
+        program_id,
+        writing_account,
+        donator,
+        refund_recipient_account,
+    )? {
+        Some(refund_recipient) => match recipient {
+            Some(recipient) if *recipient.key == refund_recipient => recipient,
+            _ => {
+                log_event!(instruction, "Refunds of this donor go to {}", refund_recipient);
+                return Err(ProgramError::InvalidAccountData);
+            }
+        },
+        None => {
+            if !donator.is_signer {
+                log_event!(instruction, "donator should be signer");
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            donator
+        }
+    };
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut donation_record = DonationRecord::try_from_slice(*donation_record_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if donation_record.campaign != *writing_account.key || donation_record.donor != *donator.key {
+        log_event!(instruction, "donation record doesn't belong to this campaign and donator");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state == CampaignState::Closed || refund_window_closed(&campaign_data)? {
+        log_event!(instruction, "Refund window is closed");
+        return Err(FundError::RefundWindowClosed.into());
+    }
+    if campaign_data.state != CampaignState::Failed && campaign_data.state != CampaignState::Liquidated {
+        log_event!(instruction, "Only failed campaigns can be refunded");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // Once a refund root is posted it is the only way to claim, so nobody is refunded twice.
+    if campaign_data.refund_root != [0; 32] {
+        log_event!(instruction, "Refunds of this campaign are claimed with a merkle proof");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if donation_record.cycle != campaign_data.cycle || donation_record.refunded || donation_record.amount == 0 {
+        log_event!(instruction, "Nothing to refund");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if donation_record.non_refundable {
+        log_event!(instruction, "Donation was made non-refundable");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // Whatever the insurance pool already paid back isn't refunded a second time.
+    let refund = pro_rata_refund(&campaign_data, donation_record.amount)
+        .min(donation_record.amount.saturating_sub(donation_record.insurance_paid));
+
+    let available = writing_account
+        .lamports()
+        .saturating_sub(campaign_min_balance(writing_account.data_len())?);
+    if available < refund {
+        log_event!(instruction, "Insufficent balance");
+        return Err(insufficient_funds(refund, available));
+    }
+
+    log_phase!(instruction, "cpi");
+    **writing_account.try_borrow_mut_lamports()? -= refund;
+    **payee.try_borrow_mut_lamports()? += refund;
+
+    // The record is settled even when the pool only covered part of it. The insured part
+    // counts as settled too, so a renewal isn't blocked by it.
+    campaign_data.total_refunded = campaign_data
+        .total_refunded
+        .checked_add(donation_record.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    donation_record.refunded = true;
+    donation_record.refund_paid = refund;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RenewCampaignRequest {
+    pub new_goal: u64,
+    pub new_end_timestamp: i64,
+}
+
+// Starts a new cycle of a finalized campaign on the same account, so links to the campaign
+// and the donation records keep working. The totals of the finished cycle are kept in previous_cycles.
+fn renew_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RenewCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    // Holds the smallest goal allowed.
+    let config_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can renew");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if campaign_data.state != CampaignState::Successful && campaign_data.state != CampaignState::Failed {
+        log_event!(instruction, "Only a finalized campaign can be renewed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.fees_reserved > 0 {
+        log_event!(instruction, "Campaign still has reserved fees, collect them before renewing");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // Donors of a failed cycle have to get their refunds before the campaign starts over,
+    // otherwise their records would be reset by the new cycle.
+    if campaign_data.state == CampaignState::Failed
+        && campaign_data.total_refunded + campaign_data.non_refundable_total < campaign_data.amount_donated
+    {
+        log_event!(instruction, "Failed campaign still has pending refunds");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: RenewCampaignRequest = parse_instruction(instruction, instruction_data)?;
+
+    if input_data.new_end_timestamp != 0 && input_data.new_end_timestamp <= Clock::get()?.unix_timestamp {
+        log_event!(instruction, "end_timestamp should be in the future");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_goal_only_deadline(instruction, &campaign_data.withdraw_policy, input_data.new_end_timestamp)?;
+    let config = load_config(instruction, program_id, config_account)?;
+    check_min_goal(instruction, &config, &campaign_data.withdraw_policy, input_data.new_goal)?;
+
+    // The pending donations belong to the cycle that is ending.
+    confirm_pending_donations(&mut campaign_data, 0, true);
+    if campaign_data.previous_cycles.len() == MAX_PREVIOUS_CYCLES {
+        campaign_data.previous_cycles.remove(0);
+    }
+    campaign_data.previous_cycles.push(CycleSummary {
+        raised: campaign_data.amount_donated,
+        donors: campaign_data.donor_count,
+        ended_at: campaign_data.end_timestamp,
+    });
+
+    campaign_data.cycle += 1;
+    campaign_data.amount_donated = 0;
+    // Every cycle raises through the same milestones.
+    for milestone in campaign_data.milestones.iter_mut() {
+        milestone.released = false;
+    }
+    campaign_data.amount_matched = 0;
+    campaign_data.donor_count = 0;
+    campaign_data.total_refunded = 0;
+    campaign_data.refund_pool = 0;
+    campaign_data.refundable_total = 0;
+    campaign_data.refund_root = [0; 32];
+    campaign_data.non_refundable_total = 0;
+    campaign_data.failed_withdrawable = 0;
+    campaign_data.goal = input_data.new_goal;
+    // The soft goal belonged to the old goal, the admin can set a new one.
+    campaign_data.soft_goal = 0;
+    campaign_data.end_timestamp = input_data.new_end_timestamp;
+    campaign_data.state = CampaignState::Active;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Layout of the first campaign accounts, before anything after amount_donated existed.
+// Those accounts were allocated with exactly this much data, there is no room to grow them.
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct CampaignDetailsV1 {
+    pub admin: Pubkey,
+    pub name: String,
+    pub description: String,
+    pub image_link: String,
+    pub amount_donated: u64,
+}
+
+impl From<CampaignDetailsV1> for CampaignDetails {
+    // A v1 campaign reads as an active campaign without a goal, end date or any of the
+    // later settings, which is how those campaigns behaved.
+    fn from(v1: CampaignDetailsV1) -> Self {
+        CampaignDetails {
+            admin: v1.admin,
+            name_hash: campaign_name_hash(&v1.name),
+            name: v1.name,
+            image_link: v1.image_link,
+            amount_donated: v1.amount_donated,
+            currency: SOL_CURRENCY,
+            // The description of a v1 account moves out with `migrate_campaign_content`.
+            ..Default::default()
+        }
+    }
+}
+
+// Reads a campaign account of any layout we shipped and migrates it to the current one.
+// The current layout is tried first. A v1 account has to be exactly as long as its data,
+// so some other account doesn't accidentally pass for one.
+// The layouts are pinned by the fixtures in tests/fixtures.
+pub fn load_campaign(data: &[u8]) -> Result<CampaignDetails, ProgramError> {
+    if let Ok(campaign) = CampaignDetails::deserialize(&mut &data[..]) {
+        return Ok(campaign);
+    }
+    CampaignDetailsV1::try_from_slice(data)
+        .map(CampaignDetails::from)
+        .map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// Helper for dashboards reading many campaign accounts at once (e.g. from getProgramAccounts).
+// Accounts that don't parse as a campaign are skipped instead of failing the whole batch,
+// we only log how many of them there were.
+pub fn load_campaigns(accounts: &[(Pubkey, Vec<u8>)]) -> Vec<(Pubkey, CampaignDetails)> {
+    let mut campaigns = Vec::with_capacity(accounts.len());
+    let mut skipped = 0;
+    for (key, data) in accounts {
+        match load_campaign(data) {
+            Ok(campaign) => campaigns.push((*key, campaign)),
+            Err(_) => skipped += 1,
+        }
+    }
+    if skipped > 0 {
+        msg!("Skipped {} accounts that aren't campaigns", skipped);
+    }
+    campaigns
+}
+
+// Vouchers are donation credits handed out by a sponsor, e.g. at events.
+// The sponsor escrows lamports in a PDA derived from the hash of a secret code,
+// whoever knows the code can donate them to the campaign of their choice.
+// Codes are short strings, there is no reason to hash more than this.
+const MAX_VOUCHER_CODE_LEN: usize = 64;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+struct Voucher {
+    pub sponsor: Pubkey,
+    pub code_hash: [u8; 32],
+    pub amount: u64,
+    // After this unix timestamp the voucher can't be redeemed and the sponsor can take it back.
+    pub expires_at: i64,
+    pub redeemed: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CreateVoucherRequest {
+    pub amount: u64,
+    // sha256 of the secret code.
+    pub code_hash: [u8; 32],
+    pub expires_at: i64,
+}
+
+fn create_voucher(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CreateVoucher;
+    let accounts_iter = &mut accounts.iter();
+    let voucher_account = next_account_info(accounts_iter)?;
+    let sponsor_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !sponsor_account.is_signer {
+        log_event!(instruction, "sponsor should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: CreateVoucherRequest = parse_instruction(instruction, instruction_data)?;
+
+    log_phase!(instruction, "validate");
+    if input_data.amount == 0 {
+        log_event!(instruction, "Voucher amount should be more then zero");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if input_data.expires_at <= Clock::get()?.unix_timestamp {
+        log_event!(instruction, "expires_at should be in the future");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (voucher_key, bump) = find_voucher_address(program_id, &input_data.code_hash);
+    if voucher_key != *voucher_account.key {
+        log_event!(instruction, "voucher account isn't the voucher PDA of this code");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        sponsor_account,
+        voucher_account,
+        system_program,
+        serialized_len::<Voucher>(),
+        &[VOUCHER_SEED, &input_data.code_hash, &[bump]],
+    )?;
+
+    // The voucher account now holds its rent, we add the voucher amount on top of it.
+    invoke(
+        &system_instruction::transfer(sponsor_account.key, voucher_account.key, input_data.amount),
+        &[
+            sponsor_account.clone(),
+            voucher_account.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    let voucher = Voucher {
+        sponsor: *sponsor_account.key,
+        code_hash: input_data.code_hash,
+        amount: input_data.amount,
+        expires_at: input_data.expires_at,
+        redeemed: false,
+    };
+    log_phase!(instruction, "serialize");
+    voucher.serialize(&mut &mut voucher_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+// Compares two hashes without stopping at the first different byte,
+// so the time it takes doesn't tell how much of the code was right.
+fn hashes_match(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    let mut diff = 0u8;
+    for i in 0..32 {
+        diff |= a[i] ^ b[i];
+    }
+    diff == 0
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RedeemVoucherRequest {
+    // The secret code itself, the campaign is the account passed to the instruction.
+    pub code: Vec<u8>,
+}
+
+fn redeem_voucher(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RedeemVoucher;
+    let accounts_iter = &mut accounts.iter();
+    let voucher_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+    // The redeemer gets the donation credited to their own record.
+    let redeemer = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let receipt_account = next_account_info(accounts_iter)?;
+    let blocklist_account = next_account_info(accounts_iter)?;
+    // A redeemed voucher is a donation, so it stops with the others when the program is sunset,
+    // and it is counted in the campaign's category.
+    let config_account = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+
+    if voucher_account.owner != program_id {
+        log_event!(instruction, "voucher account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing_account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !redeemer.is_signer {
+        log_event!(instruction, "redeemer should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    check_not_sunset(instruction, &config)?;
+
+    let input_data: RedeemVoucherRequest = parse_instruction(instruction, instruction_data)?;
+
+    if input_data.code.is_empty() || input_data.code.len() > MAX_VOUCHER_CODE_LEN {
+        log_event!(instruction, "Voucher code should be between 1 and {} bytes", MAX_VOUCHER_CODE_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut voucher = Voucher::try_from_slice(*voucher_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    let code_hash = hash(&input_data.code).to_bytes();
+    if !hashes_match(&code_hash, &voucher.code_hash) {
+        log_event!(instruction, "Wrong voucher code");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if voucher.redeemed {
+        log_event!(instruction, "Voucher is already redeemed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if Clock::get()?.unix_timestamp >= voucher.expires_at {
+        log_event!(instruction, "Voucher has expired");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    check_can_donate(instruction, &campaign_data)?;
+    check_donor_not_blocked(instruction, program_id, writing_account, redeemer, blocklist_account)?;
+    // A voucher is one donation of its whole amount, it can't get around the per transaction limit.
+    check_donation_size(instruction, &config, &campaign_data, voucher.amount)?;
+    // Cut down to what the hard cap leaves, the rest goes back to the sponsor with reclaim_voucher.
+    let amount = accepted_donation(instruction, &campaign_data, voucher.amount)?;
+
+    log_phase!(instruction, "cpi");
+    let mut donation_record = load_or_create_donation_record(
+        instruction,
+        program_id,
+        writing_account,
+        redeemer,
+        redeemer,
+        donation_record_account,
+        system_program,
+    )?;
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    credit_donation(instruction, &config, &mut campaign_data, &mut global_stats, &mut donation_record, amount, false)?;
+    log_phase!(instruction, "serialize");
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    create_donation_receipt(
+        instruction,
+        program_id,
+        writing_account,
+        &mut campaign_data,
+        redeemer,
+        receipt_account,
+        system_program,
+        amount,
+    )?;
+
+    // Both accounts are owned by the program so we can move the lamports directly.
+    // The voucher keeps its rent until the sponsor reclaims it.
+    **voucher_account.try_borrow_mut_lamports()? -= amount;
+    **writing_account.try_borrow_mut_lamports()? += amount;
+
+    voucher.redeemed = true;
+    voucher.serialize(&mut &mut voucher_account.data.borrow_mut()[..])?;
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// The sponsor closes a voucher that was redeemed or has expired and gets back
+// whatever is left in it (the unredeemed amount and the rent).
+fn reclaim_voucher(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ReclaimVoucher;
+    let accounts_iter = &mut accounts.iter();
+    let voucher_account = next_account_info(accounts_iter)?;
+    let sponsor_account = next_account_info(accounts_iter)?;
+
+    if voucher_account.owner != program_id {
+        log_event!(instruction, "voucher account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !sponsor_account.is_signer {
+        log_event!(instruction, "sponsor should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let voucher = Voucher::try_from_slice(*voucher_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if voucher.sponsor != *sponsor_account.key {
+        log_event!(instruction, "Only the sponsor can reclaim the voucher");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !voucher.redeemed && Clock::get()?.unix_timestamp < voucher.expires_at {
+        log_event!(instruction, "Voucher can still be redeemed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    log_phase!(instruction, "cpi");
+    **sponsor_account.try_borrow_mut_lamports()? += **voucher_account.lamports.borrow();
+    **voucher_account.try_borrow_mut_lamports()? = 0;
+    voucher_account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+// The insurance pool is a single PDA holding the insurance slice of the platform fees.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct InsurancePool {
+    // Everything that came in from fees, and everything paid out to donors.
+    // They are public so anyone can check how the pool is used.
+    pub total_in: u64,
+    pub total_paid: u64,
+}
+
+// Platform wide counters, a single PDA created with the config.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct GlobalStats {
+    // Id the next campaign gets.
+    pub next_campaign_id: u64,
+    // Lamports donors tipped the platform with donate_direct, over the platform's lifetime.
+    pub total_tips: u64,
+    // Totals per campaign category, indexed by `CampaignDetails::category`.
+    pub categories: [CategoryStats; MAX_CATEGORIES],
+}
+
+pub const MAX_CATEGORIES: usize = 8;
+
+// Public totals of one category, so a dashboard can show them without an indexer.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone, Copy)]
+pub struct CategoryStats {
+    pub campaigns_created: u64,
+    // Lamports donated on every path and matched by sponsors, counted when they arrive, pending or
+    // not. Returned and reclaimed donations are taken off, refunds aren't. Redirected funds move
+    // to the partner's category.
+    pub amount_raised: u64,
+    // Campaigns `finalize_campaign` found successful.
+    pub successful_campaigns: u64,
+}
+
+// Counts lamports that came into a campaign of `category`, see `CategoryStats::amount_raised`.
+fn count_raised(global_stats: &mut GlobalStats, category: u8, amount: u64) -> ProgramResult {
+    let category_stats = &mut global_stats.categories[category as usize];
+    category_stats.amount_raised = category_stats
+        .amount_raised
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(())
+}
+
+// Takes back lamports that left again without being a refund. Donations from before the stats
+// counted every path were never added, so it only goes down as far as zero.
+fn uncount_raised(global_stats: &mut GlobalStats, category: u8, amount: u64) {
+    let category_stats = &mut global_stats.categories[category as usize];
+    category_stats.amount_raised = category_stats.amount_raised.saturating_sub(amount);
+}
+
+fn check_category(instruction: Instruction, category: u8) -> ProgramResult {
+    if category as usize >= MAX_CATEGORIES {
+        log_event!(instruction, "category should be less then {}", MAX_CATEGORIES);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
+fn load_global_stats(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    global_stats_account: &AccountInfo,
+) -> Result<GlobalStats, ProgramError> {
+    let (global_stats_key, _) = find_global_stats_address(program_id);
+    if global_stats_key != *global_stats_account.key || global_stats_account.owner != program_id {
+        log_event!(instruction, "global stats account isn't the global stats PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let global_stats = deserialize_padded::<GlobalStats>(instruction, &global_stats_account.data.borrow())?;
+    Ok(global_stats)
+}
+
+// Reads the insurance pool and makes sure it really is the insurance pool PDA.
+fn load_insurance_pool(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    insurance_pool_account: &AccountInfo,
+) -> Result<InsurancePool, ProgramError> {
+    let (insurance_pool_key, _) = find_insurance_pool_address(program_id);
+    if insurance_pool_key != *insurance_pool_account.key || insurance_pool_account.owner != program_id {
+        log_event!(instruction, "insurance pool account isn't the insurance pool PDA");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let insurance_pool = InsurancePool::try_from_slice(*insurance_pool_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(insurance_pool)
+}
+
+// The dispute resolver confirms a disputed campaign is fraudulent.
+// The campaign is frozen for good, the admin can't withdraw anymore and donors can get refunds
+// and insurance claims.
+fn liquidate_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::LiquidateCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let resolver_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !resolver_account.is_signer {
+        log_event!(instruction, "dispute resolver should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.dispute_resolver != *resolver_account.key {
+        log_event!(instruction, "Only the dispute resolver can liquidate a campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    // Only a campaign somebody reported can be liquidated.
+    if !campaign_data.disputed {
+        log_event!(instruction, "Campaign isn't under dispute");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // A failed campaign already took its snapshot and donors may have claimed against it.
+    let already_refundable = campaign_data.state == CampaignState::Failed;
+
+    campaign_data.state = CampaignState::Liquidated;
+    campaign_data.donations_paused = true;
+    if !already_refundable {
+        // The reserved fees go back to the donors with their refunds.
+        campaign_data.fees_reserved = 0;
+        campaign_data.refund_deadline = refund_deadline(&config, Clock::get()?.unix_timestamp);
+        snapshot_refund_pool(&mut campaign_data, writing_account)?;
+    }
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct PayInsuranceClaimRequest {
+    pub amount: u64,
+}
+
+// The platform authority pays a donor of a liquidated campaign out of the insurance pool.
+// The campaign and the donor are the accounts passed to the instruction.
+fn pay_insurance_claim(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::PayInsuranceClaim;
+    let accounts_iter = &mut accounts.iter();
+    let insurance_pool_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+
+    if !authority_account.is_signer {
+        log_event!(instruction, "authority should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donor_account.key);
+    if record_key != *donation_record_account.key || donation_record_account.owner != program_id {
+        log_event!(instruction, "donation record account isn't the donation record PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.authority != *authority_account.key {
+        log_event!(instruction, "Only the platform authority can pay insurance claims");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut insurance_pool = load_insurance_pool(instruction, program_id, insurance_pool_account)?;
+
+    let input_data: PayInsuranceClaimRequest = parse_instruction(instruction, instruction_data)?;
+
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut donation_record = DonationRecord::try_from_slice(*donation_record_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if campaign_data.state != CampaignState::Liquidated {
+        log_event!(instruction, "Insurance only covers liquidated campaigns");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if donation_record.campaign != *writing_account.key || donation_record.donor != *donor_account.key {
+        log_event!(instruction, "donation record doesn't belong to this campaign and donor");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // A donor can't be made more then whole, what was refunded or already insured doesn't count.
+    // A pro-rata refund can leave part of the donation uncovered, the insurance can pay that part.
+    let unrefunded = if donation_record.cycle != campaign_data.cycle {
+        0
+    } else {
+        donation_record
+            .amount
+            .checked_sub(donation_record.insurance_paid)
+            .and_then(|left| left.checked_sub(donation_record.refund_paid))
+            .ok_or(ProgramError::InvalidAccountData)?
+    };
+    if input_data.amount == 0 || input_data.amount > unrefunded {
+        log_event!(instruction, "Claim is more then the donor's unrefunded donation");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let rent_exemption = Rent::get()?.minimum_balance(insurance_pool_account.data_len());
+    let available = insurance_pool_account.lamports().saturating_sub(rent_exemption);
+    if available < input_data.amount {
+        log_event!(instruction, "Insufficent balance in the insurance pool");
+        return Err(insufficient_funds(input_data.amount, available));
+    }
+
+    log_phase!(instruction, "cpi");
+    **insurance_pool_account.try_borrow_mut_lamports()? -= input_data.amount;
+    **donor_account.try_borrow_mut_lamports()? += input_data.amount;
+
+    insurance_pool.total_paid = insurance_pool
+        .total_paid
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    donation_record.insurance_paid = donation_record
+        .insurance_paid
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "serialize");
+    insurance_pool.serialize(&mut &mut insurance_pool_account.data.borrow_mut()[..])?;
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Companies running matching programs register once as a sponsor, all their matches
+// are then added up on this account, derived from the sponsor's wallet.
+const MAX_SPONSOR_NAME_LEN: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct Sponsor {
+    pub wallet: Pubkey,
+    pub name: String,
+    pub total_committed: u64,
+    pub total_matched: u64,
+    // Set by the platform authority, a flagged sponsor can't make new commitments.
+    pub flagged: bool,
+}
+
+// Size of a Sponsor account, room for the longest name.
+fn sponsor_len() -> usize {
+    serialized_len::<Sponsor>() + MAX_SPONSOR_NAME_LEN
+}
+
+// Reads the sponsor account of `wallet`. The name can be shorter then the room we have
+// for it, so it is read with `deserialize` which doesn't mind the unused bytes at the end.
+fn load_sponsor(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    sponsor_account: &AccountInfo,
+    wallet: &Pubkey,
+) -> Result<Sponsor, ProgramError> {
+    let (sponsor_key, _) = find_sponsor_address(program_id, wallet);
+    if sponsor_key != *sponsor_account.key || sponsor_account.owner != program_id {
+        log_event!(instruction, "sponsor account isn't the sponsor PDA of this wallet");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    let sponsor = Sponsor::deserialize(&mut &sponsor_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(sponsor)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SponsorNameRequest {
+    pub name: String,
+}
+
+fn register_sponsor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RegisterSponsor;
+    let accounts_iter = &mut accounts.iter();
+    let sponsor_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if !wallet_account.is_signer {
+        log_event!(instruction, "sponsor wallet should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let (sponsor_key, bump) = find_sponsor_address(program_id, wallet_account.key);
+    if sponsor_key != *sponsor_account.key {
+        log_event!(instruction, "sponsor account isn't the sponsor PDA of this wallet");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: SponsorNameRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.name.len() > MAX_SPONSOR_NAME_LEN {
+        log_event!(instruction, "Sponsor name can't be longer then {} bytes", MAX_SPONSOR_NAME_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        wallet_account,
+        sponsor_account,
+        system_program,
+        sponsor_len(),
+        &[SPONSOR_SEED, wallet_account.key.as_ref(), &[bump]],
+    )?;
+
+    let sponsor = Sponsor {
+        wallet: *wallet_account.key,
+        name: input_data.name,
+        total_committed: 0,
+        total_matched: 0,
+        flagged: false,
+    };
+    log_phase!(instruction, "serialize");
+    sponsor.serialize(&mut &mut sponsor_account.data.borrow_mut()[..])?;
+
+    Ok(())
+}
+
+fn update_sponsor_name(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::UpdateSponsorName;
+    let accounts_iter = &mut accounts.iter();
+    let sponsor_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+
+    if !wallet_account.is_signer {
+        log_event!(instruction, "sponsor wallet should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let mut sponsor = load_sponsor(instruction, program_id, sponsor_account, wallet_account.key)?;
+
+    let input_data: SponsorNameRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.name.len() > MAX_SPONSOR_NAME_LEN {
+        log_event!(instruction, "Sponsor name can't be longer then {} bytes", MAX_SPONSOR_NAME_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    sponsor.name = input_data.name;
+    log_phase!(instruction, "serialize");
+    sponsor.serialize(&mut &mut sponsor_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct FlagSponsorRequest {
+    pub flagged: bool,
+}
+
+// The platform authority flags (or unflags) a sponsor, the sponsor wallet is passed as an account.
+fn flag_sponsor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::FlagSponsor;
+    let accounts_iter = &mut accounts.iter();
+    let sponsor_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if !authority_account.is_signer {
+        log_event!(instruction, "authority should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.authority != *authority_account.key {
+        log_event!(instruction, "Only the platform authority can flag a sponsor");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut sponsor = load_sponsor(instruction, program_id, sponsor_account, wallet_account.key)?;
+
+    let input_data: FlagSponsorRequest = parse_instruction(instruction, instruction_data)?;
+
+    sponsor.flagged = input_data.flagged;
+    log_phase!(instruction, "serialize");
+    sponsor.serialize(&mut &mut sponsor_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A sponsor escrows lamports in a commitment for one campaign, derived from the campaign
+// and the sponsor wallet. Donations made with the commitment get matched out of it, 1:1
+// unless the commitment has a schedule.
+const MAX_MATCH_STEPS: usize = 4;
+
+// One step of a match schedule: donations before `until_ts` get matched at `ratio_bps`
+// of their amount. A donation exactly at `until_ts` already falls in the next step.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default, Clone)]
+struct MatchStep {
+    pub until_ts: i64,
+    pub ratio_bps: u16,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+struct MatchCommitment {
+    pub campaign: Pubkey,
+    pub sponsor: Pubkey,
+    // Everything committed and everything matched so far, the difference is still in escrow.
+    pub committed: u64,
+    pub matched: u64,
+    // Cycle of the campaign `matched_in_cycle` belongs to.
+    pub cycle: u32,
+    pub matched_in_cycle: u64,
+    // Match ratio decaying over time, so earlier donations get more. Empty matches 1:1 forever,
+    // past the last step nothing is matched anymore.
+    pub schedule: Vec<MatchStep>,
+}
+
+// Size of a MatchCommitment with a full schedule, it's read with `deserialize`
+// because the schedule doesn't always fill the account.
+fn match_commitment_len() -> usize {
+    serialized_len::<MatchCommitment>() + MAX_MATCH_STEPS * serialized_len::<MatchStep>()
+}
+
+// Steps have strictly increasing timestamps and strictly decreasing ratios, at most 100%.
+fn check_match_schedule(instruction: Instruction, schedule: &[MatchStep]) -> ProgramResult {
+    check_entries(instruction, schedule.len(), MAX_MATCH_STEPS, "match schedule steps")?;
+    for (i, step) in schedule.iter().enumerate() {
+        if step.ratio_bps > 10_000 {
+            log_event!(instruction, "A match ratio can't be more then 10000 bps");
+            return Err(FundError::InvalidInstructionData.into());
+        }
+        if i > 0 && (step.until_ts <= schedule[i - 1].until_ts || step.ratio_bps >= schedule[i - 1].ratio_bps) {
+            log_event!(instruction, "Match steps should have increasing timestamps and decreasing ratios");
+            return Err(FundError::InvalidInstructionData.into());
+        }
+    }
+    Ok(())
+}
+
+// The ratio a donation at `now` gets matched at, in basis points.
+fn match_ratio_bps(schedule: &[MatchStep], now: i64) -> u16 {
+    if schedule.is_empty() {
+        return 10_000;
+    }
+    schedule
+        .iter()
+        .find(|step| now < step.until_ts)
+        .map(|step| step.ratio_bps)
+        .unwrap_or(0)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CommitMatchRequest {
+    pub amount: u64,
+    // Only taken when the commitment is created, top ups leave it empty.
+    pub schedule: Vec<MatchStep>,
+}
+
+// Creates the commitment on the first call, later calls top it up.
+fn commit_match(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CommitMatch;
+    let accounts_iter = &mut accounts.iter();
+    let match_commitment_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+    let sponsor_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !wallet_account.is_signer {
+        log_event!(instruction, "sponsor wallet should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let mut sponsor = load_sponsor(instruction, program_id, sponsor_account, wallet_account.key)?;
+    log_phase!(instruction, "validate");
+    if sponsor.flagged {
+        log_event!(instruction, "Sponsor is flagged and can't make new commitments");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    check_can_donate(instruction, &campaign_data)?;
+
+    let input_data: CommitMatchRequest = parse_instruction(instruction, instruction_data)?;
+    if input_data.amount == 0 {
+        log_event!(instruction, "Commitment amount should be more then zero");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (match_commitment_key, bump) = find_match_address(program_id, writing_account.key, wallet_account.key);
+    if match_commitment_key != *match_commitment_account.key {
+        log_event!(instruction, "match commitment isn't the match PDA of this campaign and sponsor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "cpi");
+    let mut match_commitment = if match_commitment_account.owner == program_id {
+        if !input_data.schedule.is_empty() {
+            log_event!(instruction, "The match schedule can only be set when the commitment is created");
+            return Err(FundError::InvalidInstructionData.into());
+        }
+        MatchCommitment::deserialize(&mut &match_commitment_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?
+    } else {
+        check_match_schedule(instruction, &input_data.schedule)?;
+        create_pda_account(
+            instruction,
+            program_id,
+            wallet_account,
+            match_commitment_account,
+            system_program,
+            match_commitment_len(),
+            &[MATCH_SEED, writing_account.key.as_ref(), wallet_account.key.as_ref(), &[bump]],
+        )?;
+        MatchCommitment {
+            campaign: *writing_account.key,
+            sponsor: *wallet_account.key,
+            committed: 0,
+            matched: 0,
+            cycle: campaign_data.cycle,
+            matched_in_cycle: 0,
+            schedule: input_data.schedule,
+        }
+    };
+
+    invoke(
+        &system_instruction::transfer(wallet_account.key, match_commitment_account.key, input_data.amount),
+        &[
+            wallet_account.clone(),
+            match_commitment_account.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    match_commitment.committed = match_commitment
+        .committed
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    sponsor.total_committed = sponsor
+        .total_committed
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "serialize");
+    match_commitment.serialize(&mut &mut match_commitment_account.data.borrow_mut()[..])?;
+    sponsor.serialize(&mut &mut sponsor_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Matches a donation of `amount` at the commitment's current ratio, as far as what is left covers it.
+#[allow(clippy::too_many_arguments)]
+fn apply_match(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo,
+    campaign_data: &mut CampaignDetails,
+    global_stats: &mut GlobalStats,
+    match_commitment_account: &AccountInfo,
+    sponsor_account: &AccountInfo,
+    amount: u64,
+    rounding: Rounding,
+) -> ProgramResult {
+    if match_commitment_account.owner != program_id {
+        log_event!(instruction, "match commitment isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut match_commitment = MatchCommitment::deserialize(&mut &match_commitment_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if match_commitment.campaign != *writing_account.key {
+        log_event!(instruction, "match commitment isn't for this campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut sponsor = load_sponsor(instruction, program_id, sponsor_account, &match_commitment.sponsor)?;
+
+    if match_commitment.cycle != campaign_data.cycle {
+        match_commitment.cycle = campaign_data.cycle;
+        match_commitment.matched_in_cycle = 0;
+    }
+
+    let ratio_bps = match_ratio_bps(&match_commitment.schedule, Clock::get()?.unix_timestamp);
+    let wanted = round_div(amount as u128 * ratio_bps as u128, 10_000, rounding) as u64;
+    let mut matched = wanted.min(match_commitment.committed - match_commitment.matched);
+    // The match counts as donated, so it only fills what the hard cap leaves after the donation.
+    if campaign_data.hard_cap != 0 {
+        let room = campaign_data
+            .hard_cap
+            .saturating_sub(campaign_data.amount_donated)
+            .saturating_sub(campaign_data.pending_amount());
+        matched = matched.min(room);
+    }
+    if matched == 0 {
+        return Ok(());
+    }
+
+    **match_commitment_account.try_borrow_mut_lamports()? -= matched;
+    **writing_account.try_borrow_mut_lamports()? += matched;
+
+    match_commitment.matched = match_commitment
+        .matched
+        .checked_add(matched)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    match_commitment.matched_in_cycle = match_commitment
+        .matched_in_cycle
+        .checked_add(matched)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    sponsor.total_matched = sponsor
+        .total_matched
+        .checked_add(matched)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    campaign_data.amount_donated = campaign_data
+        .amount_donated
+        .checked_add(matched)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    campaign_data.amount_matched = campaign_data
+        .amount_matched
+        .checked_add(matched)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    count_raised(global_stats, campaign_data.category, matched)?;
+
+    log_event!(instruction, "Matched {} lamports from sponsor {}", matched, match_commitment.sponsor);
+    sol_log_data(&[
+        b"match",
+        writing_account.key.as_ref(),
+        match_commitment.sponsor.as_ref(),
+        &matched.to_le_bytes(),
+    ]);
+
+    match_commitment.serialize(&mut &mut match_commitment_account.data.borrow_mut()[..])?;
+    sponsor.serialize(&mut &mut sponsor_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Once the campaign is finalized the sponsor closes the commitment and gets back what wasn't matched.
+// If the campaign failed the matched lamports come back from the campaign as well.
+fn release_match(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ReleaseMatch;
+    let accounts_iter = &mut accounts.iter();
+    let match_commitment_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+
+    if match_commitment_account.owner != program_id {
+        log_event!(instruction, "match commitment isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !wallet_account.is_signer {
+        log_event!(instruction, "sponsor wallet should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let match_commitment = MatchCommitment::deserialize(&mut &match_commitment_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if match_commitment.campaign != *writing_account.key || match_commitment.sponsor != *wallet_account.key {
+        log_event!(instruction, "match commitment doesn't belong to this campaign and sponsor");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if campaign_data.state == CampaignState::Active {
+        log_event!(instruction, "Campaign isn't finalized yet");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let failed = campaign_data.state == CampaignState::Failed || campaign_data.state == CampaignState::Liquidated;
+    log_phase!(instruction, "cpi");
+    if failed && match_commitment.cycle == campaign_data.cycle && match_commitment.matched_in_cycle > 0 {
+        // The matched lamports are in the refund snapshot like the donations, so the sponsor gets
+        // the same share of them as every donor when the pool is short.
+        let refund = pro_rata_refund(&campaign_data, match_commitment.matched_in_cycle);
+        // A campaign holding less, e.g. below its rent exemption after the rent went up, pays what
+        // it has above its rent and reserved fees, down to nothing. The match still counts as
+        // settled and the sponsor still gets the commitment's escrow back below.
+        let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
+        let available = writing_account
+            .lamports()
+            .saturating_sub(rent_exemption)
+            .saturating_sub(campaign_data.fees_reserved);
+        let refund = if available < refund {
+            log_event!(instruction, "Campaign can only give back {} of {} matched lamports", available, refund);
+            available
+        } else {
+            refund
+        };
+        **writing_account.try_borrow_mut_lamports()? -= refund;
+        **wallet_account.try_borrow_mut_lamports()? += refund;
+        // Like a donor refund, so a failed campaign can be settled and renewed.
+        campaign_data.total_refunded = campaign_data
+            .total_refunded
+            .checked_add(match_commitment.matched_in_cycle)
+            .ok_or(ProgramError::InvalidAccountData)?;
+        campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    }
+
+    **wallet_account.try_borrow_mut_lamports()? += **match_commitment_account.lamports.borrow();
+    **match_commitment_account.try_borrow_mut_lamports()? = 0;
+    match_commitment_account.data.borrow_mut().fill(0);
+
+    Ok(())
+}
+
+// Every campaign can have a feed of short announcements from its admin, derived from the campaign.
+// Donors' clients poll it to find out about updates. It is append-only and bounded,
+// so the account is created with room for all the updates it can ever hold.
+const MAX_UPDATES: usize = 16;
+const MAX_UPDATE_MESSAGE_LEN: usize = 140;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct CampaignUpdate {
+    pub posted_at: i64,
+    pub message: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct UpdateFeed {
+    pub campaign: Pubkey,
+    pub updates: Vec<CampaignUpdate>,
+}
+
+// Size of a full UpdateFeed.
+fn update_feed_len() -> usize {
+    serialized_len::<UpdateFeed>() + MAX_UPDATES * (serialized_len::<CampaignUpdate>() + MAX_UPDATE_MESSAGE_LEN)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct PostUpdateRequest {
+    pub message: String,
+}
+
+fn post_update(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::PostUpdate;
+    let accounts_iter = &mut accounts.iter();
+    let update_feed_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can post updates");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: PostUpdateRequest = parse_instruction(instruction, instruction_data)?;
+    if input_data.message.is_empty() || input_data.message.len() > MAX_UPDATE_MESSAGE_LEN {
+        log_event!(instruction, "Update message should be between 1 and {} bytes", MAX_UPDATE_MESSAGE_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (update_feed_key, bump) = find_update_feed_address(program_id, writing_account.key);
+    if update_feed_key != *update_feed_account.key {
+        log_event!(instruction, "update feed isn't the update feed PDA of this campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "cpi");
+    // The feed is created with the first update, paid by the admin.
+    let mut update_feed = if update_feed_account.owner == program_id {
+        UpdateFeed::deserialize(&mut &update_feed_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?
+    } else {
+        create_pda_account(
+            instruction,
+            program_id,
+            admin_account,
+            update_feed_account,
+            system_program,
+            update_feed_len(),
+            &[UPDATE_FEED_SEED, writing_account.key.as_ref(), &[bump]],
+        )?;
+        UpdateFeed {
+            campaign: *writing_account.key,
+            updates: Vec::new(),
+        }
+    };
+
+    check_entries(instruction, update_feed.updates.len() + 1, MAX_UPDATES, "updates in a feed")?;
+
+    update_feed.updates.push(CampaignUpdate {
+        posted_at: Clock::get()?.unix_timestamp,
+        message: input_data.message,
+    });
+    log_phase!(instruction, "serialize");
+    update_feed.serialize(&mut &mut update_feed_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetFeeExemptRequest {
+    pub fee_exempt: bool,
+}
+
+// The platform authority exempts a verified charity campaign from platform fees, or takes it back.
+fn set_fee_exempt(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetFeeExempt;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_authority(instruction, program_id, config_account, authority_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let input_data: SetFeeExemptRequest = parse_instruction(instruction, instruction_data)?;
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    campaign_data.fee_exempt = input_data.fee_exempt;
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// The charity allowlist is one PDA per charity wallet, derived from the wallet.
+// The account existing is what puts the wallet on the list.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+struct CharityEntry {
+    pub wallet: Pubkey,
+}
+
+fn is_allowlisted_charity(program_id: &Pubkey, charity_account: &AccountInfo, wallet: &Pubkey) -> bool {
+    let (charity_key, _) = find_charity_address(program_id, wallet);
+    charity_key == *charity_account.key && charity_account.owner == program_id
+}
+
+// Checks the signer is the platform authority, for the instructions maintaining the allowlist.
+fn check_authority(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    config_account: &AccountInfo,
+    authority_account: &AccountInfo,
+) -> ProgramResult {
+    if !authority_account.is_signer {
+        log_event!(instruction, "authority should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let config = load_config(instruction, program_id, config_account)?;
+    if config.authority != *authority_account.key {
+        log_event!(instruction, "Only the platform authority can do this");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// The platform authority puts a charity wallet on the allowlist, the wallet is passed as an account.
+fn add_charity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::AddCharity;
+    let accounts_iter = &mut accounts.iter();
+    let charity_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+
+    let (charity_key, bump) = find_charity_address(program_id, wallet_account.key);
+    if charity_key != *charity_account.key {
+        log_event!(instruction, "charity account isn't the charity PDA of this wallet");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if charity_account.owner == program_id {
+        log_event!(instruction, "charity is already on the allowlist");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        authority_account,
+        charity_account,
+        system_program,
+        serialized_len::<CharityEntry>(),
+        &[CHARITY_SEED, wallet_account.key.as_ref(), &[bump]],
+    )?;
+
+    let entry = CharityEntry {
+        wallet: *wallet_account.key,
+    };
+    log_phase!(instruction, "serialize");
+    entry.serialize(&mut &mut charity_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Takes a charity off the allowlist, the rent goes back to the authority.
+// Campaigns it already created keep their fee exemption until the authority changes it.
+fn remove_charity(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RemoveCharity;
+    let accounts_iter = &mut accounts.iter();
+    let charity_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+
+    if charity_account.owner != program_id {
+        log_event!(instruction, "charity account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    log_phase!(instruction, "deserialize");
+    let entry = CharityEntry::try_from_slice(*charity_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if !is_allowlisted_charity(program_id, charity_account, &entry.wallet) {
+        log_event!(instruction, "charity account isn't the charity PDA of its wallet");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    log_phase!(instruction, "cpi");
+    **authority_account.try_borrow_mut_lamports()? += **charity_account.lamports.borrow();
+    **charity_account.try_borrow_mut_lamports()? = 0;
+    charity_account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+// Pays the fees reserved in a successful campaign to the treasury. Anyone can call it.
+//
+// How refundable fees work: with `refund_fees` on in the config, the treasury share of a withdraw
+// fee isn't sent to the treasury but stays in the campaign account as `fees_reserved`, where the
+// admin can't withdraw it. If the campaign succeeds the reserve is collected here. If it fails or
+// is liquidated the reserve is dropped, and the lamports are paid out with the donor refunds.
+// The fee collector never has to give anything back. The insurance slice isn't reserved.
+fn collect_fees(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CollectFees;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if campaign_data.state != CampaignState::Successful {
+        log_event!(instruction, "Reserved fees can only be collected from a successful campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    check_fee_collector(instruction, treasury_account)?;
+    log_phase!(instruction, "cpi");
+    **writing_account.try_borrow_mut_lamports()? -= campaign_data.fees_reserved;
+    **treasury_account.try_borrow_mut_lamports()? += campaign_data.fees_reserved;
+    campaign_data.fees_reserved = 0;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// The read-only queries below don't deserialize the whole campaign. Everything from
+// `amount_donated` up to `pause_until` has a fixed size, so once we skip the two
+// strings in front of it we can read those fields at fixed offsets.
+// New fixed size fields go right before `withdraw_recipients` to keep these offsets valid.
+// The first ones are read through `CampaignCounters`.
+const FEE_EXEMPT_OFFSET: usize = 99;
+const ADMIN_PAUSED_OFFSET: usize = 286;
+const PAUSE_UNTIL_OFFSET: usize = 287;
+// Indexers skip archived campaigns by this byte.
+pub const ARCHIVED_OFFSET: usize = 372;
+
+// Returns where `amount_donated` starts, after the admin and the two strings.
+fn campaign_fixed_fields_start(data: &[u8]) -> Option<usize> {
+    let mut offset = 32 + 32 + 8;
+    for _ in 0..2 {
+        let len_bytes = data.get(offset..offset + 4)?;
+        let len = u32::from_le_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+        offset = offset.checked_add(4 + len)?;
+    }
+    Some(offset)
+}
+
+// The first fields of the fixed part, `amount_donated` up to `donor_count`, read in place
+// without decoding anything. The layout is the Borsh one: packed, little endian (like the
+// program's target), bools and the state as one byte each. CampaignDetails and this struct have
+// to change together.
+#[repr(C, packed)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CampaignCounters {
+    pub amount_donated: u64,
+    pub disputed: u8,
+    pub donations_paused: u8,
+    pub goal: u64,
+    pub end_timestamp: i64,
+    pub state: u8,
+    pub donor_count: u32,
+}
+
+// Clients reading the counter block take its size from here.
+pub const CAMPAIGN_COUNTERS_LEN: usize = 31;
+const _: () = assert!(std::mem::size_of::<CampaignCounters>() == CAMPAIGN_COUNTERS_LEN);
+
+// The counter block of a campaign's account data. Being packed it has an alignment of 1,
+// so any sub-slice of the right length can be cast, the bounds are the only thing to check.
+pub fn campaign_counters(data: &[u8]) -> Result<&CampaignCounters, ProgramError> {
+    let start = campaign_fixed_fields_start(data).ok_or(ProgramError::InvalidAccountData)?;
+    let bytes = data
+        .get(start..start + CAMPAIGN_COUNTERS_LEN)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(bytemuck::from_bytes(bytes))
+}
+
+pub fn campaign_counters_mut(data: &mut [u8]) -> Result<&mut CampaignCounters, ProgramError> {
+    let start = campaign_fixed_fields_start(data).ok_or(ProgramError::InvalidAccountData)?;
+    let bytes = data
+        .get_mut(start..start + CAMPAIGN_COUNTERS_LEN)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    Ok(bytemuck::from_bytes_mut(bytes))
+}
+
+impl From<&CampaignDetails> for CampaignCounters {
+    fn from(campaign: &CampaignDetails) -> Self {
+        CampaignCounters {
+            amount_donated: campaign.amount_donated,
+            disputed: campaign.disputed as u8,
+            donations_paused: campaign.donations_paused as u8,
+            goal: campaign.goal,
+            end_timestamp: campaign.end_timestamp,
+            state: campaign.state as u8,
+            donor_count: campaign.donor_count,
+        }
+    }
+}
+
+fn read_u64_at(data: &[u8], offset: usize) -> Option<u64> {
+    let bytes = data.get(offset..offset + 8)?;
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    Some(u64::from_le_bytes(buf))
+}
+
+// Returns the program version as return data, it doesn't need any account.
+fn version() -> ProgramResult {
+    log_phase!(Instruction::Version, "serialize");
+    set_return_data(env!("CARGO_PKG_VERSION").as_bytes());
+    Ok(())
+}
+
+// Fails unless the campaign is program owned, active and not disputed or paused.
+// The state byte is returned as return data.
+fn health_check(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let instruction = Instruction::HealthCheck;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let data = writing_account.data.borrow();
+    log_phase!(instruction, "deserialize");
+    let counters = *campaign_counters(&data)?;
+    log_phase!(instruction, "validate");
+    let start = campaign_fixed_fields_start(&data).ok_or(ProgramError::InvalidAccountData)?;
+    let state = counters.state;
+    let admin_paused = *data.get(start + ADMIN_PAUSED_OFFSET).ok_or(ProgramError::InvalidAccountData)?;
+    let pause_until = read_u64_at(&data, start + PAUSE_UNTIL_OFFSET).ok_or(ProgramError::InvalidAccountData)? as i64;
+    let admin_paused = admin_pause_active(admin_paused != 0, pause_until, Clock::get()?.unix_timestamp);
+
+    log_phase!(instruction, "serialize");
+    set_return_data(&[state]);
+
+    if state != CampaignState::Active as u8 || counters.disputed != 0 || counters.donations_paused != 0 || admin_paused {
+        log_event!(instruction, "Campaign isn't healthy");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+// Returns how many lamports the admin could withdraw right now as a little-endian u64,
+// before fees. It doesn't check cooldowns or epoch limits. Unlike the other queries it decodes
+// the campaign, the answer has to come from `withdrawable_balance` like the withdraw's.
+fn get_withdrawable(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let instruction = Instruction::GetWithdrawable;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    let withdrawable = withdrawable_balance(writing_account, &campaign_data)?;
+
+    log_phase!(instruction, "serialize");
+    set_return_data(&withdrawable.to_le_bytes());
+    Ok(())
+}
+
+// Bits of the status byte returned by get_campaign_summary.
+pub const SUMMARY_ACTIVE: u8 = 1 << 0;
+pub const SUMMARY_PAUSED: u8 = 1 << 1;
+pub const SUMMARY_CLOSED: u8 = 1 << 2;
+pub const SUMMARY_FROZEN: u8 = 1 << 3;
+pub const SUMMARY_DISPUTED: u8 = 1 << 4;
+pub const SUMMARY_VERIFIED: u8 = 1 << 5;
+
+// Returns a compact summary of the campaign as return data, 30 bytes, integers little endian:
+// status bits (u8), state (u8), amount_donated (u64), goal (u64), end_timestamp (i64), donor_count (u32).
+// Paused covers a resolver pause and an admin pause that hasn't expired, frozen is a liquidated
+// campaign and verified a fee exempt one.
+fn get_campaign_summary(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let instruction = Instruction::GetCampaignSummary;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let data = writing_account.data.borrow();
+    let start = campaign_fixed_fields_start(&data).ok_or(ProgramError::InvalidAccountData)?;
+    let byte_at = |offset: usize| data.get(start + offset).copied().ok_or(ProgramError::InvalidAccountData);
+    let u64_at = |offset: usize| read_u64_at(&data, start + offset).ok_or(ProgramError::InvalidAccountData);
+
+    let counters = *campaign_counters(&data)?;
+    log_phase!(instruction, "validate");
+    let state = counters.state;
+    let admin_paused = admin_pause_active(
+        byte_at(ADMIN_PAUSED_OFFSET)? != 0,
+        u64_at(PAUSE_UNTIL_OFFSET)? as i64,
+        Clock::get()?.unix_timestamp,
+    );
+
+    let mut status = 0u8;
+    if state == CampaignState::Active as u8 {
+        status |= SUMMARY_ACTIVE;
+    }
+    if counters.donations_paused != 0 || admin_paused {
+        status |= SUMMARY_PAUSED;
+    }
+    if state == CampaignState::Closed as u8 {
+        status |= SUMMARY_CLOSED;
+    }
+    if state == CampaignState::Liquidated as u8 {
+        status |= SUMMARY_FROZEN;
+    }
+    if counters.disputed != 0 {
+        status |= SUMMARY_DISPUTED;
+    }
+    if byte_at(FEE_EXEMPT_OFFSET)? != 0 {
+        status |= SUMMARY_VERIFIED;
+    }
+
+    let mut summary = Vec::with_capacity(30);
+    summary.push(status);
+    summary.push(state);
+    summary.extend_from_slice(&{ counters.amount_donated }.to_le_bytes());
+    summary.extend_from_slice(&{ counters.goal }.to_le_bytes());
+    summary.extend_from_slice(&{ counters.end_timestamp }.to_le_bytes());
+    summary.extend_from_slice(&{ counters.donor_count }.to_le_bytes());
+    log_phase!(instruction, "serialize");
+    set_return_data(&summary);
+    Ok(())
+}
+
+// Returns amount_donated and goal as return data, 16 bytes little endian. Progress bars poll
+// this often, it only reads the counter block and doesn't need the clock.
+fn get_progress(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let instruction = Instruction::GetProgress;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let data = writing_account.data.borrow();
+    let counters = *campaign_counters(&data)?;
+
+    log_phase!(instruction, "serialize");
+    let mut progress = [0u8; 16];
+    progress[..8].copy_from_slice(&{ counters.amount_donated }.to_le_bytes());
+    progress[8..].copy_from_slice(&{ counters.goal }.to_le_bytes());
+    set_return_data(&progress);
+    Ok(())
+}
+
+// Bits of the status byte returned by get_donor_standing.
+pub const STANDING_REFUNDABLE: u8 = 1 << 0;
+pub const STANDING_REFUNDED: u8 = 1 << 1;
+pub const STANDING_NON_REFUNDABLE: u8 = 1 << 2;
+
+// Tiers of `get_donor_standing`. A loyal donor gave the campaign enough for the config's loyalty
+// discount on the suggested tip.
+pub const TIER_NONE: u8 = 0;
+pub const TIER_LOYAL: u8 = 1;
+
+// Returns what a donor gave in the current cycle of the campaign as return data, 10 bytes:
+// the total (u64, little endian), status bits and the donor's tier. Refundable means they can claim
+// a refund right now, with a proof when the campaign posted a refund root. A donor who never gave
+// gets zeros. There is no leaderboard, ranking donors would take every record of the campaign,
+// so there is no rank to return.
+fn get_donor_standing(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let instruction = Instruction::GetDonorStanding;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    // The loyalty threshold the tier is measured against.
+    let config_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donor_account.key);
+    if record_key != *donation_record_account.key {
+        log_event!(instruction, "donation record account isn't the donation record PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut standing = [0u8; 10];
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    if donation_record_account.owner == program_id {
+        let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let donation_record = DonationRecord::try_from_slice(*donation_record_account.data.borrow())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        if donation_record.cycle == campaign_data.cycle && donation_record.amount > 0 {
+            let mut status = 0u8;
+            if donation_record.refunded {
+                status |= STANDING_REFUNDED;
+            }
+            if donation_record.non_refundable {
+                status |= STANDING_NON_REFUNDABLE;
+            }
+            let refunds_open = (campaign_data.state == CampaignState::Failed
+                || campaign_data.state == CampaignState::Liquidated)
+                && !refund_window_closed(&campaign_data)?;
+            if refunds_open && !donation_record.refunded && !donation_record.non_refundable {
+                status |= STANDING_REFUNDABLE;
+            }
+            standing[..8].copy_from_slice(&donation_record.amount.to_le_bytes());
+            standing[8] = status;
+            standing[9] = if tip_discount_bps(&config, donation_record.amount) > 0 { TIER_LOYAL } else { TIER_NONE };
+        }
+    }
+    log_phase!(instruction, "serialize");
+    set_return_data(&standing);
+    Ok(())
+}
+
+// When the refund window of a campaign failing at `now` ends, 0 when refunds never expire.
+fn refund_deadline(config: &PlatformConfig, now: i64) -> i64 {
+    if config.refund_window_secs == 0 {
+        0
+    } else {
+        now.saturating_add(config.refund_window_secs as i64)
+    }
+}
+
+fn refund_window_closed(campaign_data: &CampaignDetails) -> Result<bool, ProgramError> {
+    Ok(campaign_data.refund_deadline != 0 && Clock::get()?.unix_timestamp >= campaign_data.refund_deadline)
+}
+
+// Once the refund window of a failed campaign is over, anyone can sweep the refunds nobody
+// claimed to the treasury. The campaign keeps its rent and ends up Closed, so a late claim
+// gets a RefundWindowClosed error instead of a confusing balance error.
+fn sweep_unclaimed_refunds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SweepUnclaimedRefunds;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if campaign_data.state != CampaignState::Failed && campaign_data.state != CampaignState::Liquidated {
+        log_event!(instruction, "Only failed campaigns have refunds to sweep");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if !refund_window_closed(&campaign_data)? {
+        log_event!(instruction, "Refund window is still open");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
+    // What the admin of a failed campaign can still withdraw isn't a refund.
+    let unclaimed = writing_account
+        .lamports()
+        .saturating_sub(rent_exemption)
+        .saturating_sub(campaign_data.failed_withdrawable);
+
+    check_fee_collector(instruction, treasury_account)?;
+    log_phase!(instruction, "cpi");
+    **writing_account.try_borrow_mut_lamports()? -= unclaimed;
+    **treasury_account.try_borrow_mut_lamports()? += unclaimed;
+
+    campaign_data.state = CampaignState::Closed;
+    log_event!(instruction, "Swept {} lamports of unclaimed refunds", unclaimed);
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Records what the campaign holds for refunds and what it owes, right when it becomes refundable.
+// Withdrawals are blocked from then on, so the pool only shrinks through the claims themselves.
+fn snapshot_refund_pool(campaign_data: &mut CampaignDetails, writing_account: &AccountInfo) -> ProgramResult {
+    // Donations still pending are refundable like the others.
+    confirm_pending_donations(campaign_data, 0, true);
+    let available = writing_account
+        .lamports()
+        .saturating_sub(campaign_min_balance(writing_account.data_len())?)
+        .saturating_sub(campaign_data.fees_reserved);
+    campaign_data.refundable_total = campaign_data
+        .amount_donated
+        .checked_sub(campaign_data.total_refunded)
+        .and_then(|total| total.checked_sub(campaign_data.non_refundable_total))
+        .ok_or(ProgramError::InvalidAccountData)?;
+    // Refundable donors come first. Whatever is over goes to the admin of a failed campaign,
+    // a liquidated one leaves it in the campaign until it is swept.
+    campaign_data.refund_pool = available.min(campaign_data.refundable_total);
+    campaign_data.failed_withdrawable = if campaign_data.state == CampaignState::Failed {
+        available - campaign_data.refund_pool
+    } else {
+        0
+    };
+    Ok(())
+}
+
+// The donor's share of the refund pool: `amount * refund_pool / refundable_total` rounded down,
+// never more then the donation itself. Rounding down means the claims can't add up to more
+// then the pool, whatever order they come in, so it doesn't follow the config's rounding.
+fn pro_rata_refund(campaign_data: &CampaignDetails, amount: u64) -> u64 {
+    if campaign_data.refundable_total == 0 || campaign_data.refund_pool >= campaign_data.refundable_total {
+        return amount;
+    }
+    let share = amount as u128 * campaign_data.refund_pool as u128 / campaign_data.refundable_total as u128;
+    share as u64
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RedirectFundsRequest {
+    pub amount: u64,
+}
+
+// Collaborative fundraising: the admin moves part of what their campaign raised into a partner
+// campaign. It leaves the campaign like a withdraw, so the same locks, reserves and limits apply,
+// but no fee is taken since the funds stay on the platform. The partner takes them as a
+// non-refundable donation of the campaign, with its own donation record and the partner's donor
+// limits, so nothing a real donor can claim back ends up counting them. Like a split part, a
+// redirect the partner's hard cap would cut down fails.
+// A campaign with withdraw recipients can only redirect to a partner in that list, and a
+// timelocked campaign can't redirect at all: the partner's admin could withdraw right away,
+// around the timelock.
+fn redirect_funds(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RedirectFunds;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let partner_account = next_account_info(accounts_iter)?;
+    // The campaign's donation record in the partner, paid by the admin the first time, the
+    // global stats moving the amount to the partner's category and the config with the donation
+    // limits the partner applies.
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id || partner_account.owner != program_id {
+        log_event!(instruction, "campaign accounts aren't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+    if writing_account.key == partner_account.key {
+        log_event!(instruction, "A campaign can't redirect funds to itself");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut partner_data = CampaignDetails::deserialize(&mut &partner_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can redirect funds");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.disputed {
+        log_event!(instruction, "Campaign is under dispute, withdraw is blocked");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state != CampaignState::Active && campaign_data.state != CampaignState::Successful {
+        log_event!(instruction, "Campaign failed, funds can only be refunded");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_withdrawals_open(instruction, &campaign_data)?;
+    if campaign_data.withdraw_delay_secs != 0 {
+        log_event!(instruction, "Withdrawals of this campaign are timelocked, funds can't be redirected");
+        return Err(FundError::WithdrawNotMatured.into());
+    }
+    if !campaign_data.withdraw_recipients.is_empty() && !campaign_data.withdraw_recipients.contains(partner_account.key) {
+        log_event!(instruction, "{} isn't an allowed withdraw recipient", partner_account.key);
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // The partner takes them like a donation.
+    let config = load_config(instruction, program_id, config_account)?;
+    check_can_donate(instruction, &partner_data)?;
+
+    let input_data: RedirectFundsRequest = parse_instruction(instruction, instruction_data)?;
+    check_donation_size(instruction, &config, &partner_data, input_data.amount)?;
+    if accepted_donation(instruction, &partner_data, input_data.amount)? != input_data.amount {
+        log_event!(instruction, "Partner can't take the whole amount");
+        return Err(FundError::HardCapReached.into());
+    }
+
+    check_campaign_rent(instruction, writing_account)?;
+    let available = withdrawable_balance(writing_account, &campaign_data)?;
+    if input_data.amount == 0 || available < input_data.amount {
+        log_event!(instruction, "Insufficent balance");
+        return Err(insufficient_funds(input_data.amount, available));
+    }
+
+    check_withdraw_policy(instruction, &campaign_data)?;
+    check_withdraw_limits(instruction, &mut campaign_data, input_data.amount)?;
+    let amount_donated = campaign_data.amount_donated;
+    for milestone in campaign_data.milestones.iter_mut() {
+        if milestone.amount <= amount_donated {
+            milestone.released = true;
+        }
+    }
+
+    campaign_data.total_redirected = campaign_data
+        .total_redirected
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let partner_balance = partner_account
+        .lamports()
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+
+    log_phase!(instruction, "cpi");
+    let mut donation_record = load_or_create_donation_record(
+        instruction,
+        program_id,
+        partner_account,
+        writing_account,
+        admin_account,
+        donation_record_account,
+        system_program,
+    )?;
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    // The lamports were counted in the campaign's category when they came in, now they count in
+    // the partner's.
+    uncount_raised(&mut global_stats, campaign_data.category, input_data.amount);
+    credit_donation(instruction, &config, &mut partner_data, &mut global_stats, &mut donation_record, input_data.amount, true)?;
+    **writing_account.try_borrow_mut_lamports()? -= input_data.amount;
+    **partner_account.try_borrow_mut_lamports()? = partner_balance;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    partner_data.serialize(&mut &mut partner_account.data.borrow_mut()[..])?;
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Refunds claimed with a merkle proof are remembered in a PDA per campaign and donor.
+// Proofs longer then this would be a tree of more then 2^32 donors.
+const MAX_PROOF_LEN: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+struct RefundClaim {
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetRefundRootRequest {
+    pub root: [u8; 32],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ClaimRefundWithProofRequest {
+    pub amount: u64,
+    pub proof: Vec<[u8; 32]>,
+}
+
+// A leaf of the refund tree is the hash of the donor's key followed by the amount in little endian.
+fn refund_leaf(donor: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[donor.as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+// Pairs are hashed smallest first, so the proof doesn't have to say on which side each node is.
+fn verify_merkle_proof(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let mut node = leaf;
+    for sibling in proof {
+        node = if node <= *sibling {
+            hashv(&[&node, sibling]).to_bytes()
+        } else {
+            hashv(&[sibling, &node]).to_bytes()
+        };
+    }
+    hashes_match(&node, root)
+}
+
+// The platform authority posts the refund root of a failed campaign with many donors.
+// It can only be set once, a new root could pay somebody a second time.
+fn set_refund_root(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetRefundRoot;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_authority(instruction, program_id, config_account, authority_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let input_data: SetRefundRootRequest = parse_instruction(instruction, instruction_data)?;
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.state != CampaignState::Failed && campaign_data.state != CampaignState::Liquidated {
+        log_event!(instruction, "Only failed campaigns can be refunded");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.refund_root != [0; 32] {
+        log_event!(instruction, "Refund root is already set");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    // The tree is built from the donations, it can't know who was refunded with their record.
+    if campaign_data.total_refunded != 0 {
+        log_event!(instruction, "Donors were already refunded with their donation records");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if input_data.root == [0; 32] {
+        log_event!(instruction, "Refund root can't be empty");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    campaign_data.refund_root = input_data.root;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A donor claims their refund with a proof that (donor, amount) is in the campaign's refund tree.
+// The claim PDA is created on the first claim, when it already exists the refund was paid.
+// The donor's donation record is passed too, whether it exists or not: a donor refunded with it
+// before the root was posted can't claim again, and the insurance pool sees the refund once paid.
+fn claim_refund_with_proof(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ClaimRefundWithProof;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let refund_claim_account = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: ClaimRefundWithProofRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.proof.len() > MAX_PROOF_LEN {
+        log_event!(instruction, "Proof can't be longer then {} hashes", MAX_PROOF_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    if campaign_data.state == CampaignState::Closed || refund_window_closed(&campaign_data)? {
+        log_event!(instruction, "Refund window is closed");
+        return Err(FundError::RefundWindowClosed.into());
+    }
+    if campaign_data.refund_root == [0; 32] {
+        log_event!(instruction, "Campaign has no refund root");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let leaf = refund_leaf(donator.key, input_data.amount);
+    if !verify_merkle_proof(&campaign_data.refund_root, leaf, &input_data.proof) {
+        log_event!(instruction, "Invalid refund proof");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (claim_key, bump) = find_refund_claim_address(program_id, writing_account.key, donator.key);
+    if claim_key != *refund_claim_account.key {
+        log_event!(instruction, "refund claim isn't the claim PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if refund_claim_account.owner == program_id {
+        log_event!(instruction, "Refund already claimed");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donator.key);
+    if record_key != *donation_record_account.key {
+        log_event!(instruction, "donation record isn't the donation PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    // Only a record of the current cycle was refundable, like in claim_refund.
+    let mut donation_record = if donation_record_account.owner == program_id {
+        let record = DonationRecord::try_from_slice(*donation_record_account.data.borrow())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        Some(record).filter(|record| record.cycle == campaign_data.cycle)
+    } else {
+        None
+    };
+    if donation_record.as_ref().is_some_and(|record| record.refunded) {
+        log_event!(instruction, "Donor was already refunded with their donation record");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // The leaf is what the donor gave, when the pool is short they get the same share of it as
+    // the donors claiming with their records. What the insurance pool paid isn't refunded again.
+    let insurance_paid = donation_record.as_ref().map_or(0, |record| record.insurance_paid);
+    let refund = pro_rata_refund(&campaign_data, input_data.amount).min(input_data.amount.saturating_sub(insurance_paid));
+    let available = writing_account
+        .lamports()
+        .saturating_sub(campaign_min_balance(writing_account.data_len())?);
+    if available < refund {
+        log_event!(instruction, "Insufficent balance");
+        return Err(insufficient_funds(refund, available));
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        donator,
+        refund_claim_account,
+        system_program,
+        serialized_len::<RefundClaim>(),
+        &[REFUND_CLAIM_SEED, writing_account.key.as_ref(), donator.key.as_ref(), &[bump]],
+    )?;
+
+    **writing_account.try_borrow_mut_lamports()? -= refund;
+    **donator.try_borrow_mut_lamports()? += refund;
+
+    campaign_data.total_refunded = campaign_data
+        .total_refunded
+        .checked_add(input_data.amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+
+    RefundClaim { amount: refund }
+        .serialize(&mut &mut refund_claim_account.data.borrow_mut()[..])?;
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    if let Some(donation_record) = donation_record.as_mut() {
+        donation_record.refunded = true;
+        donation_record.refund_paid = refund;
+        donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    }
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetSoftGoalRequest {
+    pub soft_goal: u64,
+}
+
+// The admin sets or changes the soft goal of an active campaign, 0 removes it.
+fn set_soft_goal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetSoftGoal;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can set the soft goal");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state != CampaignState::Active {
+        log_event!(instruction, "Campaign isn't active anymore");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: SetSoftGoalRequest = parse_instruction(instruction, instruction_data)?;
+
+    if input_data.soft_goal > campaign_data.goal {
+        log_event!(instruction, "soft_goal can't be more then the goal");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    campaign_data.soft_goal = input_data.soft_goal;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A split donation can go to at most this many campaigns.
+const MAX_SPLIT_TARGETS: usize = 8;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct DonateSplitRequest {
+    // One amount per target campaign, in the order the campaigns are passed.
+    pub amounts: Vec<u64>,
+}
+
+// Donation drives split a donation over several campaigns in one instruction, with a system
+// transfer from the donator to each of them like `donate_direct`.
+// The donator, system program, config, instructions sysvar and global stats come first, then a
+// (campaign, donation record, receipt, blocklist) group per target.
+// If any target can't take its part the whole instruction fails, so a drive is never half applied.
+// It is guarded, see the `guards` module.
+fn donate_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::DonateSplit;
+    let accounts_iter = &mut accounts.iter();
+    let donator = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+    let target_accounts = accounts_iter.as_slice();
+
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    check_not_sunset(instruction, &config)?;
+    guards::check_caller(instruction, program_id, instructions_sysvar, &config.cpi_callers)?;
+
+    let input_data: DonateSplitRequest = parse_instruction(instruction, instruction_data)?;
+    if input_data.amounts.is_empty() || input_data.amounts.len() > MAX_SPLIT_TARGETS {
+        log_event!(instruction, "A split donation goes to 1 to {} campaigns", MAX_SPLIT_TARGETS);
+        return Err(FundError::InvalidInstructionData.into());
+    }
+    if target_accounts.len() != input_data.amounts.len() * 4 {
+        log_event!(
+            instruction,
+            "Expected a campaign, donation record, receipt and blocklist account for each of the {} amounts",
+            input_data.amounts.len()
+        );
+        return Err(FundError::InvalidInstructionData.into());
+    }
+
+    let mut total: u64 = 0;
+    for amount in input_data.amounts.iter() {
+        if *amount == 0 {
+            log_event!(instruction, "Donation amount should be more then zero");
+            return Err(FundError::InvalidInstructionData.into());
+        }
+        total = total.checked_add(*amount).ok_or(FundError::InvalidInstructionData)?;
+    }
+    if total > donator.lamports() {
+        log_event!(instruction, "Donation amounts are more then the donator's balance");
+        return Err(insufficient_funds(total, donator.lamports()));
+    }
+
+    log_phase!(instruction, "cpi");
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    for (target, amount) in target_accounts.chunks_exact(4).zip(input_data.amounts.iter()) {
+        let writing_account = &target[0];
+        let donation_record_account = &target[1];
+        let receipt_account = &target[2];
+        let blocklist_account = &target[3];
+
+        if writing_account.owner != program_id {
+            log_event!(instruction, "writing_account isn't owned by program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
+        check_can_donate(instruction, &campaign_data)?;
+        check_donor_not_blocked(instruction, program_id, writing_account, donator, blocklist_account)?;
+        check_donation_size(instruction, &config, &campaign_data, *amount)?;
+        // A split is all or nothing, a part cut down by the hard cap fails the split.
+        if accepted_donation(instruction, &campaign_data, *amount)? != *amount {
+            log_event!(instruction, "{} can't take the whole donation", writing_account.key);
+            return Err(FundError::HardCapReached.into());
+        }
+
+        let mut donation_record = load_or_create_donation_record(
+            instruction,
+            program_id,
+            writing_account,
+            donator,
+            donator,
+            donation_record_account,
+            system_program,
+        )?;
+        credit_donation(instruction, &config, &mut campaign_data, &mut global_stats, &mut donation_record, *amount, false)?;
+        donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+        create_donation_receipt(
+            instruction,
+            program_id,
+            writing_account,
+            &mut campaign_data,
+            donator,
+            receipt_account,
+            system_program,
+            *amount,
+        )?;
+
+        invoke(
+            &system_instruction::transfer(donator.key, writing_account.key, *amount),
+            &[donator.clone(), writing_account.clone(), system_program.clone()],
+        )?;
+
+        log_event!(instruction, "Donated {} lamports to {}", amount, writing_account.key);
+        campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    }
+
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A batch withdraw can sweep at most this many campaigns.
+const MAX_WITHDRAW_MANY: usize = 16;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct WithdrawManyRequest {
+    // One amount per campaign, in the order the campaigns are passed. 0 withdraws everything available.
+    pub amounts: Vec<u64>,
+    // Fail the whole batch when one campaign can't be withdrawn from, instead of skipping it.
+    pub strict: bool,
+}
+
+// Admins running many campaigns sweep them in one instruction. The admin, config, treasury,
+// insurance pool and instructions sysvar come first, then the campaigns. Each campaign goes
+// through the same checks as `withdraw` and pays to the admin. It is guarded, see the `guards` module.
+fn withdraw_many(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::WithdrawMany;
+    let accounts_iter = &mut accounts.iter();
+    let admin_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let insurance_pool_account = next_account_info(accounts_iter)?;
+    let instructions_sysvar = next_account_info(accounts_iter)?;
+    let campaign_accounts = accounts_iter.as_slice();
+
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let input_data: WithdrawManyRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.amounts.is_empty() || input_data.amounts.len() > MAX_WITHDRAW_MANY {
+        log_event!(instruction, "A batch withdraw goes over 1 to {} campaigns", MAX_WITHDRAW_MANY);
+        return Err(FundError::InvalidInstructionData.into());
+    }
+    if campaign_accounts.len() != input_data.amounts.len() {
+        log_event!(instruction, "Expected a campaign for each of the {} amounts", input_data.amounts.len());
+        return Err(FundError::InvalidInstructionData.into());
+    }
+
+    let config = load_config(instruction, program_id, config_account)?;
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    guards::check_caller(instruction, program_id, instructions_sysvar, &config.cpi_callers)?;
+    let mut insurance_pool = load_insurance_pool(instruction, program_id, insurance_pool_account)?;
+
+    log_phase!(instruction, "cpi");
+    for (writing_account, amount) in campaign_accounts.iter().zip(input_data.amounts.iter()) {
+        let amount = if *amount == 0 { None } else { Some(*amount) };
+        let result = withdraw_from_campaign(
+            instruction,
+            program_id,
+            writing_account,
+            admin_account,
+            admin_account,
+            treasury_account,
+            insurance_pool_account,
+            &config,
+            &mut insurance_pool,
+            amount,
+            false,
+        );
+        if let Err(err) = result {
+            if input_data.strict {
+                return Err(err);
+            }
+            // The reason was logged by the failing check.
+            log_event!(instruction, "Skipped {}: {:?}", writing_account.key, err);
+        }
+    }
+    log_phase!(instruction, "serialize");
+    // A skipped campaign may have left an error payload, the batch itself succeeded.
+    set_return_data(&[]);
+
+    insurance_pool.serialize(&mut &mut insurance_pool_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Maps an admin and a campaign name hash to the campaign, see `campaign_name_hash`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct CampaignName {
+    pub campaign: Pubkey,
+}
+
+// Campaign names are compared by the hash of `name_key`, so "Clean  Water " and "clean water"
+// are the same name for an admin.
+pub fn campaign_name_hash(name: &str) -> [u8; 32] {
+    hash(name_key(name).as_bytes()).to_bytes()
+}
+
+// Creates the name PDA of `campaign`, it fails if the admin already has a campaign with that name.
+fn register_campaign_name<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    campaign: &AccountInfo<'a>,
+    admin: &AccountInfo<'a>,
+    name_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    name_hash: &[u8; 32],
+) -> ProgramResult {
+    let (name_key, bump) = find_campaign_name_address(program_id, admin.key, name_hash);
+    if name_key != *name_account.key {
+        log_event!(instruction, "name account isn't the name PDA of this admin and name");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if name_account.owner == program_id {
+        log_event!(instruction, "Admin already has a campaign with this name");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        instruction,
+        program_id,
+        admin,
+        name_account,
+        system_program,
+        serialized_len::<CampaignName>(),
+        &[CAMPAIGN_NAME_SEED, admin.key.as_ref(), name_hash, &[bump]],
+    )?;
+    CampaignName { campaign: *campaign.key }.serialize(&mut &mut name_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RenameCampaignRequest {
+    pub name: String,
+}
+
+// The admin renames a campaign. The old name PDA is closed and the new one created,
+// unless only the case or surrounding whitespace changed.
+fn rename_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RenameCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let old_name_account = next_account_info(accounts_iter)?;
+    let new_name_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can rename the campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_metadata_editable(instruction, &campaign_data)?;
+
+    let input_data: RenameCampaignRequest = parse_instruction(instruction, instruction_data)?;
+    let name = clean_text(instruction, &input_data.name)?;
+    if name.is_empty() {
+        log_event!(instruction, "name can't be empty");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let name_hash = campaign_name_hash(&name);
+
+    log_phase!(instruction, "cpi");
+    if name_hash != campaign_data.name_hash {
+        let (old_name_key, _) = find_campaign_name_address(program_id, admin_account.key, &campaign_data.name_hash);
+        if old_name_key != *old_name_account.key {
+            log_event!(instruction, "old name account isn't the name PDA of this campaign");
+            return Err(ProgramError::InvalidSeeds);
+        }
+        register_campaign_name(
+            instruction,
+            program_id,
+            writing_account,
+            admin_account,
+            new_name_account,
+            system_program,
+            &name_hash,
+        )?;
+
+        // Closing the old name PDA frees the name and gives the admin the rent back.
+        let old_name_lamports = old_name_account.lamports();
+        **old_name_account.try_borrow_mut_lamports()? = 0;
+        **admin_account.try_borrow_mut_lamports()? += old_name_lamports;
+        old_name_account.data.borrow_mut().fill(0);
+    }
+
+    campaign_data.name = name;
+    campaign_data.name_hash = name_hash;
+
+    // A longer name still has to leave room for the previous cycles and queued withdrawals.
+    let required_len = checked_account_len(
+        instruction,
+        campaign_data.try_to_vec()?.len(),
+        campaign_growth_room(&campaign_data),
+    )?;
+    if writing_account.data_len() < required_len {
+        log_event!(instruction, "New name doesn't fit in the campaign account");
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+fn check_not_sunset(instruction: Instruction, config: &PlatformConfig) -> ProgramResult {
+    if config.sunset {
+        log_event!(instruction, "The program is winding down, it takes no new campaigns or donations");
+        return Err(FundError::ProgramSunset.into());
+    }
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetSunsetRequest {
+    pub sunset: bool,
+}
+
+// The platform authority starts winding the program down, or calls it off.
+fn set_sunset(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetSunset;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetSunsetRequest = parse_instruction(instruction, instruction_data)?;
+    config.sunset = input_data.sunset;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetRoundingRequest {
+    pub rounding: Rounding,
+}
+
+// The platform authority picks how shares are rounded.
+fn set_rounding(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetRounding;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetRoundingRequest = parse_instruction(instruction, instruction_data)?;
+    config.rounding = input_data.rounding;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetTipLoyaltyRequest {
+    pub suggested_tip_bps: u16,
+    pub loyalty_min_donated: u64,
+    pub loyalty_discount_bps: u16,
+}
+
+// The platform authority sets the suggested tip and the loyalty discount on it.
+fn set_tip_loyalty(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetTipLoyalty;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetTipLoyaltyRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.suggested_tip_bps > 10_000 || input_data.loyalty_discount_bps > 10_000 {
+        log_event!(instruction, "suggested_tip_bps and loyalty_discount_bps can't be more then 10000");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    config.suggested_tip_bps = input_data.suggested_tip_bps;
+    config.loyalty_min_donated = input_data.loyalty_min_donated;
+    config.loyalty_discount_bps = input_data.loyalty_discount_bps;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetFeatureFeeRequest {
+    pub feature_fee_per_day: u64,
+}
+
+// The platform authority sets the price of featuring a campaign, 0 stops new features.
+fn set_feature_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetFeatureFee;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetFeatureFeeRequest = parse_instruction(instruction, instruction_data)?;
+    config.feature_fee_per_day = input_data.feature_fee_per_day;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetMinGoalRequest {
+    pub min_goal_lamports: u64,
+}
+
+// The platform authority sets the smallest goal new campaigns and goal changes can have.
+// Campaigns that already have a smaller goal keep it.
+fn set_min_goal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetMinGoal;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetMinGoalRequest = parse_instruction(instruction, instruction_data)?;
+    config.min_goal_lamports = input_data.min_goal_lamports;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetDonationLimitsRequest {
+    pub min_donation_lamports: u64,
+    pub max_donations_per_window: u32,
+    pub donation_window_secs: u64,
+}
+
+// The platform authority sets the smallest donation and the donation window of every campaign.
+// Donations already counted in a campaign's current window stay counted.
+fn set_donation_limits(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetDonationLimits;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetDonationLimitsRequest = parse_instruction(instruction, instruction_data)?;
+    config.min_donation_lamports = input_data.min_donation_lamports;
+    config.max_donations_per_window = input_data.max_donations_per_window;
+    config.donation_window_secs = input_data.donation_window_secs;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetLegacyDonateDisabledRequest {
+    pub disabled: bool,
+}
+
+// The platform authority turns the staging account donate path off, or back on.
+fn set_legacy_donate_disabled(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetLegacyDonateDisabled;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetLegacyDonateDisabledRequest = parse_instruction(instruction, instruction_data)?;
+
+    config.legacy_donate_disabled = input_data.disabled;
+
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// The description of a campaign lives in its own PDA, derived from the campaign.
+// Donations and withdrawals never touch it, only `update_campaign` does.
+// It is created with room for the longest description, so edits never have to resize it.
+const MAX_DESCRIPTION_LEN: usize = 1024;
+// Minimum time between two `update_campaign`, so donors aren't shown a description that keeps
+// changing under them. 0 turns the cooldown off.
+const UPDATE_COOLDOWN_SECS: i64 = 3_600;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct CampaignContent {
+    pub campaign: Pubkey,
+    pub description: String,
+    // When `update_campaign` last changed the description, 0 before the first edit. It comes after
+    // the description, in the room left for a longer one.
+    pub last_update_ts: i64,
+}
+
+// Size of a CampaignContent with a description of `description_len` bytes or less.
+fn campaign_content_len(instruction: Instruction, description_len: usize) -> Result<usize, ProgramError> {
+    checked_account_len(instruction, serialized_len::<CampaignContent>(), description_len.max(MAX_DESCRIPTION_LEN))
+}
+
+// `check_description` without the log, None for a description with control characters or too long.
+fn trimmed_description(description: &str) -> Option<String> {
+    trimmed_text(description).filter(|description| description.len() <= MAX_DESCRIPTION_LEN)
+}
+
+fn check_description(instruction: Instruction, description: &str) -> Result<String, ProgramError> {
+    let description = clean_text(instruction, description)?;
+    if description.len() > MAX_DESCRIPTION_LEN {
+        log_event!(instruction, "Description can't be longer then {} bytes", MAX_DESCRIPTION_LEN);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(description)
+}
+
+// Creates the content PDA of `campaign` with the given description and returns its key.
+fn create_campaign_content<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    campaign: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    content_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    description: String,
+) -> Result<Pubkey, ProgramError> {
+    let (content_key, bump) = find_campaign_content_address(program_id, campaign.key);
+    if content_key != *content_account.key {
+        log_event!(instruction, "content account isn't the content PDA of this campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if content_account.owner == program_id {
+        log_event!(instruction, "Campaign content already exists");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        instruction,
+        program_id,
+        payer,
+        content_account,
+        system_program,
+        campaign_content_len(instruction, description.len())?,
+        &[CAMPAIGN_CONTENT_SEED, campaign.key.as_ref(), &[bump]],
+    )?;
+    CampaignContent {
+        campaign: *campaign.key,
+        description,
+        last_update_ts: 0,
+    }
+    .serialize(&mut &mut content_account.data.borrow_mut()[..])?;
+    Ok(content_key)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetGoalRequest {
+    pub goal: u64,
+    pub withdraw_policy: WithdrawPolicy,
+}
+
+// The admin changes the goal and the withdraw policy of the current cycle. Both are free until the
+// first donation. After it the policy is frozen, and a GoalOnly campaign can only lower its goal:
+// donors gave on the promise that nothing is withdrawn before the goal is reached.
+fn set_goal(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetGoal;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    // Holds the smallest goal allowed.
+    let config_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can change the goal");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state != CampaignState::Active {
+        log_event!(instruction, "Campaign isn't active anymore");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: SetGoalRequest = parse_instruction(instruction, instruction_data)?;
+    let has_donations = campaign_data.donor_count > 0 || !campaign_data.pending_donations.is_empty();
+    if has_donations {
+        if input_data.withdraw_policy != campaign_data.withdraw_policy {
+            log_event!(instruction, "The withdraw policy can't change once the campaign got donations");
+            return Err(FundError::FundingModelLocked.into());
+        }
+        if campaign_data.withdraw_policy == WithdrawPolicy::GoalOnly && input_data.goal > campaign_data.goal {
+            log_event!(instruction, "The goal of a GoalOnly campaign can only be lowered once it got donations");
+            return Err(FundError::GoalLockedAfterDonations.into());
+        }
+    }
+    if campaign_data.soft_goal > input_data.goal {
+        log_event!(instruction, "goal can't be less then the soft_goal");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if campaign_data.hard_cap != 0 && campaign_data.hard_cap < input_data.goal {
+        log_event!(instruction, "goal can't be more then the hard_cap");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    check_goal_only_deadline(instruction, &input_data.withdraw_policy, campaign_data.end_timestamp)?;
+    let config = load_config(instruction, program_id, config_account)?;
+    check_min_goal(instruction, &config, &input_data.withdraw_policy, input_data.goal)?;
+
+    sol_log_data(&[
+        b"goal_changed",
+        writing_account.key.as_ref(),
+        &campaign_data.goal.to_le_bytes(),
+        &input_data.goal.to_le_bytes(),
+    ]);
+    campaign_data.goal = input_data.goal;
+    campaign_data.withdraw_policy = input_data.withdraw_policy;
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+const SECONDS_PER_DAY: u64 = 86_400;
+// Longest a campaign can be featured ahead of now.
+const MAX_FEATURE_SECS: u64 = 30 * SECONDS_PER_DAY;
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct FeatureCampaignRequest {
+    pub duration_secs: u64,
+}
+
+// The admin pays the treasury to have the campaign featured for `duration_secs` more.
+// The price is `feature_fee_per_day` prorated to the second, rounded up. Featuring an already
+// featured campaign extends it from its current expiry.
+fn feature_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::FeatureCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can feature the campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state != CampaignState::Active || campaign_data.archived {
+        log_event!(instruction, "Only active campaigns that aren't archived can be featured");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let config = load_config(instruction, program_id, config_account)?;
+    check_not_sunset(instruction, &config)?;
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if config.feature_fee_per_day == 0 {
+        log_event!(instruction, "Featuring campaigns is turned off");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: FeatureCampaignRequest = parse_instruction(instruction, instruction_data)?;
+    // Checked before any arithmetic, a duration over i64::MAX would wrap to a negative one.
+    if input_data.duration_secs == 0 || input_data.duration_secs > MAX_FEATURE_SECS {
+        log_event!(instruction, "A campaign can be featured for at most {} seconds ahead", MAX_FEATURE_SECS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let now = Clock::get()?.unix_timestamp;
+    let starts_at = campaign_data.featured_until.max(now);
+    let featured_until = starts_at
+        .checked_add(input_data.duration_secs as i64)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    if featured_until - now > MAX_FEATURE_SECS as i64 {
+        log_event!(instruction, "A campaign can be featured for at most {} seconds ahead", MAX_FEATURE_SECS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let fee = u64::try_from(
+        (input_data.duration_secs as u128 * config.feature_fee_per_day as u128).div_ceil(SECONDS_PER_DAY as u128),
+    )
+    .map_err(|_| ProgramError::InvalidInstructionData)?;
+    check_fee_collector(instruction, treasury_account)?;
+    log_phase!(instruction, "cpi");
+    invoke(
+        &system_instruction::transfer(admin_account.key, treasury_account.key, fee),
+        &[
+            admin_account.clone(),
+            treasury_account.clone(),
+            system_program.clone(),
+        ],
+    )?;
+
+    sol_log_data(&[
+        b"campaign_featured",
+        writing_account.key.as_ref(),
+        &featured_until.to_le_bytes(),
+        &fee.to_le_bytes(),
+    ]);
+    campaign_data.featured_until = featured_until;
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetCategoryRequest {
+    pub category: u8,
+}
+
+// The admin moves the campaign to another category. Only until its first donation: after it the
+// donations are counted in the old category's stats, and moving them along isn't worth the trouble.
+fn set_category(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetCategory;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let global_stats_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can change the category");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.donation_sequence > 0 {
+        log_event!(instruction, "The category can't change once the campaign got a donation");
+        return Err(FundError::CategoryLocked.into());
+    }
+
+    let input_data: SetCategoryRequest = parse_instruction(instruction, instruction_data)?;
+    check_category(instruction, input_data.category)?;
+
+    // Campaigns created before there were categories weren't counted, there is no telling them
+    // apart here, so the old count only goes down as far as zero.
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    let old_stats = &mut global_stats.categories[campaign_data.category as usize];
+    old_stats.campaigns_created = old_stats.campaigns_created.saturating_sub(1);
+    let new_stats = &mut global_stats.categories[input_data.category as usize];
+    new_stats.campaigns_created = new_stats
+        .campaigns_created
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "serialize");
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+
+    campaign_data.category = input_data.category;
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct UpdateCampaignRequest {
+    pub description: String,
+}
+
+// The admin edits the description. Only the content account is written,
+// so it doesn't conflict with donations landing on the campaign at the same time.
+fn update_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::UpdateCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let content_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id || content_account.owner != program_id {
+        log_event!(instruction, "campaign and content accounts should be owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    // v1 campaigns are read too, their admin can edit the description once it was migrated.
+    let campaign_data = load_campaign(&writing_account.data.borrow())?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can update the campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_metadata_editable(instruction, &campaign_data)?;
+
+    let (content_key, _) = find_campaign_content_address(program_id, writing_account.key);
+    if content_key != *content_account.key {
+        log_event!(instruction, "content account isn't the content PDA of this campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let input_data: UpdateCampaignRequest = parse_instruction(instruction, instruction_data)?;
+    let mut content = CampaignContent::deserialize(&mut &content_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let now = Clock::get()?.unix_timestamp;
+    if UPDATE_COOLDOWN_SECS != 0 && content.last_update_ts != 0 {
+        let elapsed = (now - content.last_update_ts).max(0);
+        if elapsed < UPDATE_COOLDOWN_SECS {
+            log_event!(instruction, "Campaign was updated recently, {} seconds left", UPDATE_COOLDOWN_SECS - elapsed);
+            return Err(FundError::UpdateCooldown.into());
+        }
+    }
+    content.description = check_description(instruction, &input_data.description)?;
+    content.last_update_ts = now;
+
+    log_phase!(instruction, "serialize");
+    content.serialize(&mut &mut content_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Campaigns created before the split keep their description in the v1 layout. This copies it into
+// a content PDA as is, so clients can read every description the same way. The v1 account itself
+// has no room to record the link, the PDA is found from the campaign key. Anyone can pay for it.
+fn migrate_campaign_content(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::MigrateCampaignContent;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let payer_account = next_account_info(accounts_iter)?;
+    let content_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !payer_account.is_signer {
+        log_event!(instruction, "Payer should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let v1 = CampaignDetailsV1::try_from_slice(&writing_account.data.borrow()).map_err(|_| {
+        log_event!(instruction, "Only v1 campaigns have a description to migrate");
+        ProgramError::InvalidAccountData
+    })?;
+
+    log_phase!(instruction, "cpi");
+    create_campaign_content(
+        instruction,
+        program_id,
+        writing_account,
+        payer_account,
+        content_account,
+        system_program,
+        v1.description,
+    )?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct PauseCampaignRequest {
+    pub paused: bool,
+    // Optional auto-resume time for a pause, 0 keeps the campaign paused until the admin resumes it.
+    pub pause_until: i64,
+}
+
+// The admin pauses donations to their campaign, or resumes them.
+// A pause with an end time can't outlive an admin who lost their keys.
+fn pause_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::PauseCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can pause the campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: PauseCampaignRequest = parse_instruction(instruction, instruction_data)?;
+
+    if input_data.paused {
+        if input_data.pause_until != 0 && input_data.pause_until <= Clock::get()?.unix_timestamp {
+            log_event!(instruction, "pause_until should be in the future");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        campaign_data.admin_paused = true;
+        campaign_data.pause_until = input_data.pause_until;
+    } else {
+        campaign_data.admin_paused = false;
+        campaign_data.pause_until = 0;
+    }
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct RequestWithdrawRequest {
+    pub amount: u64,
+}
+
+// The admin of a timelocked campaign queues a withdraw. Everything queued together can't be more
+// then what is withdrawable right now, and each request is checked again when it is executed.
+fn request_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::RequestWithdraw;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can request a withdraw");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.withdraw_delay_secs == 0 {
+        log_event!(instruction, "Campaign isn't timelocked, use withdraw");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.disputed {
+        log_event!(instruction, "Campaign is under dispute, withdraw is blocked");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_withdrawals_open(instruction, &campaign_data)?;
+
+    let input_data: RequestWithdrawRequest = parse_instruction(instruction, instruction_data)?;
+    if input_data.amount == 0 {
+        log_event!(instruction, "amount should be more then 0");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if campaign_data.withdraw_queue.len() >= MAX_QUEUED_WITHDRAWS {
+        log_event!(instruction, "A campaign can have at most {} queued withdrawals", MAX_QUEUED_WITHDRAWS);
+        return Err(FundError::WithdrawQueueFull.into());
+    }
+
+    let queued: u64 = campaign_data.withdraw_queue.iter().map(|r| r.amount).sum();
+    let required = queued.checked_add(input_data.amount).ok_or(ProgramError::InvalidInstructionData)?;
+    check_campaign_rent(instruction, writing_account)?;
+    let available = withdrawable_balance(writing_account, &campaign_data)?;
+    if required > available {
+        log_event!(instruction, "Queued withdrawals would be more then the {} lamports available", available);
+        return Err(insufficient_funds(required, available));
+    }
+
+    campaign_data.withdraw_queue.push(QueuedWithdraw {
+        amount: input_data.amount,
+        requested_at: Clock::get()?.unix_timestamp,
+    });
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Executes the oldest queued withdraw once its delay has passed. Accounts are the same as `withdraw`,
+// and the withdraw goes through the same checks.
+fn execute_withdraw(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ExecuteWithdraw;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let treasury_account = next_account_info(accounts_iter)?;
+    let insurance_pool_account = next_account_info(accounts_iter)?;
+    let recipient_account = next_account_info(accounts_iter).unwrap_or(admin_account);
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let config = load_config(instruction, program_id, config_account)?;
+    log_phase!(instruction, "validate");
+    if config.treasury != *treasury_account.key {
+        log_event!(instruction, "treasury account isn't the platform treasury");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let mut insurance_pool = load_insurance_pool(instruction, program_id, insurance_pool_account)?;
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if campaign_data.withdraw_queue.is_empty() {
+        log_event!(instruction, "No queued withdraw to execute");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let request = campaign_data.withdraw_queue[0].clone();
+    let matures_at = request.requested_at.saturating_add(campaign_data.withdraw_delay_secs as i64);
+    if Clock::get()?.unix_timestamp < matures_at {
+        log_event!(instruction, "Oldest queued withdraw can be executed at {}", matures_at);
+        return Err(FundError::WithdrawNotMatured.into());
+    }
+
+    // The request leaves the queue before the withdraw reloads the campaign.
+    campaign_data.withdraw_queue.remove(0);
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+
+    log_phase!(instruction, "cpi");
+    withdraw_from_campaign(
+        instruction,
+        program_id,
+        writing_account,
+        admin_account,
+        recipient_account,
+        treasury_account,
+        insurance_pool_account,
+        &config,
+        &mut insurance_pool,
+        Some(request.amount),
+        true,
+    )?;
+
+    log_phase!(instruction, "serialize");
+    insurance_pool.serialize(&mut &mut insurance_pool_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CancelWithdrawRequest {
+    // Position in the queue, 0 is the oldest request.
+    pub index: u8,
+}
+
+// The admin drops a queued withdraw, the ones after it move up.
+fn cancel_withdraw_request(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CancelWithdrawRequest;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can cancel a withdraw request");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let input_data: CancelWithdrawRequest = parse_instruction(instruction, instruction_data)?;
+    let index = input_data.index as usize;
+    if index >= campaign_data.withdraw_queue.len() {
+        log_event!(instruction, "No queued withdraw at index {}", index);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    campaign_data.withdraw_queue.remove(index);
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A campaign can be snapshotted at most once every this many slots, about a minute.
+const SNAPSHOT_INTERVAL_SLOTS: u64 = 150;
+
+// Hash of the campaign in `account_data` as it is at `slot`. The campaign is serialized again so the
+// unused room at the end of the account isn't part of it. The snapshot fields are left out,
+// they can't be part of the state they record, so a copy of the account taken after the snapshot
+// still hashes the same.
+pub fn campaign_snapshot_hash(account_data: &[u8], slot: u64) -> Result<[u8; 32], ProgramError> {
+    let mut campaign_data =
+        CampaignDetails::deserialize(&mut &account_data[..]).map_err(|_| ProgramError::InvalidAccountData)?;
+    campaign_data.snapshot_slot = 0;
+    campaign_data.snapshot_hash = [0; 32];
+    Ok(hashv(&[&campaign_data.try_to_vec()?, &slot.to_le_bytes()]).to_bytes())
+}
+
+// For clients holding a historical copy of a campaign account: true when it is the state
+// a snapshot recorded at `slot` with `snapshot_hash`.
+pub fn verify_snapshot(account_data: &[u8], slot: u64, snapshot_hash: &[u8; 32]) -> bool {
+    match campaign_snapshot_hash(account_data, slot) {
+        Ok(computed) => computed == *snapshot_hash,
+        Err(_) => false,
+    }
+}
+
+// Anyone can record the hash of a campaign at the current slot, so grant programs can later prove
+// what the campaign looked like without trusting an indexer. The pair is stored on the campaign
+// and logged as program data: "snapshot", the campaign key, the slot and the hash.
+fn snapshot_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SnapshotCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    let slot = Clock::get()?.slot;
+    if campaign_data.snapshot_slot != 0 && slot < campaign_data.snapshot_slot + SNAPSHOT_INTERVAL_SLOTS {
+        log_event!(
+            instruction,
+            "Campaign was snapshotted at slot {}, the next snapshot can be taken at slot {}",
+            campaign_data.snapshot_slot,
+            campaign_data.snapshot_slot + SNAPSHOT_INTERVAL_SLOTS
+        );
+        return Err(FundError::SnapshotTooSoon.into());
+    }
+
+    let snapshot_hash = campaign_snapshot_hash(&writing_account.data.borrow(), slot)?;
+    sol_log_data(&[b"snapshot", writing_account.key.as_ref(), &slot.to_le_bytes(), &snapshot_hash]);
+
+    campaign_data.snapshot_slot = slot;
+    campaign_data.snapshot_hash = snapshot_hash;
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// A housekeeping batch closes at most this many campaigns, so it stays well within the compute limit.
+const MAX_CLOSE_BATCH: usize = 10;
+// Accounts close_expired_campaigns takes per campaign.
+const CLOSE_GROUP_LEN: usize = 4;
+
+// True when nothing is left in the campaign but its rent and buffer, and it can't take donations anymore.
+fn is_empty_and_expired(writing_account: &AccountInfo, campaign_data: &CampaignDetails, now: i64) -> Result<bool, ProgramError> {
+    Ok(writing_account.lamports() <= campaign_min_balance(writing_account.data_len())?
+        && !campaign_data.disputed
+        && campaign_data.withdraw_queue.is_empty()
+        && campaign_data.end_timestamp != 0
+        && now >= campaign_data.end_timestamp)
+}
+
+// The platform authority closes empty campaigns that have ended. The config and the authority
+// come first, then a (campaign, creator, name PDA, content PDA) group per campaign; the rent of
+// the campaign and of its name and content PDAs goes back to the creator, and the name is free
+// again. Campaigns that don't qualify, or come with the wrong PDAs, are skipped and counted, the
+// batch doesn't fail because of them. Campaigns migrated from the first layout may not have their
+// PDAs yet, there is nothing to close then.
+fn close_expired_campaigns(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CloseExpiredCampaigns;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let groups = accounts_iter.as_slice();
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+
+    log_phase!(instruction, "validate");
+    if groups.is_empty()
+        || !groups.len().is_multiple_of(CLOSE_GROUP_LEN)
+        || groups.len() / CLOSE_GROUP_LEN > MAX_CLOSE_BATCH
+    {
+        log_event!(
+            instruction,
+            "Expected 1 to {} (campaign, creator, name, content) groups",
+            MAX_CLOSE_BATCH
+        );
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let mut skipped = 0;
+    log_phase!(instruction, "deserialize");
+    for group in groups.chunks(CLOSE_GROUP_LEN) {
+        let (writing_account, creator_account, name_account, content_account) =
+            (&group[0], &group[1], &group[2], &group[3]);
+        if writing_account.owner != program_id {
+            skipped += 1;
+            continue;
+        }
+        let campaign_data = match CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..]) {
+            Ok(campaign_data) => campaign_data,
+            Err(_) => {
+                skipped += 1;
+                continue;
+            }
+        };
+        let (name_key, _) = find_campaign_name_address(program_id, creator_account.key, &campaign_data.name_hash);
+        let (content_key, _) = find_campaign_content_address(program_id, writing_account.key);
+        if campaign_data.admin != *creator_account.key
+            || name_key != *name_account.key
+            || content_key != *content_account.key
+            || !is_empty_and_expired(writing_account, &campaign_data, now)?
+        {
+            skipped += 1;
+            continue;
+        }
+
+        // Zeroed and handed back to the system program, the address is an ordinary empty account
+        // again. Money sent to it in the same transaction can't make it a campaign, and creating a
+        // campaign there later goes through create_campaign and gets a new id. Ids are never reused.
+        for account in [writing_account, name_account, content_account] {
+            if account.owner != program_id {
+                continue;
+            }
+            let lamports = account.lamports();
+            **account.try_borrow_mut_lamports()? = 0;
+            **creator_account.try_borrow_mut_lamports()? += lamports;
+            account.data.borrow_mut().fill(0);
+            account.assign(&system_program::id());
+        }
+    }
+
+    log_event!(
+        instruction,
+        "Closed {} campaigns, skipped {}",
+        groups.len() / CLOSE_GROUP_LEN - skipped,
+        skipped
+    );
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetCpiCallersRequest {
+    pub cpi_callers: Vec<Pubkey>,
+}
+
+// The platform authority replaces the programs allowed to invoke the guarded instructions.
+fn set_cpi_callers(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetCpiCallers;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let mut config = load_config(instruction, program_id, config_account)?;
+
+    let input_data: SetCpiCallersRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    check_entries(instruction, input_data.cpi_callers.len(), MAX_CPI_CALLERS, "CPI callers")?;
+
+    config.cpi_callers = input_data.cpi_callers;
+    log_phase!(instruction, "serialize");
+    config.serialize(&mut &mut config_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// Every donation gets an immutable receipt at a PDA from the campaign and its sequence number,
+// so "donation #k to campaign X" always has the same address. Unlike the DonationRecord it is
+// never written again. The client reads `donation_sequence` to derive the address, when another
+// donation lands first the seeds don't match and the donation has to be sent again.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct DonationReceipt {
+    pub campaign: Pubkey,
+    pub sequence: u64,
+    pub donor: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+// Creates the receipt of the next donation to the campaign, paid by the donor, and moves the
+// campaign's sequence on. The caller saves the campaign.
+#[allow(clippy::too_many_arguments)]
+fn create_donation_receipt<'a>(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo<'a>,
+    campaign_data: &mut CampaignDetails,
+    donor: &AccountInfo<'a>,
+    receipt_account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let sequence = campaign_data.donation_sequence;
+    let sequence_bytes = sequence.to_le_bytes();
+    let (receipt_key, bump) = find_receipt_address(program_id, writing_account.key, sequence);
+    if receipt_key != *receipt_account.key {
+        log_event!(instruction, "receipt account isn't the receipt PDA of donation {} to this campaign", sequence);
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    create_pda_account(
+        instruction,
+        program_id,
+        donor,
+        receipt_account,
+        system_program,
+        serialized_len::<DonationReceipt>(),
+        &[RECEIPT_SEED, writing_account.key.as_ref(), &sequence_bytes, &[bump]],
+    )?;
+    DonationReceipt {
+        campaign: *writing_account.key,
+        sequence,
+        donor: *donor.key,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    }
+    .serialize(&mut &mut receipt_account.data.borrow_mut()[..])?;
+
+    campaign_data.donation_sequence += 1;
+    Ok(())
+}
+
+// Once the campaign is closed, or its account is gone, the donor can close their receipt
+// and get its rent back.
+fn close_receipt(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::CloseReceipt;
+    let accounts_iter = &mut accounts.iter();
+    let receipt_account = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if receipt_account.owner != program_id {
+        log_event!(instruction, "receipt account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !donor_account.is_signer {
+        log_event!(instruction, "donor should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let receipt = DonationReceipt::try_from_slice(&receipt_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if receipt.donor != *donor_account.key || receipt.campaign != *writing_account.key {
+        log_event!(instruction, "Receipt doesn't belong to this donor and campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    if writing_account.owner == program_id {
+        let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if campaign_data.state != CampaignState::Closed {
+            log_event!(instruction, "Receipts can only be closed once the campaign is closed");
+            return Err(ProgramError::InvalidAccountData);
+        }
+    }
+
+    let lamports = receipt_account.lamports();
+    log_phase!(instruction, "cpi");
+    **receipt_account.try_borrow_mut_lamports()? = 0;
+    **donor_account.try_borrow_mut_lamports()? += lamports;
+    receipt_account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+// An admin can refuse donations from a wallet (sanctioned addresses, harassers). The block is a PDA
+// from the campaign and the wallet that only exists while the wallet is blocked. Donating passes it
+// whether it exists or not, the program checks the address so a donor can't leave it out.
+// Donations made before the block keep their refund rights, refunds never look at it.
+// There is no donor allowlist: campaigns are open to every wallet that isn't blocked here.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct DonorBlock {
+    pub campaign: Pubkey,
+    pub wallet: Pubkey,
+}
+
+fn check_donor_not_blocked(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo,
+    donor: &AccountInfo,
+    blocklist_account: &AccountInfo,
+) -> ProgramResult {
+    let (blocklist_key, _) = find_blocklist_address(program_id, writing_account.key, donor.key);
+    if blocklist_key != *blocklist_account.key {
+        log_event!(instruction, "blocklist account isn't the blocklist PDA of this donor and campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if blocklist_account.owner == program_id && blocklist_account.lamports() > 0 {
+        log_event!(instruction, "{} is blocked from donating to this campaign", donor.key);
+        return Err(FundError::DonorBlocked.into());
+    }
+    Ok(())
+}
+
+// The admin blocks a wallet from donating to their campaign, paying for the blocklist PDA.
+fn block_donor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::BlockDonor;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let blocklist_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+    log_phase!(instruction, "deserialize");
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can block donors");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (blocklist_key, bump) = find_blocklist_address(program_id, writing_account.key, wallet_account.key);
+    if blocklist_key != *blocklist_account.key {
+        log_event!(instruction, "blocklist account isn't the blocklist PDA of this wallet and campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if blocklist_account.owner == program_id {
+        log_event!(instruction, "Wallet is already blocked");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    log_phase!(instruction, "cpi");
+    create_pda_account(
+        instruction,
+        program_id,
+        admin_account,
+        blocklist_account,
+        system_program,
+        serialized_len::<DonorBlock>(),
+        &[BLOCKLIST_SEED, writing_account.key.as_ref(), wallet_account.key.as_ref(), &[bump]],
+    )?;
+    log_phase!(instruction, "serialize");
+    DonorBlock {
+        campaign: *writing_account.key,
+        wallet: *wallet_account.key,
+    }
+    .serialize(&mut &mut blocklist_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+// The admin lifts a block, closing the blocklist PDA gives them its rent back.
+fn unblock_donor(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::UnblockDonor;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let admin_account = next_account_info(accounts_iter)?;
+    let wallet_account = next_account_info(accounts_iter)?;
+    let blocklist_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+    log_phase!(instruction, "deserialize");
+    let campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can unblock donors");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (blocklist_key, _) = find_blocklist_address(program_id, writing_account.key, wallet_account.key);
+    if blocklist_key != *blocklist_account.key {
+        log_event!(instruction, "blocklist account isn't the blocklist PDA of this wallet and campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if blocklist_account.owner != program_id {
+        log_event!(instruction, "Wallet isn't blocked");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let lamports = blocklist_account.lamports();
+    log_phase!(instruction, "cpi");
+    **blocklist_account.try_borrow_mut_lamports()? = 0;
+    **admin_account.try_borrow_mut_lamports()? += lamports;
+    blocklist_account.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct CorrectAccountingRequest {
+    pub new_amount_donated: u64,
+    pub reason_code: u16,
+}
+
+// The platform authority fixes an `amount_donated` that drifted from reality, e.g. rent that
+// older versions counted as a donation. Every correction is kept on the campaign and logged as
+// program data: "correction", the campaign key, the old and new amount and the reason code.
+// A failed or liquidated campaign is paying out refunds based on its totals, it can't be corrected.
+// The new amount can't go below the non-refundable donations and the pending ones, the refund
+// snapshot and `return_donation` take those out of it.
+fn correct_accounting(
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
-    // This is the data we want to process our instuction for.
-    // It is a list of 8 bitunsinged integers(0..255).
     instruction_data: &[u8],
 ) -> ProgramResult {
+    let instruction = Instruction::CorrectAccounting;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
 
-    // We check if we have a instruction_data len greater then 0, if it is not, we do not want to procced.
-    // So we return Error with InvalidInstructionData Message.
-    if instruction_data.len() == 0 {
-        return Err(ProgramError::InvalidInstructionData);
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
     }
+    check_authority(instruction, program_id, config_account, authority_account)?;
 
-    // As we know that solana program have only one entrypoint, but we want three entry point for your program.
-    // In order to fix this, we are going to take advantage of the fact that there is no limit to the instruction_data array 😜
-    // we use the first element of the instruction_data array to know what entry point we want to call.
-    // Now we just check and call the funciton for each of them.
-    // 0 for create_campaign,
-    // 1 for withdraw
-    // 2 for donate.
-    if instruction_data[0] == 0 {
-        return create_campaign(
-            program_id,
-            accounts,
-            // Notice we pass program_id and accounts as they were,
-            // but we pass a reference to silce of [instruction_data].
-            // we do not want the first element in any of our function
-            &instruction_data[1..instruction_data.len()],
-        );
-    } else if instruction_data[0] == 1 {
-        return withdraw(
-            program_id,
-            accounts,
-            &instruction_data[1..instruction_data.len()],
-        );
-    } else if instruction_data[0] ==2 {
-        return donate(
-            program_id,
-            accounts,
-            &instruction_data[1..instruction_data.len()],
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    if campaign_data.state == CampaignState::Failed || campaign_data.state == CampaignState::Liquidated {
+        log_event!(instruction, "Campaign is paying out refunds, its totals can't be corrected");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    check_entries(instruction, campaign_data.corrections.len() + 1, MAX_CORRECTIONS, "corrections of a campaign")?;
+
+    let input_data: CorrectAccountingRequest = parse_instruction(instruction, instruction_data)?;
+    let floor = campaign_data
+        .non_refundable_total
+        .checked_add(campaign_data.pending_amount())
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if input_data.new_amount_donated < floor {
+        log_event!(
+            instruction,
+            "amount_donated can't go below {} lamports of non-refundable and pending donations",
+            floor
         );
+        return Err(ProgramError::InvalidInstructionData);
     }
 
-    // If instruction data doesn't match we give an error.
-    msg!("Didn't find the required entrypoint ");
-    Err(ProgramError::InvalidInstructionData)
+    let old_amount_donated = campaign_data.amount_donated;
+    campaign_data.amount_donated = input_data.new_amount_donated;
+    campaign_data.corrections.push(AccountingCorrection {
+        old_amount_donated,
+        new_amount_donated: input_data.new_amount_donated,
+        reason_code: input_data.reason_code,
+        corrected_at: Clock::get()?.unix_timestamp,
+    });
+    sol_log_data(&[
+        b"correction",
+        writing_account.key.as_ref(),
+        &old_amount_donated.to_le_bytes(),
+        &input_data.new_amount_donated.to_le_bytes(),
+        &input_data.reason_code.to_le_bytes(),
+    ]);
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
 }
 
-// Then we call the entry point macro to add `process_instruction` as our entrypoint to our program
-entrypoint!(process_instruction);
+// Moves the pending donations that waited `confirmation_slots` at `current_slot` into
+// `amount_donated`, or all of them with `all`. Returns how much was moved.
+fn confirm_pending_donations(campaign_data: &mut CampaignDetails, current_slot: u64, all: bool) -> u64 {
+    let confirmation_slots = campaign_data.confirmation_slots;
+    let mut confirmed = 0;
+    campaign_data.pending_donations.retain(|pending| {
+        let matured = all || current_slot >= pending.slot.saturating_add(confirmation_slots);
+        if matured {
+            confirmed += pending.amount;
+        }
+        !matured
+    });
+    campaign_data.amount_donated += confirmed;
+    confirmed
+}
+
+// Anyone can fold the donations that waited long enough into `amount_donated`.
+// Donating does it too, this is for when no donation comes in.
+fn confirm_donations(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ConfirmDonations;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+
+    log_phase!(instruction, "validate");
+    let confirmed = confirm_pending_donations(&mut campaign_data, Clock::get()?.slot, false);
+    log_event!(
+        instruction,
+        "Confirmed {} lamports, {} donations still pending",
+        confirmed,
+        campaign_data.pending_donations.len()
+    );
+
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    Ok(())
+}
 
-// Here I have created the function for every action we want to do in our program.
-// They take same parameter as in process_instruction and same return type
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct CampaignDetails {
-    pub admin: Pubkey,
-    pub name: String,
-    pub description: String,
+struct SetImageRequest {
     pub image_link: String,
-    pub amount_donated: u64,
 }
-fn create_campaign(
+
+// The admin changes the image of the campaign, nothing else is touched.
+fn set_image(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
-
-    // We create a iterator an accounts
-    // account parameter is the array of accounts related to this entrypoint
+    let instruction = Instruction::SetImage;
     let accounts_iter = &mut accounts.iter();
-
-    // writing accounts or we can call it program account
-    // This is an account we will create in our front-end.
-    // This account should be owned by the solana program
     let writing_account = next_account_info(accounts_iter)?;
-    
-    // Accounts of the person creating the campaign, signer
-    let creator_account = next_account_info(accounts_iter)?;
-
-    // Now to allow transcation we want the creator account to sign the transcation.
-    if !creator_account.is_signer {
-        msg!("creator_account should be signer");
-        return Err(ProgramError::IncorrectProgramId);
-    }
+    let admin_account = next_account_info(accounts_iter)?;
 
-    // We want to write in this account so we want it is owned by the program.
     if writing_account.owner != program_id {
-        msg!("writing_accounts isn't owned by program");
+        log_event!(instruction, "writing account isn't owned by program");
         return Err(ProgramError::IncorrectProgramId);
     }
-
-    // By deriving the trait BorshDeserializer in our CampaignDetails struct we have added a method `try_from_slice` which take in the parameter array of u8 and create
-    // an object of CampaignDetails with it.
-    let mut input_data = CampaignDetails::try_from_slice(&instruction_data)
-        .expect("Instruction data serialization didn't work");
-
-    // Validating that only admin can create campaign
-    if input_data.admin != *creator_account.key {
-        msg!("Invalid instruction data");
-        return Err(ProgramError::InvalidInstructionData);
+    if !admin_account.is_signer {
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
     }
+    check_distinct_signer(instruction, writing_account, admin_account)?;
 
-    // let try to make our program rent exempet
-    let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
-    if **writing_account.lamports.borrow() < rent_exemption {
-        msg!("The balance of writing_account should be more then rent_exemption");
-        return Err(ProgramError::InsufficientFunds);
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *admin_account.key {
+        log_event!(instruction, "Only the account admin can change the image");
+        return Err(ProgramError::InvalidAccountData);
     }
+    check_metadata_editable(instruction, &campaign_data)?;
 
-    // Then we can set the initial amount donated to be zero.
-    input_data.amount_donated = 0;
+    let input_data: SetImageRequest = parse_instruction(instruction, instruction_data)?;
+    campaign_data.image_link = clean_text(instruction, &input_data.image_link)?;
 
-    // writing into CampaignDetails
-    input_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    // Like a longer name, a longer link still has to leave the room the lists can grow into.
+    let required_len = checked_account_len(
+        instruction,
+        campaign_data.try_to_vec()?.len(),
+        campaign_growth_room(&campaign_data),
+    )?;
+    if writing_account.data_len() < required_len {
+        log_event!(instruction, "New image link doesn't fit in the campaign account");
+        return Err(ProgramError::AccountDataTooSmall);
+    }
 
+    log_phase!(instruction, "serialize");
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
     Ok(())
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug)]
-struct WithdrawRequest {
-    pub amount: u64,
+struct ReturnDonationRequest {
+    pub donor: Pubkey,
 }
-fn withdraw(
+
+// The admin of an active campaign gives a donor back everything they donated in the current cycle,
+// pending donations included, for a conflict of interest or because the donor asked. The record
+// is emptied, so the donor can donate again later like a new donor. What a sponsor matched stays
+// in the campaign. It fails if the campaign already released a milestone the donation counted towards.
+fn return_donation(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
+    let instruction = Instruction::ReturnDonation;
     let accounts_iter = &mut accounts.iter();
     let writing_account = next_account_info(accounts_iter)?;
     let admin_account = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    let donor_account = next_account_info(accounts_iter)?;
+    // The returned donation comes off the category's raised amount.
+    let global_stats_account = next_account_info(accounts_iter)?;
 
-    // we check if writing program is owned by program
     if writing_account.owner != program_id {
-        msg!("writing account isn't owned by program");
+        log_event!(instruction, "writing account isn't owned by program");
         return Err(ProgramError::IncorrectProgramId);
     }
-    // Admin accounts should be the signer in this transaction
     if !admin_account.is_signer {
-        msg!("Admin should be signer");
-        return Err(ProgramError::IncorrectProgramId)
+        log_event!(instruction, "Admin should be signer");
+        return Err(ProgramError::IncorrectProgramId);
     }
-    let campaign_data = CampaignDetails::try_from_slice(*writing_account.data.borrow())
-        .expect("Error deserializing data");
+    check_distinct_signer(instruction, writing_account, admin_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let input_data: ReturnDonationRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.donor != *donor_account.key {
+        log_event!(instruction, "donor account isn't the donor to return the donation to");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donor_account.key);
+    if record_key != *donation_record_account.key || donation_record_account.owner != program_id {
+        log_event!(instruction, "donation record account isn't the donation record PDA of this donor");
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut donation_record = DonationRecord::try_from_slice(&donation_record_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    // Then we check if the admin_account's public key is equal to 
-    // the public key we have stored in our campaing_data.
     if campaign_data.admin != *admin_account.key {
-        msg!("Only the account admin can withdraw");
+        log_event!(instruction, "Only the account admin can return a donation");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if campaign_data.state != CampaignState::Active {
+        log_event!(instruction, "Only an active campaign can return a donation, donors of a failed one claim refunds");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if donation_record.cycle != campaign_data.cycle || donation_record.amount == 0 {
+        log_event!(instruction, "Donor has nothing donated in the current cycle");
         return Err(ProgramError::InvalidAccountData);
     }
 
-    let input_data = WithdrawRequest::try_from_slice(&instruction_data)
-        .expect("Instruction data serialization didn't worked");
+    let amount = donation_record.amount;
+    let pending: u64 = campaign_data
+        .pending_donations
+        .iter()
+        .filter(|pending| pending.donor == *donor_account.key)
+        .map(|pending| pending.amount)
+        .sum();
+    let confirmed = amount.checked_sub(pending).ok_or(ProgramError::InvalidAccountData)?;
+    let amount_donated = campaign_data
+        .amount_donated
+        .checked_sub(confirmed)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if campaign_data
+        .milestones
+        .iter()
+        .any(|milestone| milestone.released && milestone.amount > amount_donated)
+    {
+        log_event!(
+            instruction,
+            "Returning {} lamports would take the campaign below a milestone it already released",
+            amount
+        );
+        return Err(FundError::MilestoneReleased.into());
+    }
 
-    let rent_exemption = Rent::get()?.minimum_balance(writing_account.data_len());
+    // The other donors' pending donations, the reserved fees and the rent stay in the campaign.
+    let available = writing_account
+        .lamports()
+        .saturating_sub(campaign_min_balance(writing_account.data_len())?)
+        .saturating_sub(campaign_data.fees_reserved)
+        .saturating_sub(campaign_data.pending_amount().saturating_sub(pending));
+    if available < amount {
+        log_event!(instruction, "Insufficent balance");
+        return Err(insufficient_funds(amount, available));
+    }
 
-    // we check if we have enough funds
-    if **writing_account.lamports.borrow() - rent_exemption < input_data.amount {
-        msg!("Insufficent balance");
-        return Err(ProgramError::InsufficientFunds);
+    campaign_data.pending_donations.retain(|pending| pending.donor != *donor_account.key);
+    campaign_data.amount_donated = amount_donated;
+    campaign_data.donor_count = campaign_data
+        .donor_count
+        .checked_sub(1)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if donation_record.non_refundable {
+        campaign_data.non_refundable_total = campaign_data
+            .non_refundable_total
+            .checked_sub(amount)
+            .ok_or(ProgramError::InvalidAccountData)?;
     }
+    campaign_data.returned_amount = campaign_data
+        .returned_amount
+        .checked_add(amount)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    donation_record.amount = 0;
+    donation_record.non_refundable = false;
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    uncount_raised(&mut global_stats, campaign_data.category, amount);
 
-    // Transfer balance
-    // we will decrease the balance of the program account, and increase the admin_account balance.
-    **writing_account.try_borrow_mut_lamports()? -= input_data.amount; //  we can only decrease the balance of a program-owned account.
-    **admin_account.try_borrow_mut_lamports()? += input_data.amount;
+    log_phase!(instruction, "cpi");
+    **writing_account.try_borrow_mut_lamports()? -= amount;
+    **donor_account.try_borrow_mut_lamports()? += amount;
+
+    sol_log_data(&[
+        b"donation_returned",
+        writing_account.key.as_ref(),
+        donor_account.key.as_ref(),
+        &amount.to_le_bytes(),
+    ]);
+
+    log_phase!(instruction, "serialize");
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
     Ok(())
 }
 
-fn donate(
+// A donor takes back their donations that are still pending long after they were made, because
+// nobody confirmed them. They come out of the donor's record as if they were never made.
+// Their receipts stay, and what a sponsor matched them with stays in the campaign.
+fn reclaim_pending(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
     _instruction_data: &[u8],
 ) -> ProgramResult {
+    let instruction = Instruction::ReclaimPending;
     let accounts_iter = &mut accounts.iter();
     let writing_account = next_account_info(accounts_iter)?;
-    let donator_program_account = next_account_info(accounts_iter)?;
     let donator = next_account_info(accounts_iter)?;
+    let donation_record_account = next_account_info(accounts_iter)?;
+    // The reclaimed donations come off the category's raised amount.
+    let global_stats_account = next_account_info(accounts_iter)?;
 
     if writing_account.owner != program_id {
-        msg!("writing_account isn't owned by program");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-    if donator_program_account.owner != program_id {
-        msg!("donator_program_account isn't owned by program");
+        log_event!(instruction, "writing account isn't owned by program");
         return Err(ProgramError::IncorrectProgramId);
     }
     if !donator.is_signer {
-        msg!("donator should be signer");
+        log_event!(instruction, "donator should be signer");
         return Err(ProgramError::IncorrectProgramId);
     }
+    check_distinct_signer(instruction, writing_account, donator)?;
 
-    let mut campaign_data = CampaignDetails::try_from_slice(*writing_account.data.borrow())
-        .expect("Error deserializing data");
+    let (record_key, _) = find_donation_record_address(program_id, writing_account.key, donator.key);
+    if record_key != *donation_record_account.key || donation_record_account.owner != program_id {
+        log_event!(instruction, "donation record account isn't the donation record PDA of this donator");
+        return Err(ProgramError::InvalidSeeds);
+    }
 
-    campaign_data.amount_donated += **donator_program_account.lamports.borrow();
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    let mut donation_record = DonationRecord::try_from_slice(&donation_record_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
 
-    **writing_account.try_borrow_mut_lamports()? += **donator_program_account.lamports.borrow();
-    **donator_program_account.try_borrow_mut_lamports()? = 0;
+    log_phase!(instruction, "validate");
+    if donation_record.cycle != campaign_data.cycle {
+        log_event!(instruction, "donation record is from another cycle of the campaign");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let slot = Clock::get()?.slot;
+    let mut reclaimed = 0;
+    campaign_data.pending_donations.retain(|pending| {
+        let stale = pending.donor == *donator.key && slot >= pending.slot.saturating_add(PENDING_RECLAIM_SLOTS);
+        if stale {
+            reclaimed += pending.amount;
+        }
+        !stale
+    });
+    if reclaimed == 0 {
+        log_event!(instruction, "No pending donation of this donator is old enough to reclaim");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    donation_record.amount = donation_record
+        .amount
+        .checked_sub(reclaimed)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    if donation_record.amount == 0 {
+        campaign_data.donor_count = campaign_data
+            .donor_count
+            .checked_sub(1)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    if donation_record.non_refundable {
+        campaign_data.non_refundable_total = campaign_data
+            .non_refundable_total
+            .checked_sub(reclaimed)
+            .ok_or(ProgramError::InvalidAccountData)?;
+    }
+    let mut global_stats = load_global_stats(instruction, program_id, global_stats_account)?;
+    uncount_raised(&mut global_stats, campaign_data.category, reclaimed);
+
+    log_phase!(instruction, "cpi");
+    **writing_account.try_borrow_mut_lamports()? -= reclaimed;
+    **donator.try_borrow_mut_lamports()? += reclaimed;
+    log_event!(instruction, "Reclaimed {} pending lamports", reclaimed);
+
+    log_phase!(instruction, "serialize");
+    donation_record.serialize(&mut &mut donation_record_account.data.borrow_mut()[..])?;
+    campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
+    global_stats.serialize(&mut &mut global_stats_account.data.borrow_mut()[..])?;
+    Ok(())
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ReallocRequest {
+    pub new_size: u32,
+}
+
+// Grows a program account to `new_size`, the payer funds the rent of the extra bytes and the new
+// bytes start zeroed. Accounts never shrink, the fields in them may already be in use.
+fn grow_account<'a>(
+    instruction: Instruction,
+    account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_size: usize,
+) -> ProgramResult {
+    if new_size < account.data_len() {
+        log_event!(instruction, "Account is {} bytes, it can't shrink to {}", account.data_len(), new_size);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if new_size - account.data_len() > MAX_PERMITTED_DATA_INCREASE {
+        log_event!(instruction, "An account can grow by at most {} bytes at once", MAX_PERMITTED_DATA_INCREASE);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    account_space(instruction, new_size)?;
+
+    let missing = Rent::get()?.minimum_balance(new_size).saturating_sub(account.lamports());
+    if missing > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, missing),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+    account.realloc(new_size, true)?;
+    log_event!(instruction, "Account grown to {} bytes", new_size);
+    Ok(())
+}
+
+// The platform authority makes room in the config for fields added after it was created.
+fn realloc_config(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ReallocConfig;
+    let accounts_iter = &mut accounts.iter();
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    let input_data: ReallocRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "cpi");
+    grow_account(instruction, config_account, authority_account, system_program, input_data.new_size as usize)
+}
+
+// Same for the global stats.
+fn realloc_global_stats(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ReallocGlobalStats;
+    let accounts_iter = &mut accounts.iter();
+    let global_stats_account = next_account_info(accounts_iter)?;
+    let config_account = next_account_info(accounts_iter)?;
+    let authority_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    check_authority(instruction, program_id, config_account, authority_account)?;
+    log_phase!(instruction, "deserialize");
+    load_global_stats(instruction, program_id, global_stats_account)?;
+    let input_data: ReallocRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "cpi");
+    grow_account(instruction, global_stats_account, authority_account, system_program, input_data.new_size as usize)
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct ArchiveCampaignRequest {
+    pub archived: bool,
+}
+
+// The campaign admin, or the platform authority passing the config, hides a campaign from default
+// listings or brings it back. An archived campaign takes no donations, everything else works as
+// its state allows, so a failed campaign's donors can still claim their refunds.
+fn archive_campaign(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::ArchiveCampaign;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let signer_account = next_account_info(accounts_iter)?;
+    let config_account = accounts_iter.next();
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !signer_account.is_signer {
+        log_event!(instruction, "Admin or authority should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, signer_account)?;
+
+    log_phase!(instruction, "deserialize");
+    let mut campaign_data = CampaignDetails::deserialize(&mut &writing_account.data.borrow()[..])
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    log_phase!(instruction, "validate");
+    if campaign_data.admin != *signer_account.key {
+        match config_account {
+            Some(config_account) => check_authority(instruction, program_id, config_account, signer_account)?,
+            None => {
+                log_event!(instruction, "Only the account admin or the platform authority can archive the campaign");
+                return Err(ProgramError::InvalidAccountData);
+            }
+        }
+    }
+
+    let input_data: ArchiveCampaignRequest = parse_instruction(instruction, instruction_data)?;
+    campaign_data.archived = input_data.archived;
 
+    log_phase!(instruction, "serialize");
     campaign_data.serialize(&mut &mut writing_account.data.borrow_mut()[..])?;
     Ok(())
 }
+
+// A donor can have their refunds from a campaign paid to another wallet, stored in a PDA from
+// `REFUND_RECIPIENT_SEED`, the campaign and the donor. Only the donor's signature changes it, never the
+// recipient's. Proof based refunds pay the donor of the merkle leaf as before.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Default)]
+pub struct RefundRecipient {
+    pub campaign: Pubkey,
+    pub donor: Pubkey,
+    pub recipient: Pubkey,
+}
+
+// The refund recipient the donor set for this campaign, None when they never set one.
+// Fails unless `refund_recipient_account` is the donor's PDA.
+fn load_refund_recipient(
+    instruction: Instruction,
+    program_id: &Pubkey,
+    writing_account: &AccountInfo,
+    donor: &AccountInfo,
+    refund_recipient_account: &AccountInfo,
+) -> Result<Option<Pubkey>, ProgramError> {
+    let (refund_recipient_key, _) = find_refund_recipient_address(program_id, writing_account.key, donor.key);
+    if refund_recipient_key != *refund_recipient_account.key {
+        log_event!(instruction, "refund recipient account isn't the refund recipient PDA of this donor and campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    if refund_recipient_account.owner != program_id {
+        return Ok(None);
+    }
+    let refund_recipient = RefundRecipient::try_from_slice(*refund_recipient_account.data.borrow())
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    Ok(Some(refund_recipient.recipient))
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+struct SetRefundRecipientRequest {
+    pub recipient: Pubkey,
+}
+
+// The donor picks where their refunds from this campaign go, paying for the PDA the first time.
+// Setting their own wallet again undoes the redirect.
+fn set_refund_recipient(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    let instruction = Instruction::SetRefundRecipient;
+    let accounts_iter = &mut accounts.iter();
+    let writing_account = next_account_info(accounts_iter)?;
+    let donator = next_account_info(accounts_iter)?;
+    let refund_recipient_account = next_account_info(accounts_iter)?;
+    let system_program = next_account_info(accounts_iter)?;
+
+    if writing_account.owner != program_id {
+        log_event!(instruction, "writing account isn't owned by program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    if !donator.is_signer {
+        log_event!(instruction, "donator should be signer");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    check_distinct_signer(instruction, writing_account, donator)?;
+
+    log_phase!(instruction, "deserialize");
+    let input_data: SetRefundRecipientRequest = parse_instruction(instruction, instruction_data)?;
+    log_phase!(instruction, "validate");
+    if input_data.recipient == Pubkey::default() {
+        log_event!(instruction, "Refund recipient can't be the zero address");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (refund_recipient_key, bump) = find_refund_recipient_address(program_id, writing_account.key, donator.key);
+    if refund_recipient_key != *refund_recipient_account.key {
+        log_event!(instruction, "refund recipient account isn't the refund recipient PDA of this donor and campaign");
+        return Err(ProgramError::InvalidSeeds);
+    }
+    log_phase!(instruction, "cpi");
+    if refund_recipient_account.owner != program_id {
+        create_pda_account(
+            instruction,
+            program_id,
+            donator,
+            refund_recipient_account,
+            system_program,
+            serialized_len::<RefundRecipient>(),
+            &[REFUND_RECIPIENT_SEED, writing_account.key.as_ref(), donator.key.as_ref(), &[bump]],
+        )?;
+    }
+
+    log_phase!(instruction, "serialize");
+    RefundRecipient {
+        campaign: *writing_account.key,
+        donor: *donator.key,
+        recipient: input_data.recipient,
+    }
+    .serialize(&mut &mut refund_recipient_account.data.borrow_mut()[..])?;
+    Ok(())
+}
@@ -0,0 +1,304 @@
+// Every line an instruction logs starts with `0xfund:<instruction>:`, so alerts and log scrapers
+// can tell which instruction it came from without parsing prose. The last line says how it ended:
+//
+//   0xfund:<instruction>:ok
+//   0xfund:<instruction>:error:<code>
+//
+// `<instruction>` is the handler's name from `INSTRUCTION_NAMES`, `<code>` the error as a u64 like
+// in the error return data, a FundError's position for our own errors. Tags that aren't an
+// instruction log as `unknown`, an empty instruction as `empty`. Alerts match these two lines.
+//
+// Everything else is `0xfund:<instruction>:<what happened>`, for people reading a transaction. The
+// text after the prefix can change any time, the values worth matching go in the `sol_log_data`
+// events. Handlers log through `log_event!` and pass their `Instruction` down to the helpers that
+// log for them. Changing the prefix or the two outcome lines breaks the alerts, add new lines next
+// to them instead.
+use solana_program::program_error::ProgramError;
+use std::fmt;
+
+pub const LOG_PREFIX: &str = "0xfund";
+
+// Handler names by their instruction tag, see the list in `dispatch`.
+pub const INSTRUCTION_NAMES: [&str; 72] = [
+    "create_campaign",
+    "withdraw",
+    "donate",
+    "initialize_config",
+    "open_dispute",
+    "resolve_dispute",
+    "finalize_campaign",
+    "claim_refund",
+    "renew_campaign",
+    "create_voucher",
+    "redeem_voucher",
+    "reclaim_voucher",
+    "liquidate_campaign",
+    "pay_insurance_claim",
+    "register_sponsor",
+    "update_sponsor_name",
+    "flag_sponsor",
+    "commit_match",
+    "release_match",
+    "post_update",
+    "set_fee_exempt",
+    "add_charity",
+    "remove_charity",
+    "collect_fees",
+    "version",
+    "health_check",
+    "get_withdrawable",
+    "sweep_unclaimed_refunds",
+    "redirect_funds",
+    "set_refund_root",
+    "claim_refund_with_proof",
+    "set_soft_goal",
+    "donate_split",
+    "withdraw_many",
+    "rename_campaign",
+    "donate_direct",
+    "set_legacy_donate_disabled",
+    "update_campaign",
+    "migrate_campaign_content",
+    "pause_campaign",
+    "request_withdraw",
+    "execute_withdraw",
+    "cancel_withdraw_request",
+    "snapshot_campaign",
+    "close_expired_campaigns",
+    "set_cpi_callers",
+    "close_receipt",
+    "block_donor",
+    "unblock_donor",
+    "correct_accounting",
+    "get_campaign_summary",
+    "confirm_donations",
+    "set_image",
+    "reclaim_pending",
+    "realloc_config",
+    "realloc_global_stats",
+    "get_donor_standing",
+    "set_sunset",
+    "archive_campaign",
+    "set_rounding",
+    "set_tip_loyalty",
+    "get_progress",
+    "set_refund_recipient",
+    "set_goal",
+    "reclaim_staged",
+    "validate_create",
+    "feature_campaign",
+    "set_feature_fee",
+    "set_category",
+    "return_donation",
+    "set_min_goal",
+    "set_donation_limits",
+];
+
+// The instructions by tag, in the order of `INSTRUCTION_NAMES`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    CreateCampaign,
+    Withdraw,
+    Donate,
+    InitializeConfig,
+    OpenDispute,
+    ResolveDispute,
+    FinalizeCampaign,
+    ClaimRefund,
+    RenewCampaign,
+    CreateVoucher,
+    RedeemVoucher,
+    ReclaimVoucher,
+    LiquidateCampaign,
+    PayInsuranceClaim,
+    RegisterSponsor,
+    UpdateSponsorName,
+    FlagSponsor,
+    CommitMatch,
+    ReleaseMatch,
+    PostUpdate,
+    SetFeeExempt,
+    AddCharity,
+    RemoveCharity,
+    CollectFees,
+    Version,
+    HealthCheck,
+    GetWithdrawable,
+    SweepUnclaimedRefunds,
+    RedirectFunds,
+    SetRefundRoot,
+    ClaimRefundWithProof,
+    SetSoftGoal,
+    DonateSplit,
+    WithdrawMany,
+    RenameCampaign,
+    DonateDirect,
+    SetLegacyDonateDisabled,
+    UpdateCampaign,
+    MigrateCampaignContent,
+    PauseCampaign,
+    RequestWithdraw,
+    ExecuteWithdraw,
+    CancelWithdrawRequest,
+    SnapshotCampaign,
+    CloseExpiredCampaigns,
+    SetCpiCallers,
+    CloseReceipt,
+    BlockDonor,
+    UnblockDonor,
+    CorrectAccounting,
+    GetCampaignSummary,
+    ConfirmDonations,
+    SetImage,
+    ReclaimPending,
+    ReallocConfig,
+    ReallocGlobalStats,
+    GetDonorStanding,
+    SetSunset,
+    ArchiveCampaign,
+    SetRounding,
+    SetTipLoyalty,
+    GetProgress,
+    SetRefundRecipient,
+    SetGoal,
+    ReclaimStaged,
+    ValidateCreate,
+    FeatureCampaign,
+    SetFeatureFee,
+    SetCategory,
+    ReturnDonation,
+    SetMinGoal,
+    SetDonationLimits,
+}
+
+pub const INSTRUCTIONS: [Instruction; 72] = [
+    Instruction::CreateCampaign,
+    Instruction::Withdraw,
+    Instruction::Donate,
+    Instruction::InitializeConfig,
+    Instruction::OpenDispute,
+    Instruction::ResolveDispute,
+    Instruction::FinalizeCampaign,
+    Instruction::ClaimRefund,
+    Instruction::RenewCampaign,
+    Instruction::CreateVoucher,
+    Instruction::RedeemVoucher,
+    Instruction::ReclaimVoucher,
+    Instruction::LiquidateCampaign,
+    Instruction::PayInsuranceClaim,
+    Instruction::RegisterSponsor,
+    Instruction::UpdateSponsorName,
+    Instruction::FlagSponsor,
+    Instruction::CommitMatch,
+    Instruction::ReleaseMatch,
+    Instruction::PostUpdate,
+    Instruction::SetFeeExempt,
+    Instruction::AddCharity,
+    Instruction::RemoveCharity,
+    Instruction::CollectFees,
+    Instruction::Version,
+    Instruction::HealthCheck,
+    Instruction::GetWithdrawable,
+    Instruction::SweepUnclaimedRefunds,
+    Instruction::RedirectFunds,
+    Instruction::SetRefundRoot,
+    Instruction::ClaimRefundWithProof,
+    Instruction::SetSoftGoal,
+    Instruction::DonateSplit,
+    Instruction::WithdrawMany,
+    Instruction::RenameCampaign,
+    Instruction::DonateDirect,
+    Instruction::SetLegacyDonateDisabled,
+    Instruction::UpdateCampaign,
+    Instruction::MigrateCampaignContent,
+    Instruction::PauseCampaign,
+    Instruction::RequestWithdraw,
+    Instruction::ExecuteWithdraw,
+    Instruction::CancelWithdrawRequest,
+    Instruction::SnapshotCampaign,
+    Instruction::CloseExpiredCampaigns,
+    Instruction::SetCpiCallers,
+    Instruction::CloseReceipt,
+    Instruction::BlockDonor,
+    Instruction::UnblockDonor,
+    Instruction::CorrectAccounting,
+    Instruction::GetCampaignSummary,
+    Instruction::ConfirmDonations,
+    Instruction::SetImage,
+    Instruction::ReclaimPending,
+    Instruction::ReallocConfig,
+    Instruction::ReallocGlobalStats,
+    Instruction::GetDonorStanding,
+    Instruction::SetSunset,
+    Instruction::ArchiveCampaign,
+    Instruction::SetRounding,
+    Instruction::SetTipLoyalty,
+    Instruction::GetProgress,
+    Instruction::SetRefundRecipient,
+    Instruction::SetGoal,
+    Instruction::ReclaimStaged,
+    Instruction::ValidateCreate,
+    Instruction::FeatureCampaign,
+    Instruction::SetFeatureFee,
+    Instruction::SetCategory,
+    Instruction::ReturnDonation,
+    Instruction::SetMinGoal,
+    Instruction::SetDonationLimits,
+];
+
+impl Instruction {
+    pub fn from_tag(tag: u8) -> Option<Instruction> {
+        INSTRUCTIONS.get(tag as usize).copied()
+    }
+
+    pub fn tag(self) -> u8 {
+        self as u8
+    }
+
+    pub fn name(self) -> &'static str {
+        INSTRUCTION_NAMES[self as usize]
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+pub fn error_code(err: &ProgramError) -> u64 {
+    u64::from(err.clone())
+}
+
+// `0xfund:<instruction>:<event>`, the event formatted like `msg!`.
+macro_rules! log_event {
+    ($instruction:expr, $($arg:tt)+) => {
+        msg!("{}:{}:{}", crate::logging::LOG_PREFIX, $instruction, format_args!($($arg)+))
+    };
+}
+
+// `0xfund:<instruction>:error:<code>`
+macro_rules! log_err {
+    ($instruction:expr, $err:expr) => {
+        msg!(
+            "{}:{}:error:{}",
+            crate::logging::LOG_PREFIX,
+            $instruction,
+            crate::logging::error_code(&solana_program::program_error::ProgramError::from($err))
+        )
+    };
+}
+
+// With the `debug-logs` feature every handler logs `0xfund:<instruction>:phase:<phase>` for each
+// phase it gets to (deserialize, validate, cpi, serialize) followed by the compute units left, so
+// a transaction running out of compute shows which step used it up. Without the feature it
+// expands to nothing.
+macro_rules! log_phase {
+    ($instruction:expr, $phase:expr) => {
+        #[cfg(feature = "debug-logs")]
+        {
+            log_event!($instruction, "phase:{}", $phase);
+            solana_program::log::sol_log_compute_units();
+        }
+    };
+}
@@ -0,0 +1,93 @@
+// Seed prefixes of every PDA the program creates, and the addresses they derive.
+//
+// The handlers and off-chain clients both derive these addresses, so they live here once
+// instead of being typed again wherever an address is needed. Each `find_*_address` returns
+// the address with its bump, the handlers sign with the same seeds followed by the bump.
+use solana_program::pubkey::Pubkey;
+
+// Single accounts of the platform.
+pub const CONFIG_SEED: &[u8] = b"config";
+pub const INSURANCE_POOL_SEED: &[u8] = b"insurance";
+pub const GLOBAL_STATS_SEED: &[u8] = b"stats";
+
+// Per campaign.
+pub const CAMPAIGN_CONTENT_SEED: &[u8] = b"content";
+pub const UPDATE_FEED_SEED: &[u8] = b"updates";
+// Followed by the admin and the name hash.
+pub const CAMPAIGN_NAME_SEED: &[u8] = b"campaign_name";
+// Followed by the campaign and the receipt's sequence number, little endian.
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+// Per campaign and wallet, the campaign comes first.
+pub const DONATION_SEED: &[u8] = b"donation";
+pub const MATCH_SEED: &[u8] = b"match";
+pub const REFUND_CLAIM_SEED: &[u8] = b"refund_claim";
+pub const BLOCKLIST_SEED: &[u8] = b"blocked";
+pub const REFUND_RECIPIENT_SEED: &[u8] = b"refund_to";
+
+// Per wallet.
+pub const SPONSOR_SEED: &[u8] = b"sponsor";
+pub const CHARITY_SEED: &[u8] = b"charity";
+
+// Followed by the hash of the voucher code.
+pub const VOUCHER_SEED: &[u8] = b"voucher";
+
+pub fn find_config_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CONFIG_SEED], program_id)
+}
+
+pub fn find_insurance_pool_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[INSURANCE_POOL_SEED], program_id)
+}
+
+pub fn find_global_stats_address(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[GLOBAL_STATS_SEED], program_id)
+}
+
+pub fn find_campaign_content_address(program_id: &Pubkey, campaign: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CAMPAIGN_CONTENT_SEED, campaign.as_ref()], program_id)
+}
+
+pub fn find_update_feed_address(program_id: &Pubkey, campaign: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[UPDATE_FEED_SEED, campaign.as_ref()], program_id)
+}
+
+pub fn find_campaign_name_address(program_id: &Pubkey, admin: &Pubkey, name_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CAMPAIGN_NAME_SEED, admin.as_ref(), name_hash], program_id)
+}
+
+pub fn find_receipt_address(program_id: &Pubkey, campaign: &Pubkey, sequence: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[RECEIPT_SEED, campaign.as_ref(), &sequence.to_le_bytes()], program_id)
+}
+
+pub fn find_donation_record_address(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[DONATION_SEED, campaign.as_ref(), donor.as_ref()], program_id)
+}
+
+pub fn find_match_address(program_id: &Pubkey, campaign: &Pubkey, sponsor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[MATCH_SEED, campaign.as_ref(), sponsor.as_ref()], program_id)
+}
+
+pub fn find_refund_claim_address(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFUND_CLAIM_SEED, campaign.as_ref(), donor.as_ref()], program_id)
+}
+
+pub fn find_blocklist_address(program_id: &Pubkey, campaign: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[BLOCKLIST_SEED, campaign.as_ref(), wallet.as_ref()], program_id)
+}
+
+pub fn find_refund_recipient_address(program_id: &Pubkey, campaign: &Pubkey, donor: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[REFUND_RECIPIENT_SEED, campaign.as_ref(), donor.as_ref()], program_id)
+}
+
+pub fn find_sponsor_address(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[SPONSOR_SEED, wallet.as_ref()], program_id)
+}
+
+pub fn find_charity_address(program_id: &Pubkey, wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CHARITY_SEED, wallet.as_ref()], program_id)
+}
+
+pub fn find_voucher_address(program_id: &Pubkey, code_hash: &[u8; 32]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VOUCHER_SEED, code_hash], program_id)
+}
@@ -0,0 +1,311 @@
+// `CampaignDetails::new`, the one place a new campaign's text and dates are checked, and the
+// zeroed `Default` everything else starts from. Campaigns without an end date don't need a bank.
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use program::{
+    campaign_name_hash, instruction, seeds::find_campaign_content_address, CampaignContent, CampaignDetails,
+    CampaignState, FundError, WithdrawPolicy, SOL_CURRENCY,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    hash::hash,
+    instruction::InstructionError,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::TransactionError,
+};
+use std::convert::TryInto;
+
+// Longer then the 1024 bytes a description can have.
+const TOO_LONG: usize = 1_025;
+
+#[test]
+fn default_is_zeroed() {
+    let campaign = CampaignDetails::default();
+    assert_eq!(campaign.admin, Pubkey::default());
+    assert_eq!(campaign.name, "");
+    assert_eq!(campaign.amount_donated, 0);
+    assert_eq!(campaign.goal, 0);
+    assert_eq!(campaign.end_timestamp, 0);
+    assert_eq!(campaign.currency, [0; 8]);
+    assert_eq!(campaign.state, CampaignState::Active);
+    assert_eq!(campaign.withdraw_policy, WithdrawPolicy::Anytime);
+    assert!(campaign.pending_donations.is_empty());
+    assert!(campaign.milestones.is_empty());
+}
+
+#[test]
+fn new_trims_the_text_and_starts_empty() {
+    let admin = Pubkey::new_unique();
+    let (campaign, description) =
+        CampaignDetails::new(admin, "  Clean water ", " Wells for the village ", " https://example.com/a.png ", 5, 0).unwrap();
+    assert_eq!(campaign.admin, admin);
+    assert_eq!(campaign.name, "Clean water");
+    assert_eq!(campaign.name_hash, campaign_name_hash("Clean water"));
+    assert_eq!(campaign.image_link, "https://example.com/a.png");
+    assert_eq!(description, "Wells for the village");
+    assert_eq!(campaign.goal, 5);
+    assert_eq!(campaign.end_timestamp, 0);
+    assert_eq!(campaign.currency, SOL_CURRENCY);
+    assert_eq!(campaign.amount_donated, 0);
+    assert_eq!(campaign.donor_count, 0);
+    assert_eq!(campaign.state, CampaignState::Active);
+}
+
+#[test]
+fn new_rejects_bad_text() {
+    let admin = Pubkey::new_unique();
+    let invalid = Err(ProgramError::InvalidInstructionData);
+    assert_eq!(CampaignDetails::new(admin, "   ", "", "", 0, 0).map(|_| ()), invalid);
+    assert_eq!(CampaignDetails::new(admin, "Clean\nwater", "", "", 0, 0).map(|_| ()), invalid);
+    assert_eq!(CampaignDetails::new(admin, "Clean water", "Two\tcolumns", "", 0, 0).map(|_| ()), invalid);
+    assert_eq!(CampaignDetails::new(admin, "Clean water", "", "https://example.com/\r", 0, 0).map(|_| ()), invalid);
+    assert_eq!(CampaignDetails::new(admin, "Clean water", &"a".repeat(TOO_LONG), "", 0, 0).map(|_| ()), invalid);
+    // The length is checked after trimming.
+    let padded = format!(" {} ", "a".repeat(TOO_LONG - 1));
+    assert!(CampaignDetails::new(admin, "Clean water", &padded, "", 0, 0).is_ok());
+}
+
+// The end date needs the clock, these go through create_campaign.
+#[tokio::test]
+async fn create_campaign_checks_through_new() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let now = env.now().await;
+
+    let ended = campaign_settings(&admin, LAMPORTS_PER_SOL, now);
+    let err = instruction_error(env.create_campaign(&admin, ended).await.map(|_| ()));
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+
+    let validate = instruction::validate_create(
+        &env.program_id,
+        campaign_settings(&admin, LAMPORTS_PER_SOL, now + 60),
+        &"a".repeat(TOO_LONG),
+    );
+    let err = instruction_error(env.process(&[validate], &[]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+
+    let campaign = Keypair::new();
+    let create = [
+        system_instruction::create_account(
+            &env.payer().pubkey(),
+            &campaign.pubkey(),
+            env.campaign_min_balance().await,
+            CAMPAIGN_SPACE as u64,
+            &env.program_id,
+        ),
+        instruction::create_campaign(
+            &env.program_id,
+            &campaign.pubkey(),
+            &env.treasury.pubkey(),
+            campaign_settings(&admin, LAMPORTS_PER_SOL, now + 60),
+            " Wells for the village ",
+        ),
+    ];
+    env.process(&create, &[&admin, &campaign]).await.unwrap();
+    let campaign = campaign.pubkey();
+    assert_eq!(env.campaign(&campaign).await.end_timestamp, now + 60);
+    let (content, _) = find_campaign_content_address(&env.program_id, &campaign);
+    let content: CampaignContent = env.borsh_account(&content).await;
+    assert_eq!(content.campaign, campaign);
+    assert_eq!(content.description, "Wells for the village");
+}
+
+#[tokio::test]
+async fn currency_label_is_stored_and_checked() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails { currency: *b"USDC\0\0\0\0", ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0) };
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    assert_eq!(&env.campaign(&campaign).await.currency, b"USDC\0\0\0\0");
+
+    // Non-ASCII bytes, a control character and padding before the end of the label.
+    for currency in [*b"\xe2\x82\xac\0\0\0\0\0", *b"SOL\n\0\0\0\0", *b"SO\0L\0\0\0\0"] {
+        let settings = CampaignDetails { currency, ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0) };
+        let err = instruction_error(env.create_campaign(&admin, settings).await.map(|_| ()));
+        assert_eq!(err, InstructionError::InvalidInstructionData, "{:?}", currency);
+    }
+}
+
+#[tokio::test]
+async fn attestation_is_set_at_creation() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let attestation = hash(b"kyc-provider:beneficiary-42").to_bytes();
+    let settings = CampaignDetails { attestation, ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0) };
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.attestation, attestation);
+
+    // Editing the campaign leaves it alone.
+    let update = instruction::update_campaign(&env.program_id, &campaign, &admin.pubkey(), "New description");
+    env.process(&[update], &[&admin]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.attestation, attestation);
+
+    let plain = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    assert_eq!(env.campaign(&plain).await.attestation, [0; 32]);
+}
+
+#[tokio::test]
+async fn goal_only_needs_an_end_date() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails { withdraw_policy: WithdrawPolicy::GoalOnly, ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0) };
+    let err = instruction_error(env.create_campaign(&admin, settings).await.map(|_| ()));
+    assert_eq!(err, fund_error(FundError::RefundRequiresDeadline));
+
+    let end = env.now().await + 86_400;
+    let settings = CampaignDetails { withdraw_policy: WithdrawPolicy::GoalOnly, ..campaign_settings(&admin, LAMPORTS_PER_SOL, end) };
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.withdraw_policy, WithdrawPolicy::GoalOnly);
+}
+
+#[tokio::test]
+async fn campaign_keeps_a_buffer_above_rent_exemption() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let rent_exempt = env.rent().await.minimum_balance(CAMPAIGN_SPACE);
+    let required = rent_exempt + RENT_BUFFER_LAMPORTS;
+    assert_eq!(env.campaign_min_balance().await, required);
+
+    // Exactly rent exempt isn't enough, the simulated error says how much is.
+    let campaign = Keypair::new();
+    let create_account = system_instruction::create_account(
+        &env.payer().pubkey(),
+        &campaign.pubkey(),
+        rent_exempt,
+        CAMPAIGN_SPACE as u64,
+        &env.program_id,
+    );
+    env.process(&[create_account], &[&campaign]).await.unwrap();
+    let settings = campaign_settings(&admin, LAMPORTS_PER_SOL, 0);
+    let create = instruction::create_campaign(&env.program_id, &campaign.pubkey(), &env.treasury.pubkey(), settings, "");
+    let simulation = env.simulate(create, &[&admin]).await;
+    assert_eq!(simulation.result, Err(TransactionError::InstructionError(0, InstructionError::InsufficientFunds)));
+    let context = |i: usize| u64::from_le_bytes(simulation.return_data[9 + 8 * i..17 + 8 * i].try_into().unwrap());
+    assert_eq!((context(0), context(1)), (required, rent_exempt));
+
+    // A withdraw leaves the buffer behind as well.
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let treasury = env.treasury.pubkey();
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL + 1)], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    env.process(&[withdraw(LAMPORTS_PER_SOL)], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&campaign).await, required);
+}
+
+// The campaign keypair still signs its own create_account, so it could sign as the admin too.
+#[tokio::test]
+async fn campaign_account_as_its_own_admin_is_rejected() {
+    let mut env = start().await;
+    let campaign = Keypair::new();
+    let create = [
+        system_instruction::create_account(
+            &env.payer().pubkey(),
+            &campaign.pubkey(),
+            env.campaign_min_balance().await,
+            CAMPAIGN_SPACE as u64,
+            &env.program_id,
+        ),
+        instruction::create_campaign(
+            &env.program_id,
+            &campaign.pubkey(),
+            &env.treasury.pubkey(),
+            campaign_settings(&campaign, LAMPORTS_PER_SOL, 0),
+            "",
+        ),
+    ];
+    let err = instruction_error(env.process(&create, &[&campaign]).await);
+    assert_eq!(err, fund_error(FundError::DuplicateAccount));
+}
+
+#[tokio::test]
+async fn set_image_only_changes_the_image() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails {
+        image_link: "https://example.com/a.png".to_string(),
+        withdraw_recipients: vec![Pubkey::new_unique()],
+        ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0)
+    };
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    let mut expected = env.campaign(&campaign).await;
+
+    let stranger = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let set_image = instruction::set_image(&env.program_id, &campaign, &stranger.pubkey(), "https://example.com/b.png");
+    let err = instruction_error(env.process(&[set_image], &[&stranger]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    let set_image = instruction::set_image(&env.program_id, &campaign, &admin.pubkey(), "https://example.com/\nb.png");
+    let err = instruction_error(env.process(&[set_image], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+
+    let set_image = instruction::set_image(&env.program_id, &campaign, &admin.pubkey(), " https://example.com/b.png ");
+    env.process(&[set_image], &[&admin]).await.unwrap();
+    expected.image_link = "https://example.com/b.png".to_string();
+    assert_eq!(env.campaign(&campaign).await.try_to_vec().unwrap(), expected.try_to_vec().unwrap());
+}
+
+#[tokio::test]
+async fn locked_metadata_cant_change_after_the_first_donation() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails {
+        name: "Clean water".to_string(),
+        metadata_locked: true,
+        ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0)
+    };
+    let locked = env.create_campaign(&admin, settings).await.unwrap();
+    let open = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let update = |campaign| instruction::update_campaign(&program_id(), campaign, &admin.pubkey(), "Wells for two villages");
+    // Until someone donates the admin can still fix a typo.
+    env.process(&[update(&locked)], &[&admin]).await.unwrap();
+
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&locked, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    env.donate(&open, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    let edits = [
+        update(&locked),
+        instruction::set_image(&env.program_id, &locked, &admin.pubkey(), "https://example.com/b.png"),
+        instruction::rename_campaign(&env.program_id, &locked, &admin.pubkey(), "Clean water", "Dirty water"),
+    ];
+    for edit in edits {
+        let err = instruction_error(env.process(&[edit], &[&admin]).await);
+        assert_eq!(err, fund_error(FundError::MetadataLocked));
+    }
+    let (content, _) = find_campaign_content_address(&env.program_id, &locked);
+    let content: CampaignContent = env.borsh_account(&content).await;
+    assert_eq!(content.description, "Wells for two villages");
+
+    // The lock is opt-in.
+    env.process(&[update(&open)], &[&admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn updates_wait_out_the_cooldown() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let (content, _) = find_campaign_content_address(&env.program_id, &campaign);
+    let update = |description| instruction::update_campaign(&program_id(), &campaign, &admin.pubkey(), description);
+
+    // The description set at creation doesn't start a cooldown.
+    env.process(&[update("First")], &[&admin]).await.unwrap();
+    let updated_at = env.borsh_account::<CampaignContent>(&content).await.last_update_ts;
+    let err = instruction_error(env.process(&[update("Second")], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::UpdateCooldown));
+    env.warp_to_timestamp(updated_at + 3_599).await;
+    let err = instruction_error(env.process(&[update("Second")], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::UpdateCooldown));
+
+    env.warp_to_timestamp(updated_at + 3_600).await;
+    env.process(&[update("Second")], &[&admin]).await.unwrap();
+    let content: CampaignContent = env.borsh_account(&content).await;
+    assert_eq!(content.description, "Second");
+    assert_eq!(content.last_update_ts, updated_at + 3_600);
+}
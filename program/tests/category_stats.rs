@@ -0,0 +1,80 @@
+// The per-category totals of the global stats: every way lamports come into a campaign counts in
+// its category, and a campaign can't change category once it counted a donation.
+mod common;
+
+use common::*;
+use program::{instruction, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}};
+
+async fn campaign_in(env: &mut Env, admin: &Keypair, category: u8) -> Pubkey {
+    let settings = program::CampaignDetails { category, ..campaign_settings(admin, 100 * LAMPORTS_PER_SOL, 0) };
+    env.create_campaign(admin, settings).await.unwrap()
+}
+
+#[tokio::test]
+async fn totals_follow_every_donation_path() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let water = campaign_in(&mut env, &admin, 1).await;
+    let schools = campaign_in(&mut env, &admin, 2).await;
+
+    // A direct donation, matched by a sponsor one for one.
+    let sponsor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&env.program_id, &sponsor.pubkey(), "Sponsor");
+    let commit = instruction::commit_match(&env.program_id, &water, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[register, commit], &[&sponsor]).await.unwrap();
+    let donor = env.new_wallet(10 * LAMPORTS_PER_SOL).await;
+    let donate = env.donate_instruction(&water, &donor, LAMPORTS_PER_SOL).await;
+    let donate = instruction::with_match(donate, &water, &sponsor.pubkey());
+    env.process(&[donate], &[&donor]).await.unwrap();
+
+    // A split over both campaigns.
+    let targets = [
+        (water, env.campaign(&water).await.donation_sequence, LAMPORTS_PER_SOL / 2),
+        (schools, env.campaign(&schools).await.donation_sequence, 3 * LAMPORTS_PER_SOL),
+    ];
+    let split = instruction::donate_split(&env.program_id, &donor.pubkey(), &targets);
+    env.process(&[split], &[&donor]).await.unwrap();
+
+    let stats = env.global_stats().await;
+    assert_eq!(stats.categories[1].campaigns_created, 1);
+    assert_eq!(stats.categories[2].campaigns_created, 1);
+    assert_eq!(stats.categories[1].amount_raised, 2 * LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2);
+    assert_eq!(stats.categories[2].amount_raised, 3 * LAMPORTS_PER_SOL);
+    assert_eq!(stats.categories[0].amount_raised, 0);
+
+    // A returned donation comes off again, the match stays.
+    let other = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&schools, &other, LAMPORTS_PER_SOL).await.unwrap();
+    assert_eq!(env.global_stats().await.categories[2].amount_raised, 4 * LAMPORTS_PER_SOL);
+    let give_back = instruction::return_donation(&env.program_id, &schools, &admin.pubkey(), &other.pubkey());
+    env.process(&[give_back], &[&admin]).await.unwrap();
+    let stats = env.global_stats().await;
+    assert_eq!(stats.categories[2].amount_raised, 3 * LAMPORTS_PER_SOL);
+    assert_eq!(stats.categories[1].amount_raised, 2 * LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2);
+}
+
+#[tokio::test]
+async fn category_is_locked_by_the_first_donation() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = campaign_in(&mut env, &admin, 1).await;
+
+    // Before it the campaign moves along with its count.
+    let move_it = instruction::set_category(&env.program_id, &campaign, &admin.pubkey(), 3);
+    env.process(&[move_it], &[&admin]).await.unwrap();
+    let stats = env.global_stats().await;
+    assert_eq!(stats.categories[1].campaigns_created, 0);
+    assert_eq!(stats.categories[3].campaigns_created, 1);
+
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let move_it = instruction::set_category(&env.program_id, &campaign, &admin.pubkey(), 1);
+    let err = instruction_error(env.process(&[move_it], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::CategoryLocked));
+    assert_eq!(env.campaign(&campaign).await.category, 3);
+    let stats = env.global_stats().await;
+    assert_eq!(stats.categories[3].amount_raised, LAMPORTS_PER_SOL);
+    assert_eq!(stats.categories[1].amount_raised, 0);
+}
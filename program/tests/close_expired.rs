@@ -0,0 +1,56 @@
+// The platform authority's housekeeping batch: empty campaigns that ended are closed with their
+// name and content PDAs, every other campaign in the batch is skipped and counted.
+mod common;
+
+use common::*;
+use program::{
+    instruction,
+    seeds::{find_campaign_content_address, find_campaign_name_address},
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+#[tokio::test]
+async fn mixed_batch_closes_only_the_empty_ended_campaigns() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let settings = campaign_settings(&admin, LAMPORTS_PER_SOL, end);
+    let name = settings.name.clone();
+    let empty = env.create_campaign(&admin, settings).await.unwrap();
+    let funded = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+    let running = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end + 86_400)).await.unwrap();
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    env.donate(&funded, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    env.warp_to_timestamp(end + 1).await;
+
+    let mut batch = Vec::new();
+    for campaign in [empty, funded, running] {
+        batch.push((campaign, admin.pubkey(), env.campaign(&campaign).await.name_hash));
+    }
+    // Someone else as the creator of the empty campaign, that group is skipped too.
+    batch.push((empty, Pubkey::new_unique(), batch[0].2));
+
+    let name_hash = batch[0].2;
+    let (name_record, _) = find_campaign_name_address(&env.program_id, &admin.pubkey(), &name_hash);
+    let (content, _) = find_campaign_content_address(&env.program_id, &empty);
+    let rent = env.balance(&empty).await + env.balance(&name_record).await + env.balance(&content).await;
+    let admin_before = env.balance(&admin.pubkey()).await;
+
+    let authority = env.authority.insecure_clone();
+    let close = instruction::close_expired_campaigns(&env.program_id, &authority.pubkey(), &batch);
+    let (result, logs) = env.process_with_logs(&[close], &[&authority]).await;
+    assert_eq!(result, Ok(()));
+    assert!(logs.contains(&"Program log: 0xfund:close_expired_campaigns:Closed 1 campaigns, skipped 3".to_string()), "{:?}", logs);
+
+    for closed in [empty, name_record, content] {
+        assert_eq!(env.context.banks_client.get_account(closed).await.unwrap(), None);
+    }
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + rent);
+    assert_eq!(env.campaign(&funded).await.amount_donated, LAMPORTS_PER_SOL / 2);
+    assert_eq!(env.campaign(&running).await.admin, admin.pubkey());
+
+    // The name is free again.
+    let settings = campaign_settings(&admin, LAMPORTS_PER_SOL, 0);
+    env.create_campaign(&admin, program::CampaignDetails { name, ..settings }).await.unwrap();
+}
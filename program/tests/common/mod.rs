@@ -0,0 +1,376 @@
+// Shared setup of the program-test suites: a bank running the program natively with the config
+// initialized, and helpers to create campaigns and wallets, move the clock and read accounts back.
+// Not every test file uses all of it.
+#![allow(dead_code)]
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use borsh::{BorshDeserialize, BorshSerialize};
+use program::{instruction, seeds::*, CampaignDetails, FundError, GlobalStats, PlatformConfig, SOL_CURRENCY};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use std::sync::Once;
+
+// What a campaign account is allocated with, enough for the lists to grow like the client leaves.
+pub const CAMPAIGN_SPACE: usize = 2_048;
+// Kept in a campaign on top of its rent exemption, see `campaign_min_balance`.
+pub const RENT_BUFFER_LAMPORTS: u64 = 10_000;
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+pub fn program_id() -> Pubkey {
+    Pubkey::new_from_array([0xf0; 32])
+}
+
+pub fn program_test() -> ProgramTest {
+    ProgramTest::new("program", program_id(), processor!(program::process_instruction))
+}
+
+// The SBF build of the program, `program.so` from `cargo build-sbf` found through SBF_OUT_DIR.
+// Only it is metered like on a validator, natively the compute units don't mean anything.
+pub fn sbf_program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new("program", program_id(), None);
+    program_test.prefer_bpf(true);
+    program_test
+}
+
+pub struct Env {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub authority: Keypair,
+    pub treasury: Keypair,
+}
+
+// A bank with the config initialized without any fee, and a rent exempt treasury.
+pub async fn start() -> Env {
+    start_with(program_test()).await
+}
+
+pub async fn start_with(program_test: ProgramTest) -> Env {
+    let context = program_test.start_with_context().await;
+    log_events();
+    let mut env = Env {
+        context,
+        program_id: program_id(),
+        authority: Keypair::new(),
+        treasury: Keypair::new(),
+    };
+    let authority = env.authority.insecure_clone();
+    let treasury = env.treasury.pubkey();
+    env.airdrop(&authority.pubkey(), 10 * LAMPORTS_PER_SOL).await;
+    env.airdrop(&treasury, LAMPORTS_PER_SOL).await;
+    let init = instruction::initialize_config(
+        &env.program_id,
+        &authority.pubkey(),
+        &authority.pubkey(),
+        &treasury,
+        0,
+        0,
+        0,
+        false,
+        0,
+        Vec::new(),
+    );
+    env.process(&[init], &[&authority]).await.unwrap();
+    env
+}
+
+impl Env {
+    pub fn payer(&self) -> Keypair {
+        self.context.payer.insecure_clone()
+    }
+
+    // Sends the instructions in one transaction paid by the payer, with a fresh blockhash so the
+    // same instructions can be sent twice. It goes through `process_with_logs`: banks-server's
+    // process_transaction hands the transaction to a sender thread and returns once its status is
+    // in, which can be before the bank unlocked its accounts, and the next transaction then fails
+    // with AccountInUse.
+    pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+        let (result, _) = self.process_with_logs(instructions, signers).await;
+        result.map_err(BanksClientError::TransactionError)
+    }
+
+    // Like `process`, also returning the logs of the transaction.
+    pub async fn process_with_logs(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> (Result<(), TransactionError>, Vec<String>) {
+        let transaction = self.transaction(instructions, signers).await;
+        let outcome = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        let logs = outcome.metadata.map(|metadata| metadata.log_messages).unwrap_or_default();
+        (outcome.result, logs)
+    }
+
+    // Simulates one instruction, returning its result, logs, return data and compute units.
+    pub async fn simulate(&mut self, instruction: Instruction, signers: &[&Keypair]) -> Simulation {
+        let transaction = self.transaction(&[instruction], signers).await;
+        let simulation = self.context.banks_client.simulate_transaction(transaction).await.unwrap();
+        let details = simulation.simulation_details.unwrap();
+        Simulation {
+            result: simulation.result.unwrap(),
+            logs: details.logs,
+            return_data: details.return_data.map(|data| data.data).unwrap_or_default(),
+            units_consumed: details.units_consumed,
+        }
+    }
+
+    async fn transaction(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Transaction {
+        let blockhash = self.context.get_new_latest_blockhash().await.unwrap();
+        let payer = self.payer();
+        let mut all_signers = vec![&payer];
+        all_signers.extend_from_slice(signers);
+        Transaction::new_signed_with_payer(instructions, Some(&payer.pubkey()), &all_signers, blockhash)
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let payer = self.payer();
+        let transfer = system_instruction::transfer(&payer.pubkey(), to, lamports);
+        self.process(&[transfer], &[]).await.unwrap();
+    }
+
+    pub async fn new_wallet(&mut self, lamports: u64) -> Keypair {
+        let wallet = Keypair::new();
+        self.airdrop(&wallet.pubkey(), lamports).await;
+        wallet
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    // Moves the clock to `unix_timestamp`, the slot stays where it is.
+    pub async fn warp_to_timestamp(&mut self, unix_timestamp: i64) {
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = unix_timestamp;
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn rent(&mut self) -> Rent {
+        self.context.banks_client.get_rent().await.unwrap()
+    }
+
+    // What create_campaign asks the campaign account to hold.
+    pub async fn campaign_min_balance(&mut self) -> u64 {
+        self.rent().await.minimum_balance(CAMPAIGN_SPACE) + RENT_BUFFER_LAMPORTS
+    }
+
+    // Creates a campaign of `admin` with `settings` and returns its address.
+    pub async fn create_campaign(&mut self, admin: &Keypair, settings: CampaignDetails) -> Result<Pubkey, BanksClientError> {
+        let campaign = Keypair::new();
+        let lamports = self.campaign_min_balance().await;
+        let payer = self.payer();
+        let create_account = system_instruction::create_account(
+            &payer.pubkey(),
+            &campaign.pubkey(),
+            lamports,
+            CAMPAIGN_SPACE as u64,
+            &self.program_id,
+        );
+        let create = instruction::create_campaign(&self.program_id, &campaign.pubkey(), &self.treasury.pubkey(), settings, "");
+        self.process(&[create_account, create], &[admin, &campaign]).await?;
+        Ok(campaign.pubkey())
+    }
+
+    pub async fn campaign(&mut self, campaign: &Pubkey) -> CampaignDetails {
+        let data = self.account(campaign).await.data;
+        CampaignDetails::deserialize(&mut &data[..]).unwrap()
+    }
+
+    // Saves `campaign_data` into the campaign account as it is, for states the instructions take
+    // a while to reach.
+    pub async fn set_campaign(&mut self, campaign: &Pubkey, campaign_data: &CampaignDetails) {
+        let mut account = self.account(campaign).await;
+        let data = campaign_data.try_to_vec().unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.context.set_account(campaign, &account.into());
+    }
+
+    pub async fn config(&mut self) -> PlatformConfig {
+        let (config, _) = find_config_address(&self.program_id);
+        let data = self.account(&config).await.data;
+        PlatformConfig::deserialize(&mut &data[..]).unwrap()
+    }
+
+    pub async fn set_config(&mut self, config_data: &PlatformConfig) {
+        let (config, _) = find_config_address(&self.program_id);
+        let mut account = self.account(&config).await;
+        let data = config_data.try_to_vec().unwrap();
+        account.data[..data.len()].copy_from_slice(&data);
+        self.context.set_account(&config, &account.into());
+    }
+
+    pub async fn global_stats(&mut self) -> GlobalStats {
+        let (global_stats, _) = find_global_stats_address(&self.program_id);
+        self.borsh_account(&global_stats).await
+    }
+
+    pub async fn account(&mut self, address: &Pubkey) -> Account {
+        self.context.banks_client.get_account(*address).await.unwrap().unwrap()
+    }
+
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.context.banks_client.get_balance(*address).await.unwrap()
+    }
+
+    pub async fn borsh_account<T: BorshDeserialize>(&mut self, address: &Pubkey) -> T {
+        let data = self.account(address).await.data;
+        T::deserialize(&mut &data[..]).unwrap()
+    }
+
+    // Donates `amount` from `donor` with donate_direct.
+    pub async fn donate(&mut self, campaign: &Pubkey, donor: &Keypair, amount: u64) -> Result<(), BanksClientError> {
+        let donate = self.donate_instruction(campaign, donor, amount).await;
+        self.process(&[donate], &[donor]).await
+    }
+
+    pub async fn donate_instruction(&mut self, campaign: &Pubkey, donor: &Keypair, amount: u64) -> Instruction {
+        let sequence = self.campaign(campaign).await.donation_sequence;
+        instruction::donate_direct(
+            &self.program_id,
+            campaign,
+            &donor.pubkey(),
+            &self.treasury.pubkey(),
+            sequence,
+            amount,
+            false,
+            0,
+            0,
+        )
+    }
+}
+
+// Settings of a campaign with everything else at its default. Names are unique, an admin can't
+// have two campaigns with the same one.
+pub fn campaign_settings(admin: &Keypair, goal: u64, end_timestamp: i64) -> CampaignDetails {
+    CampaignDetails {
+        admin: admin.pubkey(),
+        name: format!("Campaign {}", Pubkey::new_unique()),
+        goal,
+        end_timestamp,
+        currency: SOL_CURRENCY,
+        ..Default::default()
+    }
+}
+
+pub struct Simulation {
+    pub result: Result<(), TransactionError>,
+    pub logs: Vec<String>,
+    pub return_data: Vec<u8>,
+    pub units_consumed: u64,
+}
+
+// The program runs natively here, and program-test's stubs print its `sol_log_data` events to
+// stdout instead of the transaction logs. This puts them in the logs as `Program log: data: ...`
+// lines, everything else goes to program-test's stubs as before. Installed once, after
+// program-test installed its own.
+struct EventStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for EventStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.0.sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields.iter().map(|field| STANDARD.encode(field)).collect();
+        self.0.sol_log(&format!("data: {}", fields.join(" ")))
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+fn log_events() {
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        let program_test_stubs = set_syscall_stubs(Box::new(DefaultStubs));
+        set_syscall_stubs(Box::new(EventStubs(program_test_stubs)));
+    });
+}
+
+struct DefaultStubs;
+impl SyscallStubs for DefaultStubs {}
+
+// The `sol_log_data` events in the logs, each one the list of its fields. On chain they are the
+// `Program data: ` lines, see `EventStubs` for the native ones.
+pub fn events(logs: &[String]) -> Vec<Vec<Vec<u8>>> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: ").or_else(|| log.strip_prefix("Program log: data: ")))
+        .map(|data| {
+            data.split(' ')
+                .map(|field| STANDARD.decode(field).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+// The instruction error a failed transaction ended with.
+pub fn instruction_error(result: Result<(), BanksClientError>) -> InstructionError {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, err) => err,
+        err => panic!("expected an instruction error, got {:?}", err),
+    }
+}
+
+pub fn fund_error(err: FundError) -> InstructionError {
+    InstructionError::Custom(err as u32)
+}
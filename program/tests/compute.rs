@@ -0,0 +1,124 @@
+// Compute units of the SBF build. Natively nothing is metered, so these are ignored by default
+// and need the program built for SBF first:
+//   cargo build-sbf --manifest-path program/Cargo.toml
+//   SBF_OUT_DIR=target/deploy cargo test -p program --test compute -- --ignored
+mod common;
+
+use common::*;
+use program::instruction;
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+// A donation to an ended campaign is turned away by the counter block, before the campaign is
+// decoded. A dust donation to an open one only fails after the decode, the difference is what the
+// zero-copy check saves.
+#[tokio::test]
+#[ignore = "needs the SBF build of the program"]
+async fn ended_campaign_rejects_a_donation_before_the_decode() {
+    let mut env = start_with(sbf_program_test()).await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 100;
+    let ending = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+    let open = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end + 86_400)).await.unwrap();
+    env.warp_to_timestamp(end).await;
+    let mut config = env.config().await;
+    config.min_donation_lamports = 10_000_000;
+    env.set_config(&config).await;
+
+    let late = env.donate_instruction(&ending, &donor, LAMPORTS_PER_SOL / 10).await;
+    let late = env.simulate(late, &[&donor]).await;
+    let dust = env.donate_instruction(&open, &donor, 1).await;
+    let dust = env.simulate(dust, &[&donor]).await;
+    assert!(late.result.is_err() && dust.result.is_err());
+    println!("ended campaign: {} CU, dust donation: {} CU", late.units_consumed, dust.units_consumed);
+    assert!(late.units_consumed < dust.units_consumed);
+}
+
+// Same for withdraw: a disputed campaign is turned away by the counter block, asking for more
+// then the balance only fails once the campaign is decoded.
+#[tokio::test]
+#[ignore = "needs the SBF build of the program"]
+async fn disputed_campaign_rejects_a_withdraw_before_the_decode() {
+    let mut env = start_with(sbf_program_test()).await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let disputed = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+    let open = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+    let mut campaign_data = env.campaign(&disputed).await;
+    campaign_data.disputed = true;
+    env.set_campaign(&disputed, &campaign_data).await;
+
+    let treasury = env.treasury.pubkey();
+    let blocked = instruction::withdraw(&env.program_id, &disputed, &admin.pubkey(), &treasury, 1);
+    let blocked = env.simulate(blocked, &[&admin]).await;
+    let too_much = instruction::withdraw(&env.program_id, &open, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    let too_much = env.simulate(too_much, &[&admin]).await;
+    assert!(blocked.result.is_err() && too_much.result.is_err());
+    println!("disputed: {} CU, over the balance: {} CU", blocked.units_consumed, too_much.units_consumed);
+    assert!(blocked.units_consumed < too_much.units_consumed);
+}
+
+// Budgets of the read-only queries. version and health_check don't decode the campaign, so their
+// cost doesn't move when it grows. get_withdrawable decodes it and has to stay well under the
+// 200_000 CU an instruction gets by default even with every list full.
+const VERSION_BUDGET: u64 = 1_000;
+const HEALTH_CHECK_BUDGET: u64 = 3_000;
+const GET_WITHDRAWABLE_BUDGET: u64 = 25_000;
+
+#[tokio::test]
+#[ignore = "needs the SBF build of the program"]
+async fn read_only_queries_stay_cheap_as_the_campaign_grows() {
+    let mut env = start_with(sbf_program_test()).await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+
+    let version = env.simulate(instruction::version(&env.program_id), &[]).await;
+    assert!(version.result.is_ok());
+    println!("version: {} CU", version.units_consumed);
+    assert!(version.units_consumed < VERSION_BUDGET);
+
+    let small_health = env.simulate(instruction::health_check(&env.program_id, &campaign), &[]).await;
+    let small_withdrawable = env.simulate(instruction::get_withdrawable(&env.program_id, &campaign), &[]).await;
+    assert!(small_health.result.is_ok() && small_withdrawable.result.is_ok());
+
+    // Every list after the counter block full, like a campaign that has been running for a while.
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.withdraw_recipients = vec![admin.pubkey(); 8];
+    campaign_data.previous_cycles = (0..8).map(|_| Default::default()).collect();
+    campaign_data.withdraw_queue = vec![Default::default(); 4];
+    campaign_data.milestones = vec![Default::default(); 8];
+    env.set_campaign(&campaign, &campaign_data).await;
+
+    let health = env.simulate(instruction::health_check(&env.program_id, &campaign), &[]).await;
+    let withdrawable = env.simulate(instruction::get_withdrawable(&env.program_id, &campaign), &[]).await;
+    assert!(health.result.is_ok() && withdrawable.result.is_ok());
+    println!(
+        "health_check: {} -> {} CU, get_withdrawable: {} -> {} CU",
+        small_health.units_consumed, health.units_consumed, small_withdrawable.units_consumed, withdrawable.units_consumed
+    );
+    assert_eq!(health.units_consumed, small_health.units_consumed);
+    assert!(health.units_consumed < HEALTH_CHECK_BUDGET);
+    assert!(withdrawable.units_consumed < GET_WITHDRAWABLE_BUDGET);
+}
+
+// The queries get the campaign read-only, the runtime fails any of them that writes to it.
+#[tokio::test]
+async fn read_only_queries_dont_write() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+    let before = env.account(&campaign).await;
+
+    for query in [
+        instruction::version(&env.program_id),
+        instruction::health_check(&env.program_id, &campaign),
+        instruction::get_withdrawable(&env.program_id, &campaign),
+    ] {
+        assert!(query.accounts.iter().all(|meta| !meta.is_writable && !meta.is_signer));
+        env.process(&[query], &[]).await.unwrap();
+    }
+    assert_eq!(env.account(&campaign).await, before);
+}
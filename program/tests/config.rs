@@ -0,0 +1,50 @@
+// The platform config and the PDAs created with it.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::*};
+use solana_program_test::tokio;
+use solana_sdk::{instruction::InstructionError, signature::Signer};
+
+#[tokio::test]
+async fn initialize_config_creates_the_platform_accounts() {
+    let mut env = start().await;
+    let config = env.config().await;
+    assert_eq!(config.authority, env.authority.pubkey());
+    assert_eq!(config.treasury, env.treasury.pubkey());
+    assert_eq!(config.fee_bps, 0);
+
+    let (insurance_pool, _) = find_insurance_pool_address(&env.program_id);
+    let (global_stats, _) = find_global_stats_address(&env.program_id);
+    assert_eq!(env.account(&insurance_pool).await.owner, env.program_id);
+    assert_eq!(env.account(&global_stats).await.owner, env.program_id);
+}
+
+#[tokio::test]
+async fn config_can_only_be_initialized_once() {
+    let mut env = start().await;
+    let intruder = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let init = instruction::initialize_config(
+        &env.program_id,
+        &intruder.pubkey(),
+        &intruder.pubkey(),
+        &intruder.pubkey(),
+        10_000,
+        0,
+        0,
+        false,
+        0,
+        Vec::new(),
+    );
+    let err = instruction_error(env.process(&[init], &[&intruder]).await);
+    assert_eq!(err, InstructionError::AccountAlreadyInitialized);
+    assert_eq!(env.config().await.authority, env.authority.pubkey());
+}
+
+#[tokio::test]
+async fn version_returns_the_crate_version() {
+    let mut env = start().await;
+    let simulation = env.simulate(instruction::version(&env.program_id), &[]).await;
+    simulation.result.unwrap();
+    assert_eq!(simulation.return_data, env!("CARGO_PKG_VERSION").as_bytes());
+}
@@ -0,0 +1,125 @@
+// Campaigns with a confirmation threshold: donations wait in `pending_donations` until they are
+// old enough, then count in `amount_donated`.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, CampaignDetails, CampaignState, DonationRecord};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const CONFIRMATION_SLOTS: u64 = 1_000;
+// MAX_PENDING_DONATIONS in the program.
+const MAX_PENDING_DONATIONS: usize = 8;
+// PENDING_RECLAIM_SLOTS in the program.
+const PENDING_RECLAIM_SLOTS: u64 = 9_000;
+
+async fn pending_campaign(env: &mut Env, admin: &Keypair, goal: u64, end_timestamp: i64) -> Pubkey {
+    let settings = CampaignDetails {
+        confirmation_slots: CONFIRMATION_SLOTS,
+        ..campaign_settings(admin, goal, end_timestamp)
+    };
+    env.create_campaign(admin, settings).await.unwrap()
+}
+
+#[tokio::test]
+async fn pending_donation_is_confirmed_after_enough_slots() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = pending_campaign(&mut env, &admin, 100 * LAMPORTS_PER_SOL, 0).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 0);
+    assert_eq!(campaign_data.pending_amount(), LAMPORTS_PER_SOL);
+
+    // Still too young, confirming leaves it pending.
+    let confirm = instruction::confirm_donations(&env.program_id, &campaign);
+    env.process(&[confirm], &[]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+
+    let slot = env.clock().await.slot;
+    env.context.warp_to_slot(slot + CONFIRMATION_SLOTS).unwrap();
+    let confirm = instruction::confirm_donations(&env.program_id, &campaign);
+    env.process(&[confirm], &[]).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+    assert!(campaign_data.pending_donations.is_empty());
+}
+
+#[tokio::test]
+async fn finalize_counts_matured_donations_nobody_confirmed() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = pending_campaign(&mut env, &admin, LAMPORTS_PER_SOL, end).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let slot = env.clock().await.slot;
+    env.context.warp_to_slot(slot + CONFIRMATION_SLOTS).unwrap();
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.state, CampaignState::Successful);
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+    assert!(campaign_data.pending_donations.is_empty());
+}
+
+// A full queue doesn't lock other donors out, the oldest pending donation counts early to make room.
+#[tokio::test]
+async fn full_queue_counts_the_oldest_donation_early() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = pending_campaign(&mut env, &admin, 100 * LAMPORTS_PER_SOL, 0).await;
+    let spammer = env.new_wallet(LAMPORTS_PER_SOL).await;
+    for _ in 0..MAX_PENDING_DONATIONS {
+        env.donate(&campaign, &spammer, LAMPORTS_PER_SOL / 100).await.unwrap();
+    }
+    assert_eq!(env.campaign(&campaign).await.pending_donations.len(), MAX_PENDING_DONATIONS);
+
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.pending_donations.len(), MAX_PENDING_DONATIONS);
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL / 100);
+    assert_eq!(campaign_data.pending_amount(), (MAX_PENDING_DONATIONS as u64 - 1) * LAMPORTS_PER_SOL / 100 + LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.pending_donations.last().unwrap().donor, donor.pubkey());
+}
+
+// A donation nobody confirmed goes back to its donor once PENDING_RECLAIM_SLOTS passed.
+#[tokio::test]
+async fn unconfirmed_donation_is_reclaimed_after_the_timeout() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = pending_campaign(&mut env, &admin, 100 * LAMPORTS_PER_SOL, 0).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let campaign_balance = env.balance(&campaign).await;
+
+    let reclaim = instruction::reclaim_pending(&env.program_id, &campaign, &donor.pubkey());
+    let err = instruction_error(env.process(&[reclaim], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    let slot = env.clock().await.slot;
+    env.context.warp_to_slot(slot + PENDING_RECLAIM_SLOTS).unwrap();
+    let donor_balance = env.balance(&donor.pubkey()).await;
+    let reclaim = instruction::reclaim_pending(&env.program_id, &campaign, &donor.pubkey());
+    env.process(&[reclaim], &[&donor]).await.unwrap();
+
+    assert_eq!(env.balance(&donor.pubkey()).await, donor_balance + LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&campaign).await, campaign_balance - LAMPORTS_PER_SOL);
+    let campaign_data = env.campaign(&campaign).await;
+    assert!(campaign_data.pending_donations.is_empty());
+    assert_eq!(campaign_data.amount_donated, 0);
+    assert_eq!(campaign_data.donor_count, 0);
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.amount, 0);
+}
@@ -0,0 +1,153 @@
+// No instruction creates or destroys lamports: across random campaigns going through create,
+// donate, withdraw, finalize, refund and close, what every transaction takes out of its accounts is
+// exactly its fee. Each seed is one reproducible scenario.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignState};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use std::{collections::BTreeSet, convert::TryInto};
+
+// xorshift64, enough to pick amounts and paths reproducibly.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // A number in `low..=high`.
+    fn between(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next() % (high - low + 1)
+    }
+}
+
+// Sends the instructions and checks the accounts they touch, the payer included, lost exactly the fee.
+async fn process_conserving(env: &mut Env, instructions: &[Instruction], signers: &[&Keypair]) {
+    let payer = env.payer().pubkey();
+    let mut keys = BTreeSet::new();
+    keys.insert(payer);
+    for instruction in instructions {
+        keys.extend(instruction.accounts.iter().map(|meta| meta.pubkey));
+    }
+    let blockhash = env.context.banks_client.get_latest_blockhash().await.unwrap();
+    let fee = env
+        .context
+        .banks_client
+        .get_fee_for_message(Message::new_with_blockhash(instructions, Some(&payer), &blockhash))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let before = total_balance(env, &keys).await;
+    env.process(instructions, signers).await.unwrap();
+    let after = total_balance(env, &keys).await;
+    assert_eq!(before - after, fee, "lamports appeared or vanished in {:?}", instructions);
+}
+
+async fn total_balance(env: &mut Env, keys: &BTreeSet<Pubkey>) -> u64 {
+    let mut total = 0;
+    for key in keys {
+        total += env.balance(key).await;
+    }
+    total
+}
+
+async fn withdrawable(env: &mut Env, campaign: &Pubkey) -> u64 {
+    let query = instruction::get_withdrawable(&env.program_id, campaign);
+    let simulation = env.simulate(query, &[]).await;
+    u64::from_le_bytes(simulation.return_data[..8].try_into().unwrap())
+}
+
+async fn scenario(seed: u64) {
+    let mut rng = Rng(seed);
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.fee_bps = rng.between(0, 500) as u16;
+    config.insurance_bps = rng.between(0, 5_000) as u16;
+    env.set_config(&config).await;
+    let authority = env.authority.insecure_clone();
+
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let goal = rng.between(1, 8) * LAMPORTS_PER_SOL / 4;
+    let campaign = Keypair::new();
+    let create_account = system_instruction::create_account(
+        &env.payer().pubkey(),
+        &campaign.pubkey(),
+        env.campaign_min_balance().await,
+        CAMPAIGN_SPACE as u64,
+        &env.program_id,
+    );
+    let create = instruction::create_campaign(
+        &env.program_id,
+        &campaign.pubkey(),
+        &env.treasury.pubkey(),
+        campaign_settings(&admin, goal, end),
+        "",
+    );
+    process_conserving(&mut env, &[create_account, create], &[&admin, &campaign]).await;
+    let campaign = campaign.pubkey();
+
+    let mut donors = Vec::new();
+    for _ in 0..rng.between(1, 4) {
+        let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+        for _ in 0..rng.between(1, 2) {
+            let amount = rng.between(10_000_000, LAMPORTS_PER_SOL / 2);
+            let donate = env.donate_instruction(&campaign, &donor, amount).await;
+            process_conserving(&mut env, &[donate], &[&donor]).await;
+        }
+        donors.push(donor);
+    }
+
+    // Sometimes the admin takes part of it before the end, the refunds then come from a short pool.
+    if rng.next().is_multiple_of(2) {
+        let amount = withdrawable(&mut env, &campaign).await * rng.between(1, 9) / 10;
+        if amount > 0 {
+            let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), amount);
+            process_conserving(&mut env, &[withdraw], &[&admin]).await;
+        }
+    }
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    process_conserving(&mut env, &[finalize], &[]).await;
+
+    if env.campaign(&campaign).await.state == CampaignState::Failed {
+        for donor in &donors {
+            let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+            process_conserving(&mut env, &[claim], &[donor]).await;
+        }
+    }
+    let amount = withdrawable(&mut env, &campaign).await;
+    if amount > 0 {
+        let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), amount);
+        process_conserving(&mut env, &[withdraw], &[&admin]).await;
+    }
+
+    // The batch skips a campaign that still holds more then its minimum balance.
+    let empty = env.balance(&campaign).await <= env.campaign_min_balance().await;
+    let name_hash = env.campaign(&campaign).await.name_hash;
+    let close = instruction::close_expired_campaigns(&env.program_id, &authority.pubkey(), &[(campaign, admin.pubkey(), name_hash)]);
+    process_conserving(&mut env, &[close], &[&authority]).await;
+    if empty {
+        assert_eq!(env.context.banks_client.get_account(campaign).await.unwrap(), None);
+    }
+}
+
+#[tokio::test]
+async fn random_campaigns_conserve_lamports() {
+    for seed in [0x0f_u64, 0xf00d, 0xdead_beef, 0x1234_5678_9abc, 7, 42, 1_000_003, 0xc0ffee] {
+        scenario(seed).await;
+    }
+}
@@ -0,0 +1,71 @@
+// The platform authority correcting a campaign's amount_donated, and the totals it can't go below.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignState};
+use solana_program_test::tokio;
+use solana_sdk::{instruction::InstructionError, signature::Signer};
+
+#[tokio::test]
+async fn correction_is_applied_and_logged() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, 2 * LAMPORTS_PER_SOL).await.unwrap();
+
+    let authority = env.authority.insecure_clone();
+    let correct = instruction::correct_accounting(&env.program_id, &campaign, &authority.pubkey(), LAMPORTS_PER_SOL, 1);
+    env.process(&[correct], &[&authority]).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.corrections.len(), 1);
+    assert_eq!(campaign_data.corrections[0].old_amount_donated, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.corrections[0].new_amount_donated, LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.corrections[0].reason_code, 1);
+}
+
+#[tokio::test]
+async fn correction_cant_go_below_non_refundable_donations() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let donate = instruction::donate_direct(
+        &env.program_id,
+        &campaign,
+        &donor.pubkey(),
+        &env.treasury.pubkey(),
+        sequence,
+        2 * LAMPORTS_PER_SOL,
+        true,
+        0,
+        0,
+    );
+    env.process(&[donate], &[&donor]).await.unwrap();
+
+    let authority = env.authority.insecure_clone();
+    let correct = instruction::correct_accounting(&env.program_id, &campaign, &authority.pubkey(), LAMPORTS_PER_SOL, 1);
+    let err = instruction_error(env.process(&[correct], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 2 * LAMPORTS_PER_SOL);
+    assert!(campaign_data.corrections.is_empty());
+}
+
+#[tokio::test]
+async fn failed_campaign_cant_be_corrected() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.state = CampaignState::Failed;
+    env.set_campaign(&campaign, &campaign_data).await;
+
+    let authority = env.authority.insecure_clone();
+    let correct = instruction::correct_accounting(&env.program_id, &campaign, &authority.pubkey(), LAMPORTS_PER_SOL, 1);
+    let err = instruction_error(env.process(&[correct], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+}
@@ -0,0 +1,70 @@
+// One donation split over several campaigns with donate_split: every target is credited, or
+// none is when one of them can't take it.
+mod common;
+
+use common::*;
+use program::instruction;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+async fn three_campaigns(env: &mut Env) -> Vec<Pubkey> {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let mut campaigns = Vec::new();
+    for _ in 0..3 {
+        campaigns.push(env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap());
+    }
+    campaigns
+}
+
+async fn split(env: &mut Env, donor: &Keypair, campaigns: &[Pubkey], amounts: &[u64]) -> Instruction {
+    let mut targets = Vec::new();
+    for (campaign, amount) in campaigns.iter().zip(amounts) {
+        targets.push((*campaign, env.campaign(campaign).await.donation_sequence, *amount));
+    }
+    instruction::donate_split(&env.program_id, &donor.pubkey(), &targets)
+}
+
+#[tokio::test]
+async fn split_credits_every_campaign() {
+    let mut env = start().await;
+    let campaigns = three_campaigns(&mut env).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let amounts = [LAMPORTS_PER_SOL / 2, LAMPORTS_PER_SOL / 4, LAMPORTS_PER_SOL / 4];
+    let donor_before = env.balance(&donor.pubkey()).await;
+
+    let donate = split(&mut env, &donor, &campaigns, &amounts).await;
+    env.process(&[donate], &[&donor]).await.unwrap();
+    for (campaign, amount) in campaigns.iter().zip(amounts) {
+        let campaign_data = env.campaign(campaign).await;
+        assert_eq!(campaign_data.amount_donated, amount);
+        assert_eq!(campaign_data.donor_count, 1);
+    }
+    // The donor also paid the rent of a record and a receipt per campaign.
+    assert!(env.balance(&donor.pubkey()).await < donor_before - LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn a_paused_campaign_reverts_the_whole_split() {
+    let mut env = start().await;
+    let campaigns = three_campaigns(&mut env).await;
+    let mut paused = env.campaign(&campaigns[1]).await;
+    paused.admin_paused = true;
+    env.set_campaign(&campaigns[1], &paused).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let donor_before = env.balance(&donor.pubkey()).await;
+
+    let amounts = [LAMPORTS_PER_SOL / 2, LAMPORTS_PER_SOL / 4, LAMPORTS_PER_SOL / 4];
+    let donate = split(&mut env, &donor, &campaigns, &amounts).await;
+    let err = instruction_error(env.process(&[donate], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // Not even the first campaign, credited before the paused one was reached, kept its share.
+    for campaign in &campaigns {
+        assert_eq!(env.campaign(campaign).await.amount_donated, 0);
+    }
+    assert_eq!(env.balance(&donor.pubkey()).await, donor_before);
+}
@@ -0,0 +1,248 @@
+// Donating with donate_direct: the platform's floor on a donation and donation window, and
+// donations landing together.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, DonationRecord, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+const MIN_DONATION: u64 = 10_000_000;
+
+async fn open_campaign(env: &mut Env) -> Pubkey {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap()
+}
+
+async fn set_donation_limits(env: &mut Env, min_donation: u64, max_donations: u32, window_secs: u64) {
+    let authority = env.authority.insecure_clone();
+    let limits =
+        instruction::set_donation_limits(&env.program_id, &authority.pubkey(), min_donation, max_donations, window_secs);
+    env.process(&[limits], &[&authority]).await.unwrap();
+}
+
+#[tokio::test]
+async fn dust_donations_are_rejected_under_the_platform_floor() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+
+    // A new config has no floor.
+    env.donate(&campaign, &donor, 1).await.unwrap();
+
+    set_donation_limits(&mut env, MIN_DONATION, 50, 60).await;
+    let err = instruction_error(env.donate(&campaign, &donor, MIN_DONATION - 1).await);
+    assert_eq!(err, fund_error(FundError::DonationTooSmall));
+    env.donate(&campaign, &donor, MIN_DONATION).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, MIN_DONATION + 1);
+}
+
+#[tokio::test]
+async fn the_donation_window_follows_the_config() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    set_donation_limits(&mut env, MIN_DONATION, 5, 60).await;
+    let spammer = env.new_wallet(LAMPORTS_PER_SOL).await;
+
+    // Filling the window costs 5 minimum donations, all kept by the campaign.
+    for _ in 0..5 {
+        env.donate(&campaign, &spammer, MIN_DONATION).await.unwrap();
+    }
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 5 * MIN_DONATION);
+
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let err = instruction_error(env.donate(&campaign, &donor, MIN_DONATION).await);
+    assert_eq!(err, fund_error(FundError::DonationVelocityExceeded));
+
+    // The window moves on after a minute.
+    let now = env.now().await;
+    env.warp_to_timestamp(now + 60).await;
+    env.donate(&campaign, &donor, MIN_DONATION).await.unwrap();
+}
+
+#[tokio::test]
+async fn the_platform_can_turn_the_window_off() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    set_donation_limits(&mut env, 0, 2, 60).await;
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+
+    env.donate(&campaign, &donor, MIN_DONATION).await.unwrap();
+    env.donate(&campaign, &donor, MIN_DONATION).await.unwrap();
+    let err = instruction_error(env.donate(&campaign, &donor, MIN_DONATION).await);
+    assert_eq!(err, fund_error(FundError::DonationVelocityExceeded));
+
+    set_donation_limits(&mut env, 0, 0, 0).await;
+    env.donate(&campaign, &donor, MIN_DONATION).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 3 * MIN_DONATION);
+}
+
+#[tokio::test]
+async fn two_donors_in_the_same_slot() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    let alice = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let bob = env.new_wallet(LAMPORTS_PER_SOL).await;
+
+    // Both in one transaction, so they land in the same slot, the second receipt after the first.
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let first = instruction::donate_direct(
+        &env.program_id,
+        &campaign,
+        &alice.pubkey(),
+        &env.treasury.pubkey(),
+        sequence,
+        LAMPORTS_PER_SOL / 4,
+        false,
+        0,
+        0,
+    );
+    let second = instruction::donate_direct(
+        &env.program_id,
+        &campaign,
+        &bob.pubkey(),
+        &env.treasury.pubkey(),
+        sequence + 1,
+        LAMPORTS_PER_SOL / 2,
+        false,
+        0,
+        0,
+    );
+    env.process(&[first, second], &[&alice, &bob]).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 3 * LAMPORTS_PER_SOL / 4);
+    assert_eq!(campaign_data.donor_count, 2);
+    assert_eq!(campaign_data.donation_sequence, sequence + 2);
+    for (donor, amount) in [(&alice, LAMPORTS_PER_SOL / 4), (&bob, LAMPORTS_PER_SOL / 2)].iter() {
+        let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+        let record: DonationRecord = env.borsh_account(&record).await;
+        assert_eq!(record.campaign, campaign);
+        assert_eq!(record.donor, donor.pubkey());
+        assert_eq!(record.amount, *amount);
+    }
+}
+
+// A campaign account the client created but create_campaign never filled takes no donation.
+#[tokio::test]
+async fn zeroed_campaign_account_is_rejected() {
+    let mut env = start().await;
+    let campaign = Keypair::new();
+    let create_account = system_instruction::create_account(
+        &env.payer().pubkey(),
+        &campaign.pubkey(),
+        env.campaign_min_balance().await,
+        CAMPAIGN_SPACE as u64,
+        &env.program_id,
+    );
+    env.process(&[create_account], &[&campaign]).await.unwrap();
+    let balance = env.balance(&campaign.pubkey()).await;
+
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let err = instruction_error(env.donate(&campaign.pubkey(), &donor, LAMPORTS_PER_SOL / 2).await);
+    assert_eq!(err, fund_error(FundError::CampaignNotInitialized));
+    assert_eq!(env.balance(&campaign.pubkey()).await, balance);
+}
+
+// Program owned data that isn't a campaign is an error, not a panic.
+#[tokio::test]
+async fn garbage_campaign_account_is_rejected() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    // Cut short in the middle of the campaign, past the admin.
+    let mut account = env.account(&campaign).await;
+    account.data.truncate(40);
+    env.context.set_account(&campaign, &account.into());
+
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donate = instruction::donate_direct(
+        &env.program_id,
+        &campaign,
+        &donor.pubkey(),
+        &env.treasury.pubkey(),
+        sequence,
+        LAMPORTS_PER_SOL / 2,
+        false,
+        0,
+        0,
+    );
+    let err = instruction_error(env.process(&[donate], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn blocked_donor_can_donate_again_once_unblocked() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let block = instruction::block_donor(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    env.process(&[block], &[&admin]).await.unwrap();
+    let err = instruction_error(env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await);
+    assert_eq!(err, fund_error(FundError::DonorBlocked));
+    // What they gave before the block is still on their record.
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.amount, LAMPORTS_PER_SOL);
+
+    let unblock = instruction::unblock_donor(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    env.process(&[unblock], &[&admin]).await.unwrap();
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 2 * LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn pause_with_an_end_lifts_by_itself() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+
+    let pause_until = env.now().await + 3_600;
+    let pause = instruction::pause_campaign(&env.program_id, &campaign, &admin.pubkey(), true, pause_until);
+    env.process(&[pause], &[&admin]).await.unwrap();
+    let err = instruction_error(env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // Nobody unpaused it, the flag is still set, but its time is up.
+    env.warp_to_timestamp(pause_until).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert!(campaign_data.admin_paused);
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn tip_goes_to_the_treasury_on_top_of_the_donation() {
+    let mut env = start().await;
+    let campaign = open_campaign(&mut env).await;
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    let treasury = env.treasury.pubkey();
+    let campaign_before = env.balance(&campaign).await;
+    let treasury_before = env.balance(&treasury).await;
+
+    let donate = |sequence, tip_bps| {
+        let donor = donor.pubkey();
+        instruction::donate_direct(&program_id(), &campaign, &donor, &treasury, sequence, LAMPORTS_PER_SOL, false, tip_bps, 0)
+    };
+    let err = instruction_error(env.process(&[donate(0, 10_001)], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+
+    env.process(&[donate(0, 500)], &[&donor]).await.unwrap();
+    assert_eq!(env.balance(&campaign).await, campaign_before + LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&treasury).await, treasury_before + LAMPORTS_PER_SOL / 20);
+    // Only the base amount is the donation, a refund never pays the tip back.
+    assert_eq!(env.campaign(&campaign).await.amount_donated, LAMPORTS_PER_SOL);
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.amount, LAMPORTS_PER_SOL);
+    assert_eq!(env.global_stats().await.total_tips, LAMPORTS_PER_SOL / 20);
+}
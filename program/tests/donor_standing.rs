@@ -0,0 +1,75 @@
+// get_donor_standing: a donor's total, status and tier in a campaign, read from the return data.
+mod common;
+
+use common::*;
+use program::{instruction, STANDING_REFUNDABLE, TIER_LOYAL, TIER_NONE};
+use solana_program_test::tokio;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use std::convert::TryInto;
+
+struct Standing {
+    total: u64,
+    status: u8,
+    tier: u8,
+}
+
+async fn standing(env: &mut Env, campaign: &Pubkey, donor: &Keypair) -> Standing {
+    let query = instruction::get_donor_standing(&env.program_id, campaign, &donor.pubkey());
+    let simulation = env.simulate(query, &[]).await;
+    simulation.result.unwrap();
+    // The total, the status bits and the tier, there is no rank.
+    assert_eq!(simulation.return_data.len(), 10);
+    Standing {
+        total: u64::from_le_bytes(simulation.return_data[..8].try_into().unwrap()),
+        status: simulation.return_data[8],
+        tier: simulation.return_data[9],
+    }
+}
+
+#[tokio::test]
+async fn standing_after_two_donations() {
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.loyalty_min_donated = LAMPORTS_PER_SOL;
+    config.loyalty_discount_bps = 5_000;
+    env.set_config(&config).await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    let first = standing(&mut env, &campaign, &donor).await;
+    assert_eq!(first.total, LAMPORTS_PER_SOL / 2);
+    assert_eq!(first.tier, TIER_NONE);
+
+    // The second donation takes the donor past the loyalty threshold.
+    env.donate(&campaign, &donor, 3 * LAMPORTS_PER_SOL / 4).await.unwrap();
+    let second = standing(&mut env, &campaign, &donor).await;
+    assert_eq!(second.total, 5 * LAMPORTS_PER_SOL / 4);
+    assert_eq!(second.tier, TIER_LOYAL);
+    // An active campaign doesn't refund anybody.
+    assert_eq!(second.status & STANDING_REFUNDABLE, 0);
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+    let failed = standing(&mut env, &campaign, &donor).await;
+    assert_eq!(failed.total, 5 * LAMPORTS_PER_SOL / 4);
+    assert_ne!(failed.status & STANDING_REFUNDABLE, 0);
+}
+
+#[tokio::test]
+async fn donor_who_never_gave_has_no_standing() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let stranger = Keypair::new();
+    let none = standing(&mut env, &campaign, &stranger).await;
+    assert_eq!(none.total, 0);
+    assert_eq!(none.status, 0);
+    assert_eq!(none.tier, TIER_NONE);
+}
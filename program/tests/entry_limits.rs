@@ -0,0 +1,105 @@
+// Lists with a maximum length: each one is accepted at its maximum and rejected with
+// TooManyEntries one past it. The update feed has its own test in updates.rs.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignDetails, FeeTier, FundError, Milestone};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+const MAX_WITHDRAW_RECIPIENTS: usize = 8;
+const MAX_MILESTONES: usize = 8;
+const MAX_CPI_CALLERS: usize = 4;
+const MAX_FEE_TIERS: usize = 4;
+const MAX_CORRECTIONS: usize = 4;
+
+fn recipients(count: usize) -> Vec<Pubkey> {
+    (0..count).map(|_| Pubkey::new_unique()).collect()
+}
+
+fn milestones(count: usize) -> Vec<Milestone> {
+    (1..=count as u64).map(|i| Milestone { amount: i * LAMPORTS_PER_SOL, released: false }).collect()
+}
+
+fn fee_tiers(count: usize) -> Vec<FeeTier> {
+    (1..=count as u64).map(|i| FeeTier { up_to_amount: i * LAMPORTS_PER_SOL, fee_bps: 100 }).collect()
+}
+
+#[tokio::test]
+async fn campaign_lists_at_and_past_their_limit() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let with_recipients =
+        |count| CampaignDetails { withdraw_recipients: recipients(count), ..campaign_settings(&admin, LAMPORTS_PER_SOL, 0) };
+    let with_milestones = |count| CampaignDetails {
+        milestones: milestones(count),
+        ..campaign_settings(&admin, MAX_MILESTONES as u64 * LAMPORTS_PER_SOL, 0)
+    };
+    let cases = [
+        (with_recipients(MAX_WITHDRAW_RECIPIENTS), with_recipients(MAX_WITHDRAW_RECIPIENTS + 1)),
+        (with_milestones(MAX_MILESTONES), with_milestones(MAX_MILESTONES + 1)),
+    ];
+
+    for (at_limit, over) in cases {
+        let validate = instruction::validate_create(&env.program_id, at_limit, "");
+        env.process(&[validate], &[]).await.unwrap();
+        let validate = instruction::validate_create(&env.program_id, over, "");
+        let err = instruction_error(env.process(&[validate], &[]).await);
+        assert_eq!(err, fund_error(FundError::TooManyEntries));
+    }
+}
+
+#[tokio::test]
+async fn cpi_callers_at_and_past_their_limit() {
+    let mut env = start().await;
+    let authority = env.authority.insecure_clone();
+    let set_callers = |count| instruction::set_cpi_callers(&program_id(), &authority.pubkey(), recipients(count));
+
+    let err = instruction_error(env.process(&[set_callers(MAX_CPI_CALLERS + 1)], &[&authority]).await);
+    assert_eq!(err, fund_error(FundError::TooManyEntries));
+    env.process(&[set_callers(MAX_CPI_CALLERS)], &[&authority]).await.unwrap();
+    assert_eq!(env.config().await.cpi_callers.len(), MAX_CPI_CALLERS);
+}
+
+#[tokio::test]
+async fn corrections_at_and_past_their_limit() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let authority = env.authority.insecure_clone();
+    let correct = |amount| instruction::correct_accounting(&program_id(), &campaign, &authority.pubkey(), amount, 1);
+
+    for i in 1..=MAX_CORRECTIONS as u64 {
+        env.process(&[correct(i * LAMPORTS_PER_SOL)], &[&authority]).await.unwrap();
+    }
+    assert_eq!(env.campaign(&campaign).await.corrections.len(), MAX_CORRECTIONS);
+    let err = instruction_error(env.process(&[correct(0)], &[&authority]).await);
+    assert_eq!(err, fund_error(FundError::TooManyEntries));
+}
+
+// The tiers are only set by initialize_config, so this needs a bank without a config.
+#[tokio::test]
+async fn fee_tiers_at_and_past_their_limit() {
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    let init = |count| -> Instruction {
+        let payer = payer.pubkey();
+        instruction::initialize_config(&program_id(), &payer, &payer, &payer, 0, 0, 0, false, 0, fee_tiers(count))
+    };
+
+    for (count, expected) in [
+        (MAX_FEE_TIERS + 1, Err(TransactionError::InstructionError(0, fund_error(FundError::TooManyEntries)))),
+        (MAX_FEE_TIERS, Ok(())),
+    ] {
+        let blockhash = context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(&[init(count)], Some(&payer.pubkey()), &[&payer], blockhash);
+        let outcome = context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+        assert_eq!(outcome.result, expected, "{} tiers", count);
+    }
+}
+
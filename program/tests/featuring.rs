@@ -0,0 +1,62 @@
+// Featuring a campaign: the admin pays the treasury by the day and the campaign stays featured
+// until `featured_until`, after which a new purchase starts again from the current time.
+mod common;
+
+use common::*;
+use program::instruction;
+use solana_program_test::tokio;
+use solana_sdk::{instruction::InstructionError, signature::Signer};
+
+const DAY: u64 = 86_400;
+const FEE_PER_DAY: u64 = LAMPORTS_PER_SOL / 10;
+
+async fn featurable_env() -> Env {
+    let mut env = start().await;
+    let authority = env.authority.insecure_clone();
+    let fee = instruction::set_feature_fee(&env.program_id, &authority.pubkey(), FEE_PER_DAY);
+    env.process(&[fee], &[&authority]).await.unwrap();
+    env
+}
+
+#[tokio::test]
+async fn fee_is_charged_and_the_flag_expires() {
+    let mut env = featurable_env().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let treasury = env.treasury.pubkey();
+    let treasury_before = env.balance(&treasury).await;
+
+    let now = env.now().await;
+    let feature = instruction::feature_campaign(&env.program_id, &campaign, &admin.pubkey(), &treasury, 2 * DAY);
+    env.process(&[feature], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&treasury).await, treasury_before + 2 * FEE_PER_DAY);
+    let featured_until = env.campaign(&campaign).await.featured_until;
+    assert_eq!(featured_until, now + 2 * DAY as i64);
+
+    // Past featured_until the flag is gone, featuring again starts from now instead of extending it.
+    env.warp_to_timestamp(featured_until + 100).await;
+    assert!(env.campaign(&campaign).await.featured_until < env.now().await);
+    let feature = instruction::feature_campaign(&env.program_id, &campaign, &admin.pubkey(), &treasury, DAY);
+    env.process(&[feature], &[&admin]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.featured_until, featured_until + 100 + DAY as i64);
+    assert_eq!(env.balance(&treasury).await, treasury_before + 3 * FEE_PER_DAY);
+}
+
+#[tokio::test]
+async fn duration_over_the_limit_is_rejected_before_wrapping() {
+    let mut env = featurable_env().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let treasury = env.treasury.pubkey();
+    let feature = instruction::feature_campaign(&env.program_id, &campaign, &admin.pubkey(), &treasury, DAY);
+    env.process(&[feature], &[&admin]).await.unwrap();
+    let featured_until = env.campaign(&campaign).await.featured_until;
+
+    // Cast to i64 these would be negative and move featured_until back.
+    for duration in [u64::MAX, i64::MAX as u64 + 1, 31 * DAY] {
+        let feature = instruction::feature_campaign(&env.program_id, &campaign, &admin.pubkey(), &treasury, duration);
+        let err = instruction_error(env.process(&[feature], &[&admin]).await);
+        assert_eq!(err, InstructionError::InvalidInstructionData);
+    }
+    assert_eq!(env.campaign(&campaign).await.featured_until, featured_until);
+}
@@ -0,0 +1,95 @@
+// Withdraw fees: the treasury's cut of a normal campaign, none for a fee exempt one, and the
+// marginal fee tiers, and a treasury too poor to be rent exempt gets nothing.
+mod common;
+
+use common::*;
+use program::{instruction, FeeTier, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+async fn with_fee_bps(fee_bps: u16) -> Env {
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.fee_bps = fee_bps;
+    env.set_config(&config).await;
+    env
+}
+
+async fn funded_campaign(env: &mut Env, donated: u64) -> (Keypair, Pubkey) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 0, 0)).await.unwrap();
+    let donor = env.new_wallet(donated + LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, donated).await.unwrap();
+    (admin, campaign)
+}
+
+// Withdraws `amount` and returns what the treasury got out of it.
+async fn treasury_cut(env: &mut Env, admin: &Keypair, campaign: &Pubkey, amount: u64) -> u64 {
+    let treasury = env.treasury.pubkey();
+    let before = env.balance(&treasury).await;
+    let withdraw = instruction::withdraw(&env.program_id, campaign, &admin.pubkey(), &treasury, amount);
+    env.process(&[withdraw], &[admin]).await.unwrap();
+    env.balance(&treasury).await - before
+}
+
+#[tokio::test]
+async fn fee_exempt_campaigns_pay_no_fee() {
+    let mut env = with_fee_bps(500).await;
+    let (admin, normal) = funded_campaign(&mut env, LAMPORTS_PER_SOL).await;
+    assert_eq!(treasury_cut(&mut env, &admin, &normal, LAMPORTS_PER_SOL).await, LAMPORTS_PER_SOL / 20);
+
+    let (admin, exempt) = funded_campaign(&mut env, LAMPORTS_PER_SOL).await;
+    // The admin can't exempt their own campaign, only the platform authority can.
+    let exempt_itself = instruction::set_fee_exempt(&env.program_id, &exempt, &admin.pubkey(), true);
+    let err = instruction_error(env.process(&[exempt_itself], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert!(!env.campaign(&exempt).await.fee_exempt);
+
+    let authority = env.authority.insecure_clone();
+    let set_exempt = instruction::set_fee_exempt(&env.program_id, &exempt, &authority.pubkey(), true);
+    env.process(&[set_exempt], &[&authority]).await.unwrap();
+    assert_eq!(treasury_cut(&mut env, &admin, &exempt, LAMPORTS_PER_SOL).await, 0);
+}
+
+#[tokio::test]
+async fn one_withdraw_spans_three_tiers() {
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.fee_tiers = vec![
+        FeeTier { up_to_amount: LAMPORTS_PER_SOL, fee_bps: 500 },
+        FeeTier { up_to_amount: 2 * LAMPORTS_PER_SOL, fee_bps: 200 },
+        FeeTier { up_to_amount: 3 * LAMPORTS_PER_SOL, fee_bps: 100 },
+    ];
+    env.set_config(&config).await;
+    let (admin, campaign) = funded_campaign(&mut env, 3 * LAMPORTS_PER_SOL).await;
+
+    assert_eq!(treasury_cut(&mut env, &admin, &campaign, LAMPORTS_PER_SOL / 2).await, LAMPORTS_PER_SOL / 40);
+    // From 0.5 to 2.5 SOL: half a SOL at 5%, one at 2% and another half at 1%.
+    let fee = LAMPORTS_PER_SOL / 40 + LAMPORTS_PER_SOL / 50 + LAMPORTS_PER_SOL / 200;
+    assert_eq!(treasury_cut(&mut env, &admin, &campaign, 2 * LAMPORTS_PER_SOL).await, fee);
+    assert_eq!(env.campaign(&campaign).await.fee_assessed_volume, 5 * LAMPORTS_PER_SOL / 2);
+}
+
+#[tokio::test]
+async fn treasury_below_rent_exemption_is_rejected() {
+    let mut env = with_fee_bps(500).await;
+    let (admin, campaign) = funded_campaign(&mut env, LAMPORTS_PER_SOL).await;
+    let treasury = Pubkey::new_unique();
+    let mut config = env.config().await;
+    config.treasury = treasury;
+    env.set_config(&config).await;
+
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::FeeCollectorNotRentExempt));
+    assert_eq!(env.campaign(&campaign).await.fee_assessed_volume, 0);
+
+    let rent_exempt = env.rent().await.minimum_balance(0);
+    env.airdrop(&treasury, rent_exempt).await;
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&treasury).await, rent_exempt + LAMPORTS_PER_SOL / 40);
+}
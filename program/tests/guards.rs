@@ -0,0 +1,112 @@
+// The caller guard of withdraw_many and donate_split: another program invoking them is rejected
+// unless it is on the config's `cpi_callers`, while unguarded instructions like donate_direct
+// can be invoked by anyone.
+mod common;
+
+use common::*;
+use program::instruction;
+use solana_program_test::{processor, tokio};
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program::invoke,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+fn relay_id() -> Pubkey {
+    Pubkey::new_from_array([0x7e; 32])
+}
+
+// A program passing its instruction on to the program in its first account, with the rest of
+// its accounts and the same signers.
+fn relay(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (target, inner_accounts) = accounts.split_first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let metas = inner_accounts
+        .iter()
+        .map(|account| AccountMeta { pubkey: *account.key, is_signer: account.is_signer, is_writable: account.is_writable })
+        .collect();
+    invoke(&Instruction { program_id: *target.key, accounts: metas, data: data.to_vec() }, accounts)
+}
+
+// `inner` sent through the relay.
+fn relayed(inner: Instruction) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(inner.program_id, false)];
+    accounts.extend(inner.accounts);
+    Instruction { program_id: relay_id(), accounts, data: inner.data }
+}
+
+async fn start_with_relay() -> Env {
+    let mut program_test = program_test();
+    program_test.add_program("relay", relay_id(), processor!(relay));
+    start_with(program_test).await
+}
+
+async fn allow_relay(env: &mut Env) {
+    let authority = env.authority.insecure_clone();
+    let allow = instruction::set_cpi_callers(&env.program_id, &authority.pubkey(), vec![relay_id()]);
+    env.process(&[allow], &[&authority]).await.unwrap();
+}
+
+async fn donated_campaign(env: &mut Env) -> (Keypair, Pubkey) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    (admin, campaign)
+}
+
+#[tokio::test]
+async fn withdraw_many_only_runs_for_allowed_callers() {
+    let mut env = start_with_relay().await;
+    let (admin, campaign) = donated_campaign(&mut env).await;
+    let treasury = env.treasury.pubkey();
+    let withdraw_all = || instruction::withdraw_many(&program_id(), &admin.pubkey(), &treasury, &[campaign], vec![0], true);
+    let admin_before = env.balance(&admin.pubkey()).await;
+
+    let err = instruction_error(env.process(&[relayed(withdraw_all())], &[&admin]).await);
+    assert_eq!(err, InstructionError::IncorrectProgramId);
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before);
+
+    allow_relay(&mut env).await;
+    env.process(&[relayed(withdraw_all())], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn donate_split_only_runs_for_allowed_callers() {
+    let mut env = start_with_relay().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let split = instruction::donate_split(&env.program_id, &donor.pubkey(), &[(campaign, sequence, LAMPORTS_PER_SOL)]);
+
+    let err = instruction_error(env.process(&[relayed(split.clone())], &[&donor]).await);
+    assert_eq!(err, InstructionError::IncorrectProgramId);
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+
+    // Top level it always runs, and the relay does once it is allowed.
+    env.process(&[split], &[&donor]).await.unwrap();
+    allow_relay(&mut env).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let split = instruction::donate_split(&env.program_id, &donor.pubkey(), &[(campaign, sequence, LAMPORTS_PER_SOL / 2)]);
+    env.process(&[relayed(split)], &[&donor]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, LAMPORTS_PER_SOL + LAMPORTS_PER_SOL / 2);
+}
+
+#[tokio::test]
+async fn unguarded_donate_direct_runs_for_any_caller() {
+    let mut env = start_with_relay().await;
+    let (_, campaign) = donated_campaign(&mut env).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let treasury = env.treasury.pubkey();
+    let donate =
+        instruction::donate_direct(&env.program_id, &campaign, &donor.pubkey(), &treasury, sequence, LAMPORTS_PER_SOL, false, 0, 0);
+
+    env.process(&[relayed(donate)], &[&donor]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 2 * LAMPORTS_PER_SOL);
+}
@@ -0,0 +1,140 @@
+// The insurance pool: its slice of every withdraw fee, and the platform authority paying donors of
+// liquidated campaigns out of it.
+mod common;
+
+use common::*;
+use program::{
+    instruction,
+    seeds::{find_donation_record_address, find_insurance_pool_address},
+    DonationRecord, InsurancePool,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+async fn insurance_pool(env: &mut Env) -> InsurancePool {
+    let (pool, _) = find_insurance_pool_address(&env.program_id);
+    env.borsh_account(&pool).await
+}
+
+// A campaign with one donation of `donated` from `donor`.
+async fn donated_campaign(env: &mut Env, donor: &Keypair, donated: u64) -> (Keypair, Pubkey) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 0, 0)).await.unwrap();
+    env.donate(&campaign, donor, donated).await.unwrap();
+    (admin, campaign)
+}
+
+// Liquidated by the dispute resolver, and a pool holding `pool` lamports to pay claims from.
+async fn liquidated_campaign(env: &mut Env, donor: &Keypair, donated: u64, pool: u64) -> Pubkey {
+    let (_, campaign) = donated_campaign(env, donor, donated).await;
+    let resolver = env.authority.insecure_clone();
+    let dispute = instruction::open_dispute(&env.program_id, &campaign, &resolver.pubkey(), true);
+    let liquidate = instruction::liquidate_campaign(&env.program_id, &campaign, &resolver.pubkey());
+    env.process(&[dispute, liquidate], &[&resolver]).await.unwrap();
+    let (pool_address, _) = find_insurance_pool_address(&env.program_id);
+    env.airdrop(&pool_address, pool).await;
+    campaign
+}
+
+fn claim(env: &Env, campaign: &Pubkey, donor: &Keypair, amount: u64) -> Instruction {
+    instruction::pay_insurance_claim(&env.program_id, &env.authority.pubkey(), campaign, &donor.pubkey(), amount)
+}
+
+#[tokio::test]
+async fn withdraw_fee_is_split_with_the_pool() {
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.fee_bps = 500;
+    config.insurance_bps = 2_000;
+    env.set_config(&config).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let (admin, campaign) = donated_campaign(&mut env, &donor, LAMPORTS_PER_SOL).await;
+    let treasury = env.treasury.pubkey();
+    let (pool, _) = find_insurance_pool_address(&env.program_id);
+    let treasury_before = env.balance(&treasury).await;
+    let pool_before = env.balance(&pool).await;
+    let admin_before = env.balance(&admin.pubkey()).await;
+
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+
+    // 5% fee, a fifth of it to the pool.
+    let fee = LAMPORTS_PER_SOL / 20;
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + LAMPORTS_PER_SOL - fee);
+    assert_eq!(env.balance(&pool).await, pool_before + fee / 5);
+    assert_eq!(env.balance(&treasury).await, treasury_before + fee - fee / 5);
+    assert_eq!(insurance_pool(&mut env).await.total_in, fee / 5);
+}
+
+#[tokio::test]
+async fn donor_of_a_liquidated_campaign_is_paid() {
+    let mut env = start().await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let campaign = liquidated_campaign(&mut env, &donor, LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL).await;
+    let donor_before = env.balance(&donor.pubkey()).await;
+
+    let authority = env.authority.insecure_clone();
+    let pay = claim(&env, &campaign, &donor, LAMPORTS_PER_SOL / 2);
+    env.process(&[pay], &[&authority]).await.unwrap();
+
+    assert_eq!(env.balance(&donor.pubkey()).await, donor_before + LAMPORTS_PER_SOL / 2);
+    assert_eq!(insurance_pool(&mut env).await.total_paid, LAMPORTS_PER_SOL / 2);
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.insurance_paid, LAMPORTS_PER_SOL / 2);
+}
+
+#[tokio::test]
+async fn claim_over_the_unrefunded_donation_is_rejected() {
+    let mut env = start().await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let campaign = liquidated_campaign(&mut env, &donor, LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL).await;
+    let authority = env.authority.insecure_clone();
+
+    let pay = claim(&env, &campaign, &donor, LAMPORTS_PER_SOL + 1);
+    let err = instruction_error(env.process(&[pay], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+
+    // What was already paid counts against the donation.
+    let pay = claim(&env, &campaign, &donor, 3 * LAMPORTS_PER_SOL / 4);
+    env.process(&[pay], &[&authority]).await.unwrap();
+    let pay = claim(&env, &campaign, &donor, LAMPORTS_PER_SOL / 2);
+    let err = instruction_error(env.process(&[pay], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+    assert_eq!(insurance_pool(&mut env).await.total_paid, 3 * LAMPORTS_PER_SOL / 4);
+}
+
+#[tokio::test]
+async fn healthy_campaign_isnt_covered() {
+    let mut env = start().await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let (_, campaign) = donated_campaign(&mut env, &donor, LAMPORTS_PER_SOL).await;
+    let (pool, _) = find_insurance_pool_address(&env.program_id);
+    env.airdrop(&pool, LAMPORTS_PER_SOL).await;
+    let authority = env.authority.insecure_clone();
+
+    let pay = claim(&env, &campaign, &donor, LAMPORTS_PER_SOL / 2);
+    let err = instruction_error(env.process(&[pay], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert_eq!(insurance_pool(&mut env).await.total_paid, 0);
+}
+
+#[tokio::test]
+async fn record_of_another_donor_is_rejected() {
+    let mut env = start().await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let campaign = liquidated_campaign(&mut env, &donor, LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL).await;
+    let authority = env.authority.insecure_clone();
+
+    // The donor's record with someone else to pay.
+    let stranger = Keypair::new();
+    let mut pay = claim(&env, &campaign, &stranger, LAMPORTS_PER_SOL / 2);
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    pay.accounts[4].pubkey = record;
+    let err = instruction_error(env.process(&[pay], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidSeeds);
+}
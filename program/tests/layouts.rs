@@ -0,0 +1,205 @@
+// The account layouts pinned by the blobs in tests/fixtures, see the README there for what each
+// blob holds. These don't need a bank, they only read the bytes.
+//
+// The snapshots serialize the values described in the README and compare the bytes with the
+// blob, a field that moves or changes size fails them. On a layout change on purpose the blob is
+// regenerated, see the README.
+use borsh::{BorshDeserialize, BorshSerialize};
+use program::{
+    campaign_counters, campaign_counters_mut, load_campaign, load_campaigns, CampaignCounters, CampaignDetails, CampaignState,
+    DonationRecord, FeeTier, PlatformConfig, Rounding, WithdrawPolicy, SOL_CURRENCY,
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(&path).unwrap_or_else(|err| panic!("can't read {}: {}", path, err))
+}
+
+#[test]
+fn v1_campaign_migrates_to_the_current_layout() {
+    let campaign = load_campaign(&fixture("campaign_v1.bin")).unwrap();
+    assert_eq!(campaign.admin, Pubkey::new_from_array([7; 32]));
+    assert_eq!(campaign.name, "Clean water");
+    assert_eq!(campaign.name_hash, program::campaign_name_hash("Clean water"));
+    assert_eq!(campaign.amount_donated, 1_500_000_000);
+    assert_eq!(campaign.currency, SOL_CURRENCY);
+    // Everything v1 didn't have reads as unset: active, no goal, no end, no limits.
+    assert_eq!(campaign.state, CampaignState::Active);
+    assert_eq!(campaign.goal, 0);
+    assert_eq!(campaign.end_timestamp, 0);
+    assert_eq!(campaign.hard_cap, 0);
+    assert_eq!(campaign.max_withdraw_per_epoch, 0);
+    assert_eq!(campaign.withdraw_policy, WithdrawPolicy::default());
+    // The description moves to a content PDA with `migrate_campaign_content`.
+    assert_eq!(campaign.content, Pubkey::default());
+    assert!(campaign.withdraw_recipients.is_empty());
+    assert!(campaign.previous_cycles.is_empty());
+}
+
+#[test]
+fn v1_campaign_with_trailing_bytes_isnt_a_campaign() {
+    let mut data = fixture("campaign_v1.bin");
+    data.extend_from_slice(&[0; 16]);
+    assert!(load_campaign(&data).is_err());
+}
+
+#[test]
+fn current_campaign_loads_as_is() {
+    let campaign = load_campaign(&fixture("campaign_current.bin")).unwrap();
+    assert_eq!(campaign.admin, Pubkey::new_from_array([7; 32]));
+    assert_eq!(campaign.name, "Clean water");
+    assert_eq!(campaign.amount_donated, 1_500_000_000);
+    assert_eq!(campaign.goal, 2_000_000_000);
+    assert_eq!(campaign.end_timestamp, 1_700_000_000);
+    assert_eq!(campaign.cycle, 1);
+    assert_eq!(campaign.soft_goal, 1_000_000_000);
+    assert_eq!(campaign.withdraw_policy, WithdrawPolicy::GoalOnly);
+    assert_eq!(campaign.hard_cap, 3_000_000_000);
+    assert!(campaign.fill_to_cap);
+    assert_eq!(campaign.content, Pubkey::new_from_array([6; 32]));
+    assert_eq!(campaign.withdraw_recipients, vec![Pubkey::new_from_array([8; 32])]);
+    assert_eq!(campaign.withdraw_delay_secs, 86_400);
+    assert_eq!(campaign.withdraw_queue.len(), 1);
+    assert_eq!(campaign.withdraw_queue[0].amount, 250_000_000);
+    assert_eq!(campaign.previous_cycles.len(), 1);
+}
+
+#[test]
+fn load_campaigns_skips_what_doesnt_parse() {
+    let keys: Vec<Pubkey> = (1..=5).map(|i| Pubkey::new_from_array([i; 32])).collect();
+    let mut v1_with_trailing_bytes = fixture("campaign_v1.bin");
+    v1_with_trailing_bytes.extend_from_slice(&[0; 16]);
+    let accounts = vec![
+        (keys[0], fixture("campaign_current.bin")),
+        (keys[1], v1_with_trailing_bytes),
+        (keys[2], fixture("campaign_v1.bin")),
+        (keys[3], Vec::new()),
+        (keys[4], vec![0xff; 64]),
+    ];
+
+    let campaigns = load_campaigns(&accounts);
+    let loaded: Vec<Pubkey> = campaigns.iter().map(|(key, _)| *key).collect();
+    assert_eq!(loaded, [keys[0], keys[2]]);
+    assert!(campaigns.iter().all(|(_, campaign)| campaign.name == "Clean water"));
+}
+
+#[test]
+fn donation_record_layout_snapshot() {
+    let record = DonationRecord {
+        campaign: Pubkey::new_from_array([1; 32]),
+        donor: Pubkey::new_from_array([2; 32]),
+        cycle: 1,
+        amount: 400_000_000,
+        refunded: true,
+        insurance_paid: 50_000_000,
+        refund_paid: 350_000_000,
+        non_refundable: false,
+    };
+    let blob = fixture("donation_record.bin");
+    assert_eq!(record.try_to_vec().unwrap(), blob, "DonationRecord no longer matches donation_record.bin");
+    assert_eq!(blob.len(), 94);
+
+    let read = DonationRecord::try_from_slice(&blob).unwrap();
+    assert_eq!(read.donor, record.donor);
+    assert_eq!(read.refund_paid, 350_000_000);
+}
+
+#[test]
+fn platform_config_layout_snapshot() {
+    let config = PlatformConfig {
+        authority: Pubkey::new_from_array([3; 32]),
+        dispute_resolver: Pubkey::new_from_array([4; 32]),
+        treasury: Pubkey::new_from_array([5; 32]),
+        fee_bps: 250,
+        insurance_bps: 2_000,
+        creation_fee: 10_000_000,
+        refund_fees: true,
+        refund_window_secs: 30 * 24 * 60 * 60,
+        legacy_donate_disabled: true,
+        fee_tiers: vec![
+            FeeTier { up_to_amount: 10_000_000_000, fee_bps: 250 },
+            FeeTier { up_to_amount: 100_000_000_000, fee_bps: 100 },
+        ],
+        cpi_callers: vec![Pubkey::new_from_array([6; 32])],
+        sunset: false,
+        rounding: Rounding::Nearest,
+        suggested_tip_bps: 300,
+        loyalty_min_donated: 10_000_000_000,
+        loyalty_discount_bps: 5_000,
+        feature_fee_per_day: 100_000_000,
+        min_goal_lamports: 1_000_000_000,
+        min_donation_lamports: 10_000_000,
+        max_donations_per_window: 50,
+        donation_window_secs: 60,
+    };
+    let blob = fixture("platform_config.bin");
+    assert_eq!(config.try_to_vec().unwrap(), blob, "PlatformConfig no longer matches platform_config.bin");
+    assert_eq!(blob.len(), 228);
+
+    let read = PlatformConfig::try_from_slice(&blob).unwrap();
+    assert_eq!(read.treasury, config.treasury);
+    assert_eq!(read.rounding, Rounding::Nearest);
+    assert_eq!(read.min_goal_lamports, 1_000_000_000);
+    assert_eq!(read.donation_window_secs, 60);
+}
+
+// Campaigns with names and links of every length, the counter block moves with them.
+fn campaigns() -> Vec<CampaignDetails> {
+    (0..40)
+        .map(|i| CampaignDetails {
+            admin: Pubkey::new_from_array([i as u8; 32]),
+            name: "n".repeat(i),
+            image_link: "l".repeat(i * 3),
+            amount_donated: 1_000_003 * i as u64,
+            disputed: i % 3 == 0,
+            donations_paused: i % 4 == 0,
+            goal: u64::MAX - i as u64,
+            end_timestamp: -(i as i64),
+            state: [CampaignState::Active, CampaignState::Successful, CampaignState::Failed][i % 3],
+            donor_count: u32::MAX - i as u32,
+            withdraw_recipients: vec![Pubkey::new_unique(); i % 3],
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[test]
+fn counters_read_what_borsh_reads() {
+    let mut blobs = vec![fixture("campaign_current.bin")];
+    blobs.extend(campaigns().iter().map(|campaign| campaign.try_to_vec().unwrap()));
+    for data in blobs {
+        let campaign = CampaignDetails::deserialize(&mut &data[..]).unwrap();
+        let counters = campaign_counters(&data).unwrap();
+        assert_eq!(bytemuck::bytes_of(counters), bytemuck::bytes_of(&CampaignCounters::from(&campaign)));
+    }
+}
+
+#[test]
+fn counters_write_what_borsh_reads_back() {
+    for campaign in campaigns() {
+        let mut data = campaign.try_to_vec().unwrap();
+        let counters = campaign_counters_mut(&mut data).unwrap();
+        counters.amount_donated = 42;
+        counters.donor_count = 7;
+        counters.state = CampaignState::Failed as u8;
+
+        let mut expected = campaign;
+        expected.amount_donated = 42;
+        expected.donor_count = 7;
+        expected.state = CampaignState::Failed;
+        // Nothing around the block moved.
+        assert_eq!(data, expected.try_to_vec().unwrap());
+    }
+}
+
+#[test]
+fn counters_of_a_short_account_are_an_error() {
+    // A 5 byte name and a 15 byte link, the block starts after admin, name_hash, campaign_id and them.
+    let data = campaigns()[5].try_to_vec().unwrap();
+    let start = 32 + 32 + 8 + 4 + 5 + 4 + 15;
+    assert!(campaign_counters(&data[..start + program::CAMPAIGN_COUNTERS_LEN]).is_ok());
+    assert!(campaign_counters(&data[..start + 10]).is_err());
+    assert!(campaign_counters(&data[..40]).is_err());
+    assert!(campaign_counters(&[]).is_err());
+}
@@ -0,0 +1,156 @@
+// The staging account donate path: off in a new config, and when the authority turns it back on
+// it only drains a staging account created from the donor's own wallet.
+mod common;
+
+use common::*;
+use program::{instruction, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+const STAGED: u64 = LAMPORTS_PER_SOL;
+
+// Creates a staging account from `donor`'s wallet and `seed`, holding STAGED lamports.
+async fn stage(env: &mut Env, donor: &Keypair, seed: &str) -> Pubkey {
+    let staging = Pubkey::create_with_seed(&donor.pubkey(), seed, &env.program_id).unwrap();
+    let create = system_instruction::create_account_with_seed(
+        &donor.pubkey(),
+        &staging,
+        &donor.pubkey(),
+        seed,
+        STAGED,
+        1,
+        &env.program_id,
+    );
+    env.process(&[create], &[donor]).await.unwrap();
+    staging
+}
+
+async fn enable_legacy_donate(env: &mut Env) {
+    let authority = env.authority.insecure_clone();
+    let enable = instruction::set_legacy_donate_disabled(&env.program_id, &authority.pubkey(), false);
+    env.process(&[enable], &[&authority]).await.unwrap();
+}
+
+async fn setup() -> (Env, Pubkey, Keypair) {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 0, 0)).await.unwrap();
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    (env, campaign, donor)
+}
+
+#[tokio::test]
+async fn disabled_in_a_new_config() {
+    let (mut env, campaign, donor) = setup().await;
+    assert!(env.config().await.legacy_donate_disabled);
+
+    let staging = stage(&mut env, &donor, "seed").await;
+    let donate = instruction::donate(&env.program_id, &campaign, &staging, &donor.pubkey(), 0, STAGED, true, "seed");
+    let err = instruction_error(env.process(&[donate], &[&donor]).await);
+    assert_eq!(err, fund_error(FundError::LegacyPathDisabled));
+    assert_eq!(env.balance(&staging).await, STAGED);
+}
+
+#[tokio::test]
+async fn enabled_donates_the_staged_lamports() {
+    let (mut env, campaign, donor) = setup().await;
+    enable_legacy_donate(&mut env).await;
+    let staging = stage(&mut env, &donor, "seed").await;
+    let before = env.balance(&campaign).await;
+
+    let donate = instruction::donate(&env.program_id, &campaign, &staging, &donor.pubkey(), 0, STAGED / 4, true, "seed");
+    env.process(&[donate], &[&donor]).await.unwrap();
+
+    assert_eq!(env.balance(&campaign).await, before + STAGED / 4);
+    assert_eq!(env.campaign(&campaign).await.amount_donated, STAGED / 4);
+    // Closing the staging account gave the rest back to the donor.
+    assert!(env.context.banks_client.get_account(staging).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn rejects_a_foreign_campaign_as_the_staging_account() {
+    let (mut env, campaign, attacker) = setup().await;
+    enable_legacy_donate(&mut env).await;
+    // The victim campaign holds donations, it is owned by the program like a staging account.
+    let victim_admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let victim = env.create_campaign(&victim_admin, campaign_settings(&victim_admin, 0, 0)).await.unwrap();
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    env.donate(&victim, &donor, 2 * LAMPORTS_PER_SOL).await.unwrap();
+    let victim_balance = env.balance(&victim).await;
+
+    let donate = instruction::donate(&env.program_id, &campaign, &victim, &attacker.pubkey(), 0, LAMPORTS_PER_SOL, true, "");
+    let err = instruction_error(env.process(&[donate], &[&attacker]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert_eq!(env.balance(&victim).await, victim_balance);
+}
+
+#[tokio::test]
+async fn rejects_another_donors_staging_account() {
+    let (mut env, campaign, attacker) = setup().await;
+    enable_legacy_donate(&mut env).await;
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    let staging = stage(&mut env, &donor, "seed").await;
+
+    let donate = instruction::donate(&env.program_id, &campaign, &staging, &attacker.pubkey(), 0, STAGED, true, "seed");
+    let err = instruction_error(env.process(&[donate], &[&attacker]).await);
+    assert_eq!(err, InstructionError::InvalidSeeds);
+    assert_eq!(env.balance(&staging).await, STAGED);
+}
+
+#[tokio::test]
+async fn rejects_the_formats_without_a_seed() {
+    let (mut env, campaign, donor) = setup().await;
+    enable_legacy_donate(&mut env).await;
+    let staging = stage(&mut env, &donor, "seed").await;
+
+    for data in [vec![2], [&[2u8][..], &STAGED.to_le_bytes()].concat()] {
+        let mut donate = instruction::donate(&env.program_id, &campaign, &staging, &donor.pubkey(), 0, STAGED, true, "seed");
+        donate.data = data;
+        let err = instruction_error(env.process(&[donate], &[&donor]).await);
+        assert_eq!(err, InstructionError::InvalidInstructionData);
+    }
+    assert_eq!(env.balance(&staging).await, STAGED);
+}
+
+#[tokio::test]
+async fn reclaim_needs_the_donors_seed() {
+    let (mut env, _, donor) = setup().await;
+    let staging = stage(&mut env, &donor, "seed").await;
+    let attacker = env.new_wallet(LAMPORTS_PER_SOL).await;
+
+    let reclaim = instruction::reclaim_staged(&env.program_id, &staging, &attacker.pubkey(), "seed");
+    let err = instruction_error(env.process(&[reclaim], &[&attacker]).await);
+    assert_eq!(err, InstructionError::InvalidSeeds);
+
+    let before = env.balance(&donor.pubkey()).await;
+    let reclaim = instruction::reclaim_staged(&env.program_id, &staging, &donor.pubkey(), "seed");
+    env.process(&[reclaim], &[&donor]).await.unwrap();
+    assert_eq!(env.balance(&donor.pubkey()).await, before + STAGED);
+}
+
+#[tokio::test]
+async fn open_staging_account_keeps_its_rent() {
+    let (mut env, campaign, donor) = setup().await;
+    enable_legacy_donate(&mut env).await;
+    let staging = stage(&mut env, &donor, "seed").await;
+    let rent_exemption = env.rent().await.minimum_balance(1);
+    let max_safe = STAGED - rent_exemption;
+
+    let donate = |sequence, amount, close_after| {
+        instruction::donate(&program_id(), &campaign, &staging, &donor.pubkey(), sequence, amount, close_after, "seed")
+    };
+    let err = instruction_error(env.process(&[donate(0, max_safe + 1, false)], &[&donor]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    env.process(&[donate(0, max_safe, false)], &[&donor]).await.unwrap();
+    assert_eq!(env.balance(&staging).await, rent_exemption);
+
+    // Closing it, the rent can be donated too.
+    env.process(&[donate(1, rent_exemption, true)], &[&donor]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, STAGED);
+    assert!(env.context.banks_client.get_account(staging).await.unwrap().is_none());
+}
@@ -0,0 +1,518 @@
+// The log schema alerts depend on, see the `logging` module: every line an instruction logs starts
+// with `0xfund:<instruction>:`, and the last one is `0xfund:<instruction>:ok` or
+// `0xfund:<instruction>:error:<code>`. Each instruction is checked failing and succeeding.
+mod common;
+
+use common::*;
+use borsh::BorshSerialize;
+use program::{instruction, logging, seeds::*, CampaignDetails, CampaignDetailsV1, CampaignState, FundError, Rounding, WithdrawPolicy};
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::Account,
+    hash::{hash, hashv},
+    instruction::Instruction,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::TransactionError,
+};
+use std::{collections::BTreeSet, convert::TryFrom};
+
+// What the program logged with `msg!`: the `Program log:` lines without the events, which the
+// native stubs log as `data: ...`, and the compute units of the `debug-logs` phases.
+fn program_logs(logs: &[String]) -> Vec<&str> {
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program log: "))
+        .filter(|log| !log.starts_with("data: ") && !log.contains("sol_log_compute_units"))
+        .collect()
+}
+
+// Every line carries the instruction's name and the last one is `outcome`.
+fn assert_logs(logs: &[String], name: &str, outcome: &str) {
+    let lines = program_logs(logs);
+    let prefix = format!("0xfund:{}:", name);
+    for line in &lines {
+        assert!(line.starts_with(&prefix), "{:?} isn't prefixed with {}: {:?}", line, prefix, logs);
+    }
+    assert_eq!(lines.last().copied(), Some(format!("{}{}", prefix, outcome).as_str()), "{:?}", logs);
+}
+
+// The code of the error line, the error as a u64 like in the error return data.
+fn error_code(result: Result<(), TransactionError>) -> u64 {
+    match result.unwrap_err() {
+        TransactionError::InstructionError(_, err) => u64::from(ProgramError::try_from(err).unwrap()),
+        err => panic!("expected an instruction error, got {:?}", err),
+    }
+}
+
+// Sends the instructions, the last one is `name`'s, and checks it logged `ok`.
+async fn assert_ok(env: &mut Env, checked: &mut BTreeSet<String>, instructions: &[Instruction], signers: &[&Keypair], name: &str) {
+    let (result, logs) = env.process_with_logs(instructions, signers).await;
+    assert_eq!(result, Ok(()), "{} failed: {:?}", name, logs);
+    assert_logs(&logs, name, "ok");
+    checked.insert(name.to_string());
+}
+
+async fn assert_fails(env: &mut Env, instruction: Instruction, signers: &[&Keypair], name: &str) -> Vec<String> {
+    let (result, logs) = env.process_with_logs(&[instruction], signers).await;
+    assert!(result.is_err(), "{} succeeded: {:?}", name, logs);
+    assert_logs(&logs, name, &format!("error:{}", error_code(result)));
+    logs
+}
+
+fn raw(data: Vec<u8>) -> Instruction {
+    Instruction { program_id: program_id(), accounts: Vec::new(), data }
+}
+
+// Every instruction without its accounts fails before doing anything, with only the error line.
+// `version` takes no accounts and can't fail.
+#[tokio::test]
+async fn every_instruction_logs_its_error() {
+    let mut env = start().await;
+    for instruction in logging::INSTRUCTIONS.iter().filter(|i| **i != logging::Instruction::Version) {
+        let (result, logs) = env.process_with_logs(&[raw(vec![instruction.tag()])], &[]).await;
+        assert_eq!(error_code(result), u64::from(ProgramError::NotEnoughAccountKeys));
+        assert_logs(&logs, instruction.name(), &format!("error:{}", u64::from(ProgramError::NotEnoughAccountKeys)));
+        assert_eq!(program_logs(&logs).len(), 1, "{:?}", logs);
+    }
+}
+
+// The helpers log with the name of the instruction that called them, and our own errors log their
+// FundError code.
+#[tokio::test]
+async fn helper_lines_carry_the_callers_name() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+
+    let stranger = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let sunset = instruction::set_sunset(&env.program_id, &stranger.pubkey(), true);
+    let logs = assert_fails(&mut env, sunset, &[&stranger], "set_sunset").await;
+    assert!(program_logs(&logs).len() > 1, "{:?}", logs);
+
+    let pause = instruction::pause_campaign(&env.program_id, &campaign, &admin.pubkey(), true, 0);
+    env.process(&[pause], &[&admin]).await.unwrap();
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donate = env.donate_instruction(&campaign, &donor, LAMPORTS_PER_SOL / 2).await;
+    let logs = assert_fails(&mut env, donate, &[&donor], "donate_direct").await;
+    assert!(program_logs(&logs).contains(&"0xfund:donate_direct:Campaign is paused by its admin until 0"), "{:?}", logs);
+
+    let resume = instruction::pause_campaign(&env.program_id, &campaign, &admin.pubkey(), false, 0);
+    env.process(&[resume], &[&admin]).await.unwrap();
+    let mut config = env.config().await;
+    config.min_donation_lamports = 10_000_000;
+    env.set_config(&config).await;
+    let dust = env.donate_instruction(&campaign, &donor, 1).await;
+    let logs = assert_fails(&mut env, dust, &[&donor], "donate_direct").await;
+    let error = format!("0xfund:donate_direct:error:{}", FundError::DonationTooSmall as u32);
+    assert_eq!(program_logs(&logs).last().copied(), Some(error.as_str()));
+}
+
+#[tokio::test]
+async fn unknown_and_empty_instructions_log_as_such() {
+    let mut env = start().await;
+    let logs = assert_fails(&mut env, raw(vec![logging::INSTRUCTIONS.len() as u8]), &[], "unknown").await;
+    assert!(program_logs(&logs).len() > 1, "{:?}", logs);
+    assert_fails(&mut env, raw(Vec::new()), &[], "empty").await;
+}
+
+// Every instruction succeeding once across the scenarios below.
+#[tokio::test]
+async fn every_instruction_logs_ok() {
+    let mut checked = BTreeSet::new();
+    platform(&mut checked).await;
+    campaign_lifecycle(&mut checked).await;
+    timelocked_withdraws(&mut checked).await;
+    donations(&mut checked).await;
+    refunds(&mut checked).await;
+    disputes(&mut checked).await;
+    sponsors_and_vouchers(&mut checked).await;
+    legacy_accounts(&mut checked).await;
+
+    let missing: Vec<_> = logging::INSTRUCTIONS
+        .iter()
+        .map(|instruction| instruction.name())
+        .filter(|name| !checked.contains(*name))
+        .collect();
+    assert!(missing.is_empty(), "never checked succeeding: {:?}", missing);
+}
+
+async fn new_campaign(env: &mut Env, admin: &Keypair, settings: CampaignDetails) -> Pubkey {
+    env.create_campaign(admin, settings).await.unwrap()
+}
+
+// The config, the settings of the platform authority and the charity allowlist.
+async fn platform(checked: &mut BTreeSet<String>) {
+    // A bank of its own, `start` already initialized the config of the others.
+    let mut context = program_test().start_with_context().await;
+    let payer = context.payer.insecure_clone();
+    let init = instruction::initialize_config(
+        &program_id(),
+        &payer.pubkey(),
+        &payer.pubkey(),
+        &payer.pubkey(),
+        0,
+        0,
+        0,
+        false,
+        0,
+        Vec::new(),
+    );
+    let blockhash = context.get_new_latest_blockhash().await.unwrap();
+    let transaction =
+        solana_sdk::transaction::Transaction::new_signed_with_payer(&[init], Some(&payer.pubkey()), &[&payer], blockhash);
+    let outcome = context.banks_client.process_transaction_with_metadata(transaction).await.unwrap();
+    assert_eq!(outcome.result, Ok(()));
+    assert_logs(&outcome.metadata.unwrap().log_messages, "initialize_config", "ok");
+    checked.insert("initialize_config".to_string());
+
+    let mut env = start().await;
+    let authority = env.authority.insecure_clone();
+    let id = env.program_id;
+    let key = authority.pubkey();
+    let settings = [
+        (instruction::set_sunset(&id, &key, true), "set_sunset"),
+        (instruction::set_sunset(&id, &key, false), "set_sunset"),
+        (instruction::set_rounding(&id, &key, Rounding::Nearest), "set_rounding"),
+        (instruction::set_tip_loyalty(&id, &key, 500, LAMPORTS_PER_SOL, 1_000), "set_tip_loyalty"),
+        (instruction::set_feature_fee(&id, &key, LAMPORTS_PER_SOL), "set_feature_fee"),
+        (instruction::set_min_goal(&id, &key, 0), "set_min_goal"),
+        (instruction::set_donation_limits(&id, &key, 0, 50, 60), "set_donation_limits"),
+        (instruction::set_cpi_callers(&id, &key, vec![Pubkey::new_unique()]), "set_cpi_callers"),
+        (instruction::set_legacy_donate_disabled(&id, &key, false), "set_legacy_donate_disabled"),
+    ];
+    for (setting, name) in settings {
+        assert_ok(&mut env, checked, &[setting], &[&authority], name).await;
+    }
+
+    let config_len = env.account(&find_config_address(&id).0).await.data.len() as u32;
+    let realloc = instruction::realloc_config(&id, &key, config_len + 8);
+    assert_ok(&mut env, checked, &[realloc], &[&authority], "realloc_config").await;
+    let stats_len = env.account(&find_global_stats_address(&id).0).await.data.len() as u32;
+    let realloc = instruction::realloc_global_stats(&id, &key, stats_len + 8);
+    assert_ok(&mut env, checked, &[realloc], &[&authority], "realloc_global_stats").await;
+
+    let charity = Pubkey::new_unique();
+    let add = instruction::add_charity(&id, &charity, &key);
+    assert_ok(&mut env, checked, &[add], &[&authority], "add_charity").await;
+    let remove = instruction::remove_charity(&id, &charity, &key);
+    assert_ok(&mut env, checked, &[remove], &[&authority], "remove_charity").await;
+
+    assert_ok(&mut env, checked, &[instruction::version(&id)], &[], "version").await;
+}
+
+// The admin editing a campaign, the read-only queries, and a successful campaign being finalized,
+// paying its fees, renewed and closed.
+async fn campaign_lifecycle(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let authority = env.authority.insecure_clone();
+    let treasury = env.treasury.pubkey();
+    let admin = env.new_wallet(10 * LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let name = campaign_settings(&admin, 0, 0).name;
+    let settings = || CampaignDetails { name: name.clone(), ..campaign_settings(&admin, LAMPORTS_PER_SOL, end) };
+
+    let validate = instruction::validate_create(&id, settings(), "A description");
+    assert_ok(&mut env, checked, &[validate], &[], "validate_create").await;
+
+    let campaign = Keypair::new();
+    let create = [
+        system_instruction::create_account(
+            &env.payer().pubkey(),
+            &campaign.pubkey(),
+            env.campaign_min_balance().await,
+            CAMPAIGN_SPACE as u64,
+            &id,
+        ),
+        instruction::create_campaign(&id, &campaign.pubkey(), &treasury, settings(), "A description"),
+    ];
+    assert_ok(&mut env, checked, &create, &[&admin, &campaign], "create_campaign").await;
+    let campaign = campaign.pubkey();
+
+    let admin_key = admin.pubkey();
+    let edits = [
+        (instruction::set_image(&id, &campaign, &admin_key, "https://example.com/image.png"), "set_image"),
+        (instruction::set_category(&id, &campaign, &admin_key, 1), "set_category"),
+        (instruction::set_goal(&id, &campaign, &admin_key, 2 * LAMPORTS_PER_SOL, WithdrawPolicy::Anytime), "set_goal"),
+        (instruction::set_soft_goal(&id, &campaign, &admin_key, LAMPORTS_PER_SOL), "set_soft_goal"),
+        (instruction::pause_campaign(&id, &campaign, &admin_key, true, 0), "pause_campaign"),
+        (instruction::pause_campaign(&id, &campaign, &admin_key, false, 0), "pause_campaign"),
+        (instruction::post_update(&id, &campaign, &admin_key, "Thanks everyone"), "post_update"),
+        (instruction::rename_campaign(&id, &campaign, &admin_key, &name, "Renamed"), "rename_campaign"),
+        (instruction::archive_campaign(&id, &campaign, &admin_key, false, true), "archive_campaign"),
+    ];
+    for (edit, name) in edits {
+        assert_ok(&mut env, checked, &[edit], &[&admin], name).await;
+    }
+    let unarchive = instruction::archive_campaign(&id, &campaign, &authority.pubkey(), true, false);
+    assert_ok(&mut env, checked, &[unarchive], &[&authority], "archive_campaign").await;
+
+    // The content was written when the campaign was created.
+    let now = env.now().await;
+    env.warp_to_timestamp(now + 3_600).await;
+    let update = instruction::update_campaign(&id, &campaign, &admin_key, "A new description");
+    assert_ok(&mut env, checked, &[update], &[&admin], "update_campaign").await;
+
+    let set_fee = instruction::set_feature_fee(&id, &authority.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[set_fee], &[&authority]).await.unwrap();
+    let feature = instruction::feature_campaign(&id, &campaign, &admin_key, &treasury, 3_600);
+    assert_ok(&mut env, checked, &[feature], &[&admin], "feature_campaign").await;
+    let exempt = instruction::set_fee_exempt(&id, &campaign, &authority.pubkey(), true);
+    assert_ok(&mut env, checked, &[exempt], &[&authority], "set_fee_exempt").await;
+
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let donate = env.donate_instruction(&campaign, &donor, 2 * LAMPORTS_PER_SOL).await;
+    assert_ok(&mut env, checked, &[donate], &[&donor], "donate_direct").await;
+
+    let queries = [
+        (instruction::health_check(&id, &campaign), "health_check"),
+        (instruction::get_withdrawable(&id, &campaign), "get_withdrawable"),
+        (instruction::get_campaign_summary(&id, &campaign), "get_campaign_summary"),
+        (instruction::get_progress(&id, &campaign), "get_progress"),
+        (instruction::get_donor_standing(&id, &campaign, &donor.pubkey()), "get_donor_standing"),
+        (instruction::snapshot_campaign(&id, &campaign), "snapshot_campaign"),
+        (instruction::confirm_donations(&id, &campaign), "confirm_donations"),
+    ];
+    for (query, name) in queries {
+        assert_ok(&mut env, checked, &[query], &[], name).await;
+    }
+
+    let correct = instruction::correct_accounting(&id, &campaign, &authority.pubkey(), 2 * LAMPORTS_PER_SOL, 1);
+    assert_ok(&mut env, checked, &[correct], &[&authority], "correct_accounting").await;
+    let withdraw = instruction::withdraw(&id, &campaign, &admin_key, &treasury, LAMPORTS_PER_SOL / 2);
+    assert_ok(&mut env, checked, &[withdraw], &[&admin], "withdraw").await;
+    let withdraw_many = instruction::withdraw_many(&id, &admin_key, &treasury, &[campaign], vec![LAMPORTS_PER_SOL / 2], true);
+    assert_ok(&mut env, checked, &[withdraw_many], &[&admin], "withdraw_many").await;
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&id, &campaign);
+    assert_ok(&mut env, checked, &[finalize], &[], "finalize_campaign").await;
+    assert_eq!(env.campaign(&campaign).await.state, CampaignState::Successful);
+    let collect = instruction::collect_fees(&id, &campaign, &treasury);
+    assert_ok(&mut env, checked, &[collect], &[], "collect_fees").await;
+    let renew = instruction::renew_campaign(&id, &campaign, &admin_key, LAMPORTS_PER_SOL, 0);
+    assert_ok(&mut env, checked, &[renew], &[&admin], "renew_campaign").await;
+
+    let name_hash = env.campaign(&campaign).await.name_hash;
+    let close = instruction::close_expired_campaigns(&id, &authority.pubkey(), &[(campaign, admin_key, name_hash)]);
+    assert_ok(&mut env, checked, &[close], &[&authority], "close_expired_campaigns").await;
+    let mut closed = env.campaign(&campaign).await;
+    closed.state = CampaignState::Closed;
+    env.set_campaign(&campaign, &closed).await;
+    let close_receipt = instruction::close_receipt(&id, &campaign, &donor.pubkey(), sequence);
+    assert_ok(&mut env, checked, &[close_receipt], &[&donor], "close_receipt").await;
+}
+
+// A timelocked campaign queueing withdraws.
+async fn timelocked_withdraws(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let treasury = env.treasury.pubkey();
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails { withdraw_delay_secs: 60, ..campaign_settings(&admin, 0, 0) };
+    let campaign = new_campaign(&mut env, &admin, settings).await;
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, 2 * LAMPORTS_PER_SOL).await.unwrap();
+
+    let admin_key = admin.pubkey();
+    for _ in 0..2 {
+        let request = instruction::request_withdraw(&id, &campaign, &admin_key, LAMPORTS_PER_SOL / 2);
+        assert_ok(&mut env, checked, &[request], &[&admin], "request_withdraw").await;
+    }
+    let cancel = instruction::cancel_withdraw_request(&id, &campaign, &admin_key, 1);
+    assert_ok(&mut env, checked, &[cancel], &[&admin], "cancel_withdraw_request").await;
+
+    let now = env.now().await;
+    env.warp_to_timestamp(now + 61).await;
+    let execute = instruction::execute_withdraw(&id, &campaign, &admin_key, &treasury);
+    assert_ok(&mut env, checked, &[execute], &[&admin], "execute_withdraw").await;
+}
+
+// Donations split over campaigns, blocked donors, a donation given back and pending donations
+// taken back by their donor.
+async fn donations(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let first = new_campaign(&mut env, &admin, campaign_settings(&admin, 0, 0)).await;
+    let second = new_campaign(&mut env, &admin, campaign_settings(&admin, 0, 0)).await;
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+
+    let targets = [(first, 0, LAMPORTS_PER_SOL), (second, 0, LAMPORTS_PER_SOL)];
+    let split = instruction::donate_split(&id, &donor.pubkey(), &targets);
+    assert_ok(&mut env, checked, &[split], &[&donor], "donate_split").await;
+
+    let redirect = instruction::redirect_funds(&id, &first, &admin.pubkey(), &second, LAMPORTS_PER_SOL / 2);
+    assert_ok(&mut env, checked, &[redirect], &[&admin], "redirect_funds").await;
+
+    let wallet = Pubkey::new_unique();
+    let block = instruction::block_donor(&id, &first, &admin.pubkey(), &wallet);
+    assert_ok(&mut env, checked, &[block], &[&admin], "block_donor").await;
+    let unblock = instruction::unblock_donor(&id, &first, &admin.pubkey(), &wallet);
+    assert_ok(&mut env, checked, &[unblock], &[&admin], "unblock_donor").await;
+
+    let give_back = instruction::return_donation(&id, &second, &admin.pubkey(), &donor.pubkey());
+    assert_ok(&mut env, checked, &[give_back], &[&admin], "return_donation").await;
+
+    let settings = CampaignDetails { confirmation_slots: 10, ..campaign_settings(&admin, 0, 0) };
+    let pending = new_campaign(&mut env, &admin, settings).await;
+    env.donate(&pending, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let slot = env.clock().await.slot;
+    env.context.warp_to_slot(slot + 9_001).unwrap();
+    let reclaim = instruction::reclaim_pending(&id, &pending, &donor.pubkey());
+    assert_ok(&mut env, checked, &[reclaim], &[&donor], "reclaim_pending").await;
+}
+
+// A failed campaign refunding its donors, to a refund recipient and with a proof, and the authority
+// sweeping what is left once the refund window closed.
+async fn refunds(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let authority = env.authority.insecure_clone();
+    let mut config = env.config().await;
+    config.refund_window_secs = 86_400;
+    env.set_config(&config).await;
+
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = new_campaign(&mut env, &admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await;
+    let mut donors = Vec::new();
+    for _ in 0..2 {
+        let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+        env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+        donors.push(donor);
+    }
+    // Refunds with a proof are for campaigns nobody was refunded by record yet.
+    let proof_campaign = new_campaign(&mut env, &admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&proof_campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let recipient = Pubkey::new_unique();
+    let set_recipient = instruction::set_refund_recipient(&id, &campaign, &donors[1].pubkey(), &recipient);
+    assert_ok(&mut env, checked, &[set_recipient], &[&donors[1]], "set_refund_recipient").await;
+
+    env.warp_to_timestamp(end + 1).await;
+    env.process(&[instruction::finalize_campaign(&id, &campaign)], &[]).await.unwrap();
+    env.process(&[instruction::finalize_campaign(&id, &proof_campaign)], &[]).await.unwrap();
+    let claim = instruction::claim_refund(&id, &campaign, &donors[0].pubkey(), None);
+    assert_ok(&mut env, checked, &[claim], &[&donors[0]], "claim_refund").await;
+    let claim = instruction::claim_refund(&id, &campaign, &donors[1].pubkey(), Some(&recipient));
+    assert_ok(&mut env, checked, &[claim], &[], "claim_refund").await;
+
+    // A tree of one leaf, its root is the leaf and the proof is empty.
+    let root = hashv(&[donor.pubkey().as_ref(), &LAMPORTS_PER_SOL.to_le_bytes()]).to_bytes();
+    let set_root = instruction::set_refund_root(&id, &proof_campaign, &authority.pubkey(), root);
+    assert_ok(&mut env, checked, &[set_root], &[&authority], "set_refund_root").await;
+    let claim = instruction::claim_refund_with_proof(&id, &proof_campaign, &donor.pubkey(), LAMPORTS_PER_SOL, Vec::new());
+    assert_ok(&mut env, checked, &[claim], &[&donor], "claim_refund_with_proof").await;
+
+    env.warp_to_timestamp(end + 86_400 + 2).await;
+    let sweep = instruction::sweep_unclaimed_refunds(&id, &campaign, &env.treasury.pubkey());
+    assert_ok(&mut env, checked, &[sweep], &[], "sweep_unclaimed_refunds").await;
+}
+
+// A dispute resolved, then one ending with the campaign liquidated and the insurance paying a donor.
+async fn disputes(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let resolver = env.authority.insecure_clone();
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = new_campaign(&mut env, &admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let open = instruction::open_dispute(&id, &campaign, &resolver.pubkey(), true);
+    assert_ok(&mut env, checked, &[open], &[&resolver], "open_dispute").await;
+    let resolve = instruction::resolve_dispute(&id, &campaign, &resolver.pubkey());
+    assert_ok(&mut env, checked, &[resolve], &[&resolver], "resolve_dispute").await;
+    let open = instruction::open_dispute(&id, &campaign, &resolver.pubkey(), false);
+    env.process(&[open], &[&resolver]).await.unwrap();
+    let liquidate = instruction::liquidate_campaign(&id, &campaign, &resolver.pubkey());
+    assert_ok(&mut env, checked, &[liquidate], &[&resolver], "liquidate_campaign").await;
+
+    // The pool only gets the insurance share of fees, there are none in this config.
+    let (pool, _) = find_insurance_pool_address(&id);
+    env.airdrop(&pool, LAMPORTS_PER_SOL).await;
+    let pay = instruction::pay_insurance_claim(&id, &resolver.pubkey(), &campaign, &donor.pubkey(), LAMPORTS_PER_SOL / 2);
+    assert_ok(&mut env, checked, &[pay], &[&resolver], "pay_insurance_claim").await;
+}
+
+// A sponsor matching donations, and a voucher redeemed then closed.
+async fn sponsors_and_vouchers(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let authority = env.authority.insecure_clone();
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = new_campaign(&mut env, &admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await;
+
+    let sponsor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&id, &sponsor.pubkey(), "Sponsor");
+    assert_ok(&mut env, checked, &[register], &[&sponsor], "register_sponsor").await;
+    let rename = instruction::update_sponsor_name(&id, &sponsor.pubkey(), "Sponsor Inc");
+    assert_ok(&mut env, checked, &[rename], &[&sponsor], "update_sponsor_name").await;
+    let flag = instruction::flag_sponsor(&id, &sponsor.pubkey(), &authority.pubkey(), false);
+    assert_ok(&mut env, checked, &[flag], &[&authority], "flag_sponsor").await;
+    let commit = instruction::commit_match(&id, &campaign, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    assert_ok(&mut env, checked, &[commit], &[&sponsor], "commit_match").await;
+
+    let code = b"secret code";
+    let expires_at = env.now().await + 3_600;
+    let create = instruction::create_voucher(&id, &sponsor.pubkey(), hash(code).to_bytes(), LAMPORTS_PER_SOL / 2, expires_at);
+    assert_ok(&mut env, checked, &[create], &[&sponsor], "create_voucher").await;
+    let redeemer = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let redeem = instruction::redeem_voucher(&id, &campaign, &redeemer.pubkey(), sequence, code);
+    assert_ok(&mut env, checked, &[redeem], &[&redeemer], "redeem_voucher").await;
+    let reclaim = instruction::reclaim_voucher(&id, &sponsor.pubkey(), hash(code).to_bytes());
+    assert_ok(&mut env, checked, &[reclaim], &[&sponsor], "reclaim_voucher").await;
+
+    env.warp_to_timestamp(end + 1).await;
+    env.process(&[instruction::finalize_campaign(&id, &campaign)], &[]).await.unwrap();
+    let release = instruction::release_match(&id, &campaign, &sponsor.pubkey());
+    assert_ok(&mut env, checked, &[release], &[&sponsor], "release_match").await;
+}
+
+// The staging account donate path, and a campaign in the v1 layout moving its description out.
+async fn legacy_accounts(checked: &mut BTreeSet<String>) {
+    let mut env = start().await;
+    let id = env.program_id;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = new_campaign(&mut env, &admin, campaign_settings(&admin, 0, 0)).await;
+    let donor = env.new_wallet(5 * LAMPORTS_PER_SOL).await;
+    let authority = env.authority.insecure_clone();
+    let enable = instruction::set_legacy_donate_disabled(&id, &authority.pubkey(), false);
+    env.process(&[enable], &[&authority]).await.unwrap();
+
+    let mut staged = Vec::new();
+    for seed in ["first", "second"] {
+        let staging = Pubkey::create_with_seed(&donor.pubkey(), seed, &id).unwrap();
+        let create =
+            system_instruction::create_account_with_seed(&donor.pubkey(), &staging, &donor.pubkey(), seed, LAMPORTS_PER_SOL, 1, &id);
+        env.process(&[create], &[&donor]).await.unwrap();
+        staged.push((staging, seed));
+    }
+    let (staging, seed) = staged[0];
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    let donate = instruction::donate(&id, &campaign, &staging, &donor.pubkey(), sequence, LAMPORTS_PER_SOL / 2, true, seed);
+    assert_ok(&mut env, checked, &[donate], &[&donor], "donate").await;
+    let (staging, seed) = staged[1];
+    let reclaim = instruction::reclaim_staged(&id, &staging, &donor.pubkey(), seed);
+    assert_ok(&mut env, checked, &[reclaim], &[&donor], "reclaim_staged").await;
+
+    let v1 = CampaignDetailsV1 {
+        admin: admin.pubkey(),
+        name: "Old campaign".to_string(),
+        description: "Written before the content split".to_string(),
+        image_link: String::new(),
+        amount_donated: 0,
+    };
+    let old = Pubkey::new_unique();
+    let data = v1.try_to_vec().unwrap();
+    let lamports = env.rent().await.minimum_balance(data.len());
+    env.context.set_account(&old, &Account { lamports, data, owner: id, ..Default::default() }.into());
+    let migrate = instruction::migrate_campaign_content(&id, &old, &env.payer().pubkey());
+    assert_ok(&mut env, checked, &[migrate], &[], "migrate_campaign_content").await;
+}
@@ -0,0 +1,156 @@
+// Sponsors matching donations, the sponsor record adding up all their commitments and matches, and
+// getting their commitment back once the campaign is finalized.
+mod common;
+
+use common::*;
+use program::{
+    instruction,
+    seeds::{find_match_address, find_sponsor_address},
+    CampaignState, Sponsor,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// A campaign ending in a day with a sponsor committing `committed`, and a donation of `donated`
+// they matched.
+async fn matched_campaign(committed: u64, donated: u64) -> (Env, Pubkey, Keypair) {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await.unwrap();
+
+    let sponsor = env.new_wallet(committed + LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&env.program_id, &sponsor.pubkey(), "Sponsor");
+    let commit = instruction::commit_match(&env.program_id, &campaign, &sponsor.pubkey(), committed);
+    env.process(&[register, commit], &[&sponsor]).await.unwrap();
+
+    let donor = env.new_wallet(donated + LAMPORTS_PER_SOL).await;
+    let donate = env.donate_instruction(&campaign, &donor, donated).await;
+    let donate = instruction::with_match(donate, &campaign, &sponsor.pubkey());
+    env.process(&[donate], &[&donor]).await.unwrap();
+    (env, campaign, sponsor)
+}
+
+#[tokio::test]
+async fn sponsor_matches_a_donation() {
+    let (mut env, campaign, _) = matched_campaign(2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL).await;
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.amount_matched, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn sponsor_totals_aggregate_every_commitment() {
+    let (mut env, _, sponsor) = matched_campaign(2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL).await;
+    let (sponsor_account, _) = find_sponsor_address(&env.program_id, &sponsor.pubkey());
+    let sponsor_data: Sponsor = env.borsh_account(&sponsor_account).await;
+    assert_eq!(sponsor_data.wallet, sponsor.pubkey());
+    assert_eq!(sponsor_data.name, "Sponsor");
+    assert_eq!(sponsor_data.total_committed, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(sponsor_data.total_matched, LAMPORTS_PER_SOL);
+    assert!(!sponsor_data.flagged);
+
+    // A commitment to another campaign adds to the same record.
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let other = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let commit = instruction::commit_match(&env.program_id, &other, &sponsor.pubkey(), LAMPORTS_PER_SOL / 2);
+    env.process(&[commit], &[&sponsor]).await.unwrap();
+    let sponsor_data: Sponsor = env.borsh_account(&sponsor_account).await;
+    assert_eq!(sponsor_data.total_committed, 5 * LAMPORTS_PER_SOL / 2);
+    assert_eq!(sponsor_data.total_matched, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn flagged_sponsor_cant_commit() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let sponsor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&env.program_id, &sponsor.pubkey(), "Sponsor");
+    env.process(&[register], &[&sponsor]).await.unwrap();
+
+    let authority = env.authority.insecure_clone();
+    let flag = instruction::flag_sponsor(&env.program_id, &sponsor.pubkey(), &authority.pubkey(), true);
+    env.process(&[flag], &[&authority]).await.unwrap();
+    let commit = instruction::commit_match(&env.program_id, &campaign, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[commit], &[&sponsor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    let (sponsor_account, _) = find_sponsor_address(&env.program_id, &sponsor.pubkey());
+    let sponsor_data: Sponsor = env.borsh_account(&sponsor_account).await;
+    assert!(sponsor_data.flagged);
+    assert_eq!(sponsor_data.total_committed, 0);
+
+    // Lifting the flag lets them commit again.
+    let unflag = instruction::flag_sponsor(&env.program_id, &sponsor.pubkey(), &authority.pubkey(), false);
+    env.process(&[unflag], &[&authority]).await.unwrap();
+    let commit = instruction::commit_match(&env.program_id, &campaign, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[commit], &[&sponsor]).await.unwrap();
+    let sponsor_data: Sponsor = env.borsh_account(&sponsor_account).await;
+    assert_eq!(sponsor_data.total_committed, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn a_match_logs_the_sponsor_and_the_amount() {
+    let (mut env, campaign, sponsor) = matched_campaign(2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let donate = env.donate_instruction(&campaign, &donor, LAMPORTS_PER_SOL / 2).await;
+    let donate = instruction::with_match(donate, &campaign, &sponsor.pubkey());
+    let (result, logs) = env.process_with_logs(&[donate], &[&donor]).await;
+    result.unwrap();
+
+    let event = events(&logs).into_iter().find(|event| event[0] == b"match").unwrap();
+    assert_eq!(event[1], campaign.as_ref());
+    assert_eq!(event[2], sponsor.pubkey().as_ref());
+    assert_eq!(event[3], (LAMPORTS_PER_SOL / 2).to_le_bytes());
+}
+
+#[tokio::test]
+async fn release_of_a_campaign_below_rent_still_closes_the_commitment() {
+    let (mut env, campaign, sponsor) = matched_campaign(LAMPORTS_PER_SOL, LAMPORTS_PER_SOL).await;
+    // A failed campaign that somehow holds less then its rent exemption.
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.state = CampaignState::Failed;
+    env.set_campaign(&campaign, &campaign_data).await;
+    let mut account = env.account(&campaign).await;
+    account.lamports = env.rent().await.minimum_balance(CAMPAIGN_SPACE) - 1;
+    env.context.set_account(&campaign, &account.into());
+    let (commitment, _) = find_match_address(&env.program_id, &campaign, &sponsor.pubkey());
+    let escrow = env.balance(&commitment).await;
+    let campaign_before = env.balance(&campaign).await;
+    let sponsor_before = env.balance(&sponsor.pubkey()).await;
+
+    // Nothing comes out of the campaign, the sponsor still gets their escrow back.
+    let release = instruction::release_match(&env.program_id, &campaign, &sponsor.pubkey());
+    env.process(&[release], &[&sponsor]).await.unwrap();
+    assert_eq!(env.balance(&campaign).await, campaign_before);
+    assert_eq!(env.balance(&sponsor.pubkey()).await, sponsor_before + escrow);
+    assert!(env.context.banks_client.get_account(commitment).await.unwrap().is_none());
+    assert_eq!(env.campaign(&campaign).await.total_refunded, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn match_stops_at_the_hard_cap() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let mut settings = campaign_settings(&admin, LAMPORTS_PER_SOL, 0);
+    settings.hard_cap = 3 * LAMPORTS_PER_SOL / 2;
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+
+    let sponsor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&env.program_id, &sponsor.pubkey(), "Sponsor");
+    let commit = instruction::commit_match(&env.program_id, &campaign, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[register, commit], &[&sponsor]).await.unwrap();
+
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let donate = env.donate_instruction(&campaign, &donor, LAMPORTS_PER_SOL).await;
+    let donate = instruction::with_match(donate, &campaign, &sponsor.pubkey());
+    env.process(&[donate], &[&donor]).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, campaign_data.hard_cap);
+    assert_eq!(campaign_data.amount_matched, LAMPORTS_PER_SOL / 2);
+}
@@ -0,0 +1,84 @@
+// Campaign names of an admin: the name PDA finds the campaign from its name hash, names that only
+// differ in case, spacing or invisible characters are the same name, and renaming moves the PDA
+// along with the hash.
+mod common;
+
+use borsh::BorshDeserialize;
+use common::*;
+use program::{campaign_name_hash, instruction, name_key, seeds::find_campaign_name_address, CampaignDetails, CampaignName};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// Names and the key they are compared by.
+const NAME_KEYS: [(&str, &str); 7] = [
+    ("Clean Water", "clean water"),
+    ("  Clean   Water  ", "clean water"),
+    ("Clean\tWater\n", "clean water"),
+    ("Clean\u{200B} Water", "clean water"),
+    ("\u{FEFF}Cle\u{200C}an\u{200D} Wa\u{2060}ter", "clean water"),
+    ("Clean Water 💧", "clean water 💧"),
+    ("Clean💧Water", "clean💧water"),
+];
+
+fn named(admin: &Keypair, name: &str) -> CampaignDetails {
+    CampaignDetails { name: name.to_string(), ..campaign_settings(admin, LAMPORTS_PER_SOL, 0) }
+}
+
+// The campaign the admin's name PDA for `name` points to, if there is one.
+async fn lookup(env: &mut Env, admin: &Keypair, name: &str) -> Option<Pubkey> {
+    let (name_record, _) = find_campaign_name_address(&env.program_id, &admin.pubkey(), &campaign_name_hash(name));
+    let account = env.context.banks_client.get_account(name_record).await.unwrap()?;
+    let name_record = CampaignName::deserialize(&mut &account.data[..]).unwrap();
+    Some(name_record.campaign)
+}
+
+#[tokio::test]
+async fn rename_moves_the_name_hash() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, named(&admin, "Clean Water")).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.name_hash, campaign_name_hash("clean water"));
+    assert_eq!(lookup(&mut env, &admin, "Clean Water").await, Some(campaign));
+
+    // Only the case differs, so it is the same name.
+    let err = instruction_error(env.create_campaign(&admin, named(&admin, "CLEAN WATER")).await.map(|_| ()));
+    assert_eq!(err, InstructionError::AccountAlreadyInitialized);
+
+    let rename = instruction::rename_campaign(&env.program_id, &campaign, &admin.pubkey(), "Clean Water", "Village Wells");
+    env.process(&[rename], &[&admin]).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.name, "Village Wells");
+    assert_eq!(campaign_data.name_hash, campaign_name_hash("Village Wells"));
+    assert_eq!(lookup(&mut env, &admin, "village wells").await, Some(campaign));
+    assert_eq!(lookup(&mut env, &admin, "Clean Water").await, None);
+
+    // The old name is free again, the new one isn't.
+    let second = env.create_campaign(&admin, named(&admin, "clean water")).await.unwrap();
+    assert_eq!(lookup(&mut env, &admin, "Clean Water").await, Some(second));
+    let err = instruction_error(env.create_campaign(&admin, named(&admin, "Village  WELLS")).await.map(|_| ()));
+    assert_eq!(err, InstructionError::AccountAlreadyInitialized);
+}
+
+#[test]
+fn name_keys() {
+    for (name, key) in NAME_KEYS {
+        assert_eq!(name_key(name), key, "{:?}", name);
+        assert_eq!(campaign_name_hash(name), campaign_name_hash(key), "{:?}", name);
+    }
+    assert_ne!(campaign_name_hash("Clean Water 💧"), campaign_name_hash("Clean Water"));
+}
+
+#[tokio::test]
+async fn invisible_characters_dont_make_a_new_name() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, named(&admin, "clean water")).await.unwrap();
+
+    let err = instruction_error(env.create_campaign(&admin, named(&admin, " Clean\u{200B}  Water ")).await.map(|_| ()));
+    assert_eq!(err, InstructionError::AccountAlreadyInitialized);
+    assert_eq!(lookup(&mut env, &admin, "\u{FEFF}CLEAN water").await, Some(campaign));
+}
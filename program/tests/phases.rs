@@ -0,0 +1,95 @@
+// The phase logs of the `debug-logs` feature, only built with it:
+//   cargo test -p program --features debug-logs --test phases
+#![cfg(feature = "debug-logs")]
+mod common;
+
+use common::*;
+use program::instruction;
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+
+// The phases the instruction logged, in order. Each one has to be followed by the compute units
+// left at that point, natively program-test only logs that they aren't available.
+fn phases(logs: &[String], name: &str) -> Vec<String> {
+    let prefix = format!("Program log: 0xfund:{}:phase:", name);
+    let mut phases = Vec::new();
+    for (i, log) in logs.iter().enumerate() {
+        if let Some(phase) = log.strip_prefix(&prefix) {
+            let next = logs.get(i + 1).map(String::as_str).unwrap_or_default();
+            assert!(next.contains("consumption") || next.contains("sol_log_compute_units"), "no compute units after {}: {:?}", log, logs);
+            phases.push(phase.to_string());
+        }
+    }
+    phases
+}
+
+async fn assert_phases(env: &mut Env, instruction: Instruction, signers: &[&Keypair], name: &str, expected: &[&str]) {
+    let (result, logs) = env.process_with_logs(&[instruction], signers).await;
+    result.unwrap();
+    assert_eq!(phases(&logs, name), expected, "{:?}", logs);
+}
+
+#[tokio::test]
+async fn handlers_log_their_phases() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+
+    let campaign = Keypair::new();
+    let lamports = env.campaign_min_balance().await;
+    let create = [
+        system_instruction::create_account(
+            &env.payer().pubkey(),
+            &campaign.pubkey(),
+            lamports,
+            CAMPAIGN_SPACE as u64,
+            &env.program_id,
+        ),
+        instruction::create_campaign(
+            &env.program_id,
+            &campaign.pubkey(),
+            &env.treasury.pubkey(),
+            campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, end),
+            "",
+        ),
+    ];
+    let (result, logs) = env.process_with_logs(&create, &[&admin, &campaign]).await;
+    result.unwrap();
+    let campaign = campaign.pubkey();
+    assert_eq!(phases(&logs, "create_campaign"), ["deserialize", "validate", "cpi", "serialize"]);
+
+    let donate = env.donate_instruction(&campaign, &donor, LAMPORTS_PER_SOL).await;
+    assert_phases(&mut env, donate, &[&donor], "donate_direct", &["deserialize", "validate", "cpi", "serialize"]).await;
+
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL / 2);
+    assert_phases(&mut env, withdraw, &[&admin], "withdraw", &["deserialize", "validate", "transfer", "serialize"]).await;
+
+    let progress = instruction::get_progress(&env.program_id, &campaign);
+    assert_phases(&mut env, progress, &[], "get_progress", &["deserialize", "serialize"]).await;
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    assert_phases(&mut env, finalize, &[], "finalize_campaign", &["deserialize", "validate", "serialize"]).await;
+
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+    assert_phases(&mut env, claim, &[&donor], "claim_refund", &["deserialize", "validate", "cpi", "serialize"]).await;
+}
+
+// A handler that fails stops logging phases where it failed.
+#[tokio::test]
+async fn failing_handler_stops_at_its_phase() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, end)).await.unwrap();
+
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL);
+    let (result, logs) = env.process_with_logs(&[withdraw], &[&admin]).await;
+    assert!(result.is_err());
+    assert_eq!(phases(&logs, "withdraw"), ["deserialize", "validate"]);
+}
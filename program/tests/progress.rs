@@ -0,0 +1,44 @@
+// Polling a campaign's progress without decoding it: get_progress on-chain and `campaign_counters`
+// on the raw account data both read what the full decode reads.
+mod common;
+
+use common::*;
+use program::{campaign_counters, instruction};
+use solana_program_test::tokio;
+use solana_sdk::pubkey::Pubkey;
+use std::convert::TryInto;
+
+async fn progress(env: &mut Env, campaign: &Pubkey) -> (u64, u64) {
+    let query = instruction::get_progress(&env.program_id, campaign);
+    let simulation = env.simulate(query, &[]).await;
+    simulation.result.unwrap();
+    assert_eq!(simulation.return_data.len(), 16);
+    (
+        u64::from_le_bytes(simulation.return_data[..8].try_into().unwrap()),
+        u64::from_le_bytes(simulation.return_data[8..].try_into().unwrap()),
+    )
+}
+
+#[tokio::test]
+async fn partial_reads_match_the_full_decode() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0);
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+
+    for amount in [0, LAMPORTS_PER_SOL, LAMPORTS_PER_SOL / 3] {
+        if amount > 0 {
+            env.donate(&campaign, &donor, amount).await.unwrap();
+        }
+        let full = env.campaign(&campaign).await;
+        assert_eq!(progress(&mut env, &campaign).await, (full.amount_donated, full.goal));
+
+        let data = env.account(&campaign).await.data;
+        let counters = campaign_counters(&data).unwrap();
+        assert_eq!({ counters.amount_donated }, full.amount_donated);
+        assert_eq!({ counters.goal }, full.goal);
+        assert_eq!({ counters.donor_count }, full.donor_count);
+    }
+    assert_eq!(progress(&mut env, &campaign).await.0, 4 * LAMPORTS_PER_SOL / 3);
+}
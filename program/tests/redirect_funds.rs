@@ -0,0 +1,186 @@
+// redirect_funds takes lamports out of a campaign like a withdraw, with the same holds and locks,
+// and the partner takes them like a non-refundable donation, with the same limits.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, CampaignState, DonationRecord, FundError, Milestone};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// A campaign of `admin` created from `settings` with `donated` lamports donated, and an open partner campaign.
+async fn setup(settings: impl FnOnce(&Keypair) -> program::CampaignDetails, donated: u64) -> (Env, Keypair, Pubkey, Pubkey) {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, settings(&admin)).await.unwrap();
+    let partner_admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let partner = env.create_campaign(&partner_admin, campaign_settings(&partner_admin, 0, 0)).await.unwrap();
+    let donor = env.new_wallet(donated + LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, donated).await.unwrap();
+    (env, admin, campaign, partner)
+}
+
+#[tokio::test]
+async fn moves_funds_between_two_campaigns() {
+    let (mut env, admin, campaign, partner) = setup(|admin| campaign_settings(admin, 0, 0), 2 * LAMPORTS_PER_SOL).await;
+    let campaign_before = env.balance(&campaign).await;
+    let partner_before = env.balance(&partner).await;
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    env.process(&[redirect], &[&admin]).await.unwrap();
+
+    assert_eq!(env.balance(&campaign).await, campaign_before - LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&partner).await, partner_before + LAMPORTS_PER_SOL);
+    assert_eq!(env.campaign(&campaign).await.total_redirected, LAMPORTS_PER_SOL);
+    assert_eq!(env.campaign(&partner).await.amount_donated, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn failed_partner_refunds_its_donors_in_full() {
+    let (mut env, admin, campaign, _) = setup(|admin| campaign_settings(admin, 0, 0), 2 * LAMPORTS_PER_SOL).await;
+    let partner_admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let settings = program::CampaignDetails { category: 1, ..campaign_settings(&partner_admin, 100 * LAMPORTS_PER_SOL, end) };
+    let partner = env.create_campaign(&partner_admin, settings).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&partner, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    env.process(&[redirect], &[&admin]).await.unwrap();
+    let partner_data = env.campaign(&partner).await;
+    assert_eq!(partner_data.amount_donated, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(partner_data.donor_count, 2);
+    assert_eq!(partner_data.non_refundable_total, LAMPORTS_PER_SOL);
+    let (record, _) = find_donation_record_address(&env.program_id, &partner, &campaign);
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.amount, LAMPORTS_PER_SOL);
+    assert!(record.non_refundable);
+    // The lamports moved from the campaign's category to the partner's.
+    let stats = env.global_stats().await;
+    assert_eq!(stats.categories[0].amount_raised, LAMPORTS_PER_SOL);
+    assert_eq!(stats.categories[1].amount_raised, 2 * LAMPORTS_PER_SOL);
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &partner);
+    env.process(&[finalize], &[]).await.unwrap();
+    let partner_data = env.campaign(&partner).await;
+    assert_eq!(partner_data.state, CampaignState::Failed);
+    // The redirected SOL isn't owed to anyone, it goes to the partner's admin and the donor's
+    // share isn't cut by it.
+    assert_eq!(partner_data.refundable_total, LAMPORTS_PER_SOL);
+    assert_eq!(partner_data.refund_pool, LAMPORTS_PER_SOL);
+    assert_eq!(partner_data.failed_withdrawable, LAMPORTS_PER_SOL);
+
+    let before = env.balance(&donor.pubkey()).await;
+    let claim = instruction::claim_refund(&env.program_id, &partner, &donor.pubkey(), None);
+    env.process(&[claim], &[&donor]).await.unwrap();
+    assert_eq!(env.balance(&donor.pubkey()).await - before, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn partner_limits_apply() {
+    let (mut env, admin, campaign, partner) = setup(|admin| campaign_settings(admin, 0, 0), 2 * LAMPORTS_PER_SOL).await;
+
+    let mut partner_data = env.campaign(&partner).await;
+    partner_data.hard_cap = LAMPORTS_PER_SOL / 2;
+    env.set_campaign(&partner, &partner_data).await;
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::HardCapReached));
+
+    // The campaign would be a new donor of a partner that has all it takes.
+    let mut partner_data = env.campaign(&partner).await;
+    partner_data.hard_cap = 0;
+    partner_data.max_donors = 1;
+    env.set_campaign(&partner, &partner_data).await;
+    let partner_donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&partner, &partner_donor, LAMPORTS_PER_SOL).await.unwrap();
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::DonorLimitReached));
+    assert_eq!(env.campaign(&partner).await.amount_donated, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn keeps_the_rent_buffer() {
+    let (mut env, admin, campaign, partner) = setup(|admin| campaign_settings(admin, 0, 0), LAMPORTS_PER_SOL).await;
+
+    let redirect = instruction::redirect_funds(
+        &env.program_id,
+        &campaign,
+        &admin.pubkey(),
+        &partner,
+        LAMPORTS_PER_SOL + RENT_BUFFER_LAMPORTS,
+    );
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    env.process(&[redirect], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&campaign).await, env.campaign_min_balance().await);
+}
+
+#[tokio::test]
+async fn leaves_what_the_milestones_lock() {
+    let milestones = |admin: &Keypair| {
+        let mut settings = campaign_settings(admin, 3 * LAMPORTS_PER_SOL, 0);
+        settings.milestones = vec![
+            Milestone { amount: LAMPORTS_PER_SOL, released: false },
+            Milestone { amount: 3 * LAMPORTS_PER_SOL, released: false },
+        ];
+        settings
+    };
+    let (mut env, admin, campaign, partner) = setup(milestones, 2 * LAMPORTS_PER_SOL).await;
+
+    // Only the first milestone is reached, the second SOL stays locked.
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL + 1);
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    env.process(&[redirect], &[&admin]).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert!(campaign_data.milestones[0].released);
+    assert!(!campaign_data.milestones[1].released);
+}
+
+#[tokio::test]
+async fn timelocked_campaigns_cant_redirect() {
+    let timelocked = |admin: &Keypair| {
+        let mut settings = campaign_settings(admin, 0, 0);
+        settings.withdraw_delay_secs = 86_400;
+        settings
+    };
+    let (mut env, admin, campaign, partner) = setup(timelocked, LAMPORTS_PER_SOL).await;
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::WithdrawNotMatured));
+}
+
+#[tokio::test]
+async fn partner_has_to_be_an_allowed_recipient() {
+    let allowed = Keypair::new();
+    let allowed_key = allowed.pubkey();
+    let with_recipients = move |admin: &Keypair| {
+        let mut settings = campaign_settings(admin, 0, 0);
+        settings.withdraw_recipients = vec![allowed_key];
+        settings
+    };
+    let (mut env, admin, campaign, partner) = setup(with_recipients, 2 * LAMPORTS_PER_SOL).await;
+
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[redirect], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // Once the partner is in the list the same redirect goes through.
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.withdraw_recipients.push(partner);
+    env.set_campaign(&campaign, &campaign_data).await;
+    let redirect = instruction::redirect_funds(&env.program_id, &campaign, &admin.pubkey(), &partner, LAMPORTS_PER_SOL);
+    env.process(&[redirect], &[&admin]).await.unwrap();
+    assert_eq!(env.campaign(&partner).await.amount_donated, LAMPORTS_PER_SOL);
+}
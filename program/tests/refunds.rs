@@ -0,0 +1,284 @@
+// Refunds of failed campaigns. When the campaign can't pay everybody back donors, sponsors and
+// proof claims all get the same share of what they put in, and a donor's refund recipient is the
+// only wallet their refund can go to.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, CampaignState, DonationRecord, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    hash::hashv,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// A campaign raising 5 SOL, 1 SOL of it matched by a sponsor, of which the admin withdrew 3 SOL
+// before it failed. What is left covers about 40% of the donations.
+struct ShortPool {
+    env: Env,
+    campaign: Pubkey,
+    sponsor: Keypair,
+    donors: Vec<(Keypair, u64)>,
+}
+
+async fn short_pool() -> ShortPool {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await.unwrap();
+
+    let sponsor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let register = instruction::register_sponsor(&env.program_id, &sponsor.pubkey(), "Sponsor");
+    let commit = instruction::commit_match(&env.program_id, &campaign, &sponsor.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[register, commit], &[&sponsor]).await.unwrap();
+
+    let mut donors = Vec::new();
+    for (i, amount) in [LAMPORTS_PER_SOL, 2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL].iter().copied().enumerate() {
+        let donor = env.new_wallet(amount + LAMPORTS_PER_SOL).await;
+        let mut donate = env.donate_instruction(&campaign, &donor, amount).await;
+        if i == 0 {
+            donate = instruction::with_match(donate, &campaign, &sponsor.pubkey());
+        }
+        env.process(&[donate], &[&donor]).await.unwrap();
+        donors.push((donor, amount));
+    }
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 5 * LAMPORTS_PER_SOL);
+
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), 3 * LAMPORTS_PER_SOL);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.state, CampaignState::Failed);
+
+    ShortPool { env, campaign, sponsor, donors }
+}
+
+fn share(amount: u64, refund_pool: u64, refundable_total: u64) -> u64 {
+    (amount as u128 * refund_pool as u128 / refundable_total as u128) as u64
+}
+
+#[tokio::test]
+async fn sponsor_and_donors_share_a_short_pool() {
+    let ShortPool { mut env, campaign, sponsor, donors } = short_pool().await;
+    let campaign_data = env.campaign(&campaign).await;
+    let (pool, total) = (campaign_data.refund_pool, campaign_data.refundable_total);
+    assert_eq!(total, 5 * LAMPORTS_PER_SOL);
+    // The rent buffer stays in the campaign, it isn't part of the pool.
+    assert_eq!(pool, 2 * LAMPORTS_PER_SOL);
+
+    // The sponsor releasing first doesn't leave the last donor with less then their share.
+    let before = env.balance(&campaign).await;
+    let release = instruction::release_match(&env.program_id, &campaign, &sponsor.pubkey());
+    env.process(&[release], &[&sponsor]).await.unwrap();
+    let mut paid = before - env.balance(&campaign).await;
+    assert_eq!(paid, share(LAMPORTS_PER_SOL, pool, total));
+
+    for (donor, amount) in &donors {
+        let before = env.balance(&donor.pubkey()).await;
+        let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+        env.process(&[claim], &[donor]).await.unwrap();
+        let refund = env.balance(&donor.pubkey()).await - before;
+        assert_eq!(refund, share(*amount, pool, total));
+        paid += refund;
+    }
+    assert!(paid <= pool);
+    assert_eq!(env.campaign(&campaign).await.total_refunded, total);
+}
+
+#[tokio::test]
+async fn proof_claims_get_the_pool_share() {
+    let ShortPool { mut env, campaign, donors, .. } = short_pool().await;
+    let campaign_data = env.campaign(&campaign).await;
+    let (pool, total) = (campaign_data.refund_pool, campaign_data.refundable_total);
+
+    // A tree of one leaf, its root is the leaf and the proof is empty.
+    let (donor, amount) = &donors[1];
+    let root = hashv(&[donor.pubkey().as_ref(), &amount.to_le_bytes()]).to_bytes();
+    let authority = env.authority.insecure_clone();
+    let set_root = instruction::set_refund_root(&env.program_id, &campaign, &authority.pubkey(), root);
+    env.process(&[set_root], &[&authority]).await.unwrap();
+
+    let before = env.balance(&campaign).await;
+    let claim = instruction::claim_refund_with_proof(&env.program_id, &campaign, &donor.pubkey(), *amount, Vec::new());
+    env.process(&[claim], &[donor]).await.unwrap();
+    assert_eq!(before - env.balance(&campaign).await, share(*amount, pool, total));
+    assert_eq!(env.campaign(&campaign).await.total_refunded, *amount);
+}
+
+fn leaf(donor: &Keypair, amount: u64) -> [u8; 32] {
+    hashv(&[donor.pubkey().as_ref(), &amount.to_le_bytes()]).to_bytes()
+}
+
+// Posts the root of a tree of the first two donors and returns the proof of each, the other leaf.
+async fn post_two_leaf_root(env: &mut Env, campaign: &Pubkey, donors: &[(Keypair, u64)]) -> [Vec<[u8; 32]>; 2] {
+    let (a, b) = (leaf(&donors[0].0, donors[0].1), leaf(&donors[1].0, donors[1].1));
+    let root = if a <= b { hashv(&[&a, &b]) } else { hashv(&[&b, &a]) }.to_bytes();
+    let authority = env.authority.insecure_clone();
+    let set_root = instruction::set_refund_root(&env.program_id, campaign, &authority.pubkey(), root);
+    env.process(&[set_root], &[&authority]).await.unwrap();
+    [vec![b], vec![a]]
+}
+
+#[tokio::test]
+async fn a_proof_claim_pays_once() {
+    let ShortPool { mut env, campaign, donors, .. } = short_pool().await;
+    let campaign_data = env.campaign(&campaign).await;
+    let (pool, total) = (campaign_data.refund_pool, campaign_data.refundable_total);
+    let proofs = post_two_leaf_root(&mut env, &campaign, &donors).await;
+
+    let (donor, amount) = &donors[1];
+    let before = env.balance(&campaign).await;
+    let claim = instruction::claim_refund_with_proof(&env.program_id, &campaign, &donor.pubkey(), *amount, proofs[1].clone());
+    env.process(std::slice::from_ref(&claim), &[donor]).await.unwrap();
+    assert_eq!(before - env.balance(&campaign).await, share(*amount, pool, total));
+
+    let err = instruction_error(env.process(&[claim], &[donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert_eq!(env.campaign(&campaign).await.total_refunded, *amount);
+}
+
+#[tokio::test]
+async fn a_proof_for_another_amount_is_rejected() {
+    let ShortPool { mut env, campaign, donors, .. } = short_pool().await;
+    let proofs = post_two_leaf_root(&mut env, &campaign, &donors).await;
+
+    let (donor, amount) = &donors[0];
+    for claimed in [amount + 1, amount - 1, 2 * amount] {
+        let claim = instruction::claim_refund_with_proof(&env.program_id, &campaign, &donor.pubkey(), claimed, proofs[0].clone());
+        let err = instruction_error(env.process(&[claim], &[donor]).await);
+        assert_eq!(err, InstructionError::InvalidInstructionData);
+    }
+    // Another donor's proof doesn't work for them either.
+    let (other, _) = &donors[2];
+    let claim = instruction::claim_refund_with_proof(&env.program_id, &campaign, &other.pubkey(), *amount, proofs[0].clone());
+    let err = instruction_error(env.process(&[claim], &[other]).await);
+    assert_eq!(err, InstructionError::InvalidInstructionData);
+    assert_eq!(env.campaign(&campaign).await.total_refunded, 0);
+}
+
+#[tokio::test]
+async fn no_root_once_donors_were_refunded_by_record() {
+    let ShortPool { mut env, campaign, donors, .. } = short_pool().await;
+    let (donor, amount) = &donors[0];
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+    env.process(&[claim], &[donor]).await.unwrap();
+
+    let authority = env.authority.insecure_clone();
+    let set_root = instruction::set_refund_root(&env.program_id, &campaign, &authority.pubkey(), leaf(donor, *amount));
+    let err = instruction_error(env.process(&[set_root], &[&authority]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert_eq!(env.campaign(&campaign).await.refund_root, [0; 32]);
+}
+
+#[tokio::test]
+async fn a_proof_claim_settles_the_donation_record() {
+    let ShortPool { mut env, campaign, donors, .. } = short_pool().await;
+    let proofs = post_two_leaf_root(&mut env, &campaign, &donors).await;
+    let (donor, amount) = &donors[1];
+    let claim = instruction::claim_refund_with_proof(&env.program_id, &campaign, &donor.pubkey(), *amount, proofs[1].clone());
+    env.process(&[claim], &[donor]).await.unwrap();
+
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &donor.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert!(record.refunded);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(record.refund_paid, share(*amount, campaign_data.refund_pool, campaign_data.refundable_total));
+}
+
+// A failed campaign with one donation of 1 SOL, the donor sending their refund to a new wallet.
+async fn redirected_refund() -> (Env, Pubkey, Keypair, Pubkey) {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let recipient = Pubkey::new_unique();
+    let set_recipient = instruction::set_refund_recipient(&env.program_id, &campaign, &donor.pubkey(), &recipient);
+    env.process(&[set_recipient], &[&donor]).await.unwrap();
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+    (env, campaign, donor, recipient)
+}
+
+#[tokio::test]
+async fn refund_goes_to_the_recipient_without_the_donors_signature() {
+    let (mut env, campaign, donor, recipient) = redirected_refund().await;
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), Some(&recipient));
+    env.process(&[claim], &[]).await.unwrap();
+    assert_eq!(env.balance(&recipient).await, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn the_donors_key_cant_take_a_redirected_refund() {
+    let (mut env, campaign, donor, _) = redirected_refund().await;
+    // Whoever holds the donor's key now claims the refund to the donor's wallet...
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+    let err = instruction_error(env.process(&[claim], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // ...or to a wallet of their own.
+    let attacker = Pubkey::new_unique();
+    let mut claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), Some(&attacker));
+    claim.accounts[2].is_signer = true;
+    let err = instruction_error(env.process(&[claim], &[&donor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // Leaving out the recipient PDA doesn't get around it either.
+    let mut claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+    claim.accounts.truncate(3);
+    let err = instruction_error(env.process(&[claim], &[&donor]).await);
+    assert_eq!(err, InstructionError::NotEnoughAccountKeys);
+
+    assert_eq!(env.campaign(&campaign).await.total_refunded, 0);
+}
+
+#[tokio::test]
+async fn unclaimed_refunds_are_swept_after_the_window() {
+    const WINDOW: u64 = 7 * 86_400;
+    let mut env = start().await;
+    let mut config = env.config().await;
+    config.refund_window_secs = WINDOW;
+    env.set_config(&config).await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let (early, late) = (env.new_wallet(2 * LAMPORTS_PER_SOL).await, env.new_wallet(2 * LAMPORTS_PER_SOL).await);
+    env.donate(&campaign, &early, LAMPORTS_PER_SOL).await.unwrap();
+    env.donate(&campaign, &late, LAMPORTS_PER_SOL).await.unwrap();
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+    let refund_deadline = env.campaign(&campaign).await.refund_deadline;
+    assert_eq!(refund_deadline, end + 1 + WINDOW as i64);
+
+    // Inside the window a claim pays and there is nothing to sweep yet.
+    let early_before = env.balance(&early.pubkey()).await;
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &early.pubkey(), None);
+    env.process(&[claim], &[&early]).await.unwrap();
+    assert_eq!(env.balance(&early.pubkey()).await, early_before + LAMPORTS_PER_SOL);
+    let treasury = env.treasury.pubkey();
+    let sweep = || instruction::sweep_unclaimed_refunds(&program_id(), &campaign, &treasury);
+    let err = instruction_error(env.process(&[sweep()], &[]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    env.warp_to_timestamp(refund_deadline).await;
+    let claim = || instruction::claim_refund(&program_id(), &campaign, &late.pubkey(), None);
+    let err = instruction_error(env.process(&[claim()], &[&late]).await);
+    assert_eq!(err, fund_error(FundError::RefundWindowClosed));
+
+    // The late donor's refund goes to the treasury with the buffer, the campaign keeps its rent.
+    let treasury_before = env.balance(&treasury).await;
+    env.process(&[sweep()], &[]).await.unwrap();
+    assert_eq!(env.balance(&treasury).await, treasury_before + LAMPORTS_PER_SOL + RENT_BUFFER_LAMPORTS);
+    assert_eq!(env.balance(&campaign).await, env.rent().await.minimum_balance(CAMPAIGN_SPACE));
+    assert_eq!(env.campaign(&campaign).await.state, CampaignState::Closed);
+    let err = instruction_error(env.process(&[claim()], &[&late]).await);
+    assert_eq!(err, fund_error(FundError::RefundWindowClosed));
+}
@@ -0,0 +1,92 @@
+// Renewing a finalized campaign: the finished cycle goes into previous_cycles and the campaign
+// starts over on the same account, once every donor of a failed cycle got their refund.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, CampaignState, DonationRecord};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// A campaign of `admin` raising `goal` until tomorrow, finalized after `donations` came in.
+async fn finalized_campaign(env: &mut Env, admin: &Keypair, goal: u64, donations: &[(&Keypair, u64)]) -> (Pubkey, i64) {
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(admin, campaign_settings(admin, goal, end)).await.unwrap();
+    for (donor, amount) in donations {
+        env.donate(&campaign, donor, *amount).await.unwrap();
+    }
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &campaign);
+    env.process(&[finalize], &[]).await.unwrap();
+    (campaign, end)
+}
+
+#[tokio::test]
+async fn successful_campaign_starts_a_new_cycle() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let alice = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    let bob = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    let donations = [(&alice, LAMPORTS_PER_SOL), (&bob, LAMPORTS_PER_SOL / 2)];
+    let (campaign, end) = finalized_campaign(&mut env, &admin, LAMPORTS_PER_SOL, &donations).await;
+    let cycle = env.campaign(&campaign).await.cycle;
+    assert_eq!(env.campaign(&campaign).await.state, CampaignState::Successful);
+
+    let new_end = end + 7 * 86_400;
+    let renew = instruction::renew_campaign(&env.program_id, &campaign, &admin.pubkey(), 2 * LAMPORTS_PER_SOL, new_end);
+    env.process(&[renew], &[&admin]).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.state, CampaignState::Active);
+    assert_eq!(campaign_data.cycle, cycle + 1);
+    assert_eq!(campaign_data.goal, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.end_timestamp, new_end);
+    assert_eq!(campaign_data.amount_donated, 0);
+    assert_eq!(campaign_data.donor_count, 0);
+    assert_eq!(campaign_data.amount_matched, 0);
+    assert_eq!(campaign_data.total_refunded, 0);
+    assert_eq!(campaign_data.previous_cycles.len(), 1);
+    let previous = &campaign_data.previous_cycles[0];
+    assert_eq!(previous.raised, 3 * LAMPORTS_PER_SOL / 2);
+    assert_eq!(previous.donors, 2);
+    assert_eq!(previous.ended_at, end);
+
+    // The donor's record carries over to the new cycle and counts from zero in it.
+    env.donate(&campaign, &alice, LAMPORTS_PER_SOL / 4).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL / 4);
+    assert_eq!(campaign_data.donor_count, 1);
+    let (record, _) = find_donation_record_address(&env.program_id, &campaign, &alice.pubkey());
+    let record: DonationRecord = env.borsh_account(&record).await;
+    assert_eq!(record.cycle, cycle + 1);
+    assert_eq!(record.amount, LAMPORTS_PER_SOL / 4);
+}
+
+#[tokio::test]
+async fn failed_campaign_with_pending_refunds_cant_be_renewed() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    let (campaign, end) = finalized_campaign(&mut env, &admin, 100 * LAMPORTS_PER_SOL, &[(&donor, LAMPORTS_PER_SOL)]).await;
+    assert_eq!(env.campaign(&campaign).await.state, CampaignState::Failed);
+
+    let new_end = end + 7 * 86_400;
+    let renew = instruction::renew_campaign(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL, new_end);
+    let err = instruction_error(env.process(&[renew], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.state, CampaignState::Failed);
+    assert!(campaign_data.previous_cycles.is_empty());
+
+    // Once the donor got their refund there is nothing left to settle.
+    let claim = instruction::claim_refund(&env.program_id, &campaign, &donor.pubkey(), None);
+    env.process(&[claim], &[&donor]).await.unwrap();
+    let renew = instruction::renew_campaign(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL, new_end);
+    env.process(&[renew], &[&admin]).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.state, CampaignState::Active);
+    assert_eq!(campaign_data.previous_cycles[0].raised, LAMPORTS_PER_SOL);
+}
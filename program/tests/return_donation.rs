@@ -0,0 +1,82 @@
+// The admin giving a donor's donation back with return_donation: the lamports, the totals and
+// the record, and the donor donating again afterwards.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_donation_record_address, DonationRecord};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+async fn campaign_with_donation(env: &mut Env, donated: u64) -> (Keypair, Pubkey, Keypair) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, donated).await.unwrap();
+    (admin, campaign, donor)
+}
+
+async fn record(env: &mut Env, campaign: &Pubkey, donor: &Keypair) -> DonationRecord {
+    let (record, _) = find_donation_record_address(&env.program_id, campaign, &donor.pubkey());
+    env.borsh_account(&record).await
+}
+
+#[tokio::test]
+async fn donation_is_returned_in_full() {
+    let mut env = start().await;
+    let (admin, campaign, donor) = campaign_with_donation(&mut env, LAMPORTS_PER_SOL).await;
+    let campaign_before = env.balance(&campaign).await;
+    let donor_before = env.balance(&donor.pubkey()).await;
+
+    let give_back = instruction::return_donation(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    env.process(&[give_back], &[&admin]).await.unwrap();
+
+    assert_eq!(env.balance(&campaign).await, campaign_before - LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&donor.pubkey()).await, donor_before + LAMPORTS_PER_SOL);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 0);
+    assert_eq!(campaign_data.donor_count, 0);
+    assert_eq!(campaign_data.returned_amount, LAMPORTS_PER_SOL);
+    assert_eq!(record(&mut env, &campaign, &donor).await.amount, 0);
+
+    // Nothing is left to give back a second time.
+    let give_back = instruction::return_donation(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    let err = instruction_error(env.process(&[give_back], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn withdrawn_donation_cant_be_returned() {
+    let mut env = start().await;
+    let (admin, campaign, donor) = campaign_with_donation(&mut env, LAMPORTS_PER_SOL).await;
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL / 2);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+    let campaign_before = env.balance(&campaign).await;
+
+    let give_back = instruction::return_donation(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    let err = instruction_error(env.process(&[give_back], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    assert_eq!(env.balance(&campaign).await, campaign_before);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.returned_amount, 0);
+    assert_eq!(record(&mut env, &campaign, &donor).await.amount, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn donor_can_donate_again_after_a_return() {
+    let mut env = start().await;
+    let (admin, campaign, donor) = campaign_with_donation(&mut env, LAMPORTS_PER_SOL).await;
+    let give_back = instruction::return_donation(&env.program_id, &campaign, &admin.pubkey(), &donor.pubkey());
+    env.process(&[give_back], &[&admin]).await.unwrap();
+
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL / 4).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL / 4);
+    assert_eq!(campaign_data.donor_count, 1);
+    assert_eq!(campaign_data.returned_amount, LAMPORTS_PER_SOL);
+    assert_eq!(record(&mut env, &campaign, &donor).await.amount, LAMPORTS_PER_SOL / 4);
+}
@@ -0,0 +1,57 @@
+// The config's rounding policy on a withdraw fee and its insurance slice: each policy rounds its
+// own way, and whatever it rounds the lamports still add up.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_insurance_pool_address, Rounding};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+// 3.33% of this is 33300.03 lamports.
+const WITHDRAWN: u64 = 1_000_001;
+
+// The fee and its insurance slice (33.33% of it) each policy ends up with.
+const EXPECTED: [(Rounding, u64, u64); 3] = [
+    (Rounding::Floor, 33_300, 11_098),
+    (Rounding::Ceil, 33_301, 11_100),
+    (Rounding::Nearest, 33_300, 11_099),
+];
+
+#[tokio::test]
+async fn each_policy_rounds_and_conserves() {
+    for (rounding, fee, insurance) in EXPECTED {
+        let mut env = start().await;
+        let mut config = env.config().await;
+        config.fee_bps = 333;
+        config.insurance_bps = 3_333;
+        env.set_config(&config).await;
+        let authority = env.authority.insecure_clone();
+        let set_rounding = instruction::set_rounding(&env.program_id, &authority.pubkey(), rounding);
+        env.process(&[set_rounding], &[&authority]).await.unwrap();
+
+        let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+        let campaign = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+        let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+        env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+        let treasury = env.treasury.pubkey();
+        let (insurance_pool, _) = find_insurance_pool_address(&env.program_id);
+        let keys = [campaign, admin.pubkey(), treasury, insurance_pool];
+        let mut before = Vec::new();
+        for key in &keys {
+            before.push(env.balance(key).await);
+        }
+        let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, WITHDRAWN);
+        env.process(&[withdraw], &[&admin]).await.unwrap();
+        let mut after = Vec::new();
+        for key in &keys {
+            after.push(env.balance(key).await);
+        }
+
+        assert_eq!(before[0] - after[0], WITHDRAWN, "{:?}", rounding);
+        assert_eq!(after[1] - before[1], WITHDRAWN - fee, "{:?}", rounding);
+        assert_eq!(after[2] - before[2], fee - insurance, "{:?}", rounding);
+        assert_eq!(after[3] - before[3], insurance, "{:?}", rounding);
+        assert_eq!(after.iter().sum::<u64>(), before.iter().sum::<u64>(), "{:?}", rounding);
+    }
+}
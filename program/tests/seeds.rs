@@ -0,0 +1,80 @@
+// The addresses the seeds helpers derive for fixed inputs. Clients derive the same addresses, so a
+// changed seed prefix or seed order has to fail here before it ships. These don't need a bank.
+use program::seeds::*;
+use solana_sdk::pubkey::Pubkey;
+use std::str::FromStr;
+
+const PROGRAM_ID: Pubkey = Pubkey::new_from_array([7; 32]);
+const CAMPAIGN: Pubkey = Pubkey::new_from_array([1; 32]);
+const WALLET: Pubkey = Pubkey::new_from_array([2; 32]);
+const HASH: [u8; 32] = [3; 32];
+const SEQUENCE: u64 = 5;
+
+#[test]
+fn helpers_derive_the_pinned_addresses() {
+    let derived = [
+        ("config", find_config_address(&PROGRAM_ID), "3vdhRboaxszmoBCWSSq7ZBfEJAEpbYizuwtAJ4vBYz1L", 255),
+        ("insurance pool", find_insurance_pool_address(&PROGRAM_ID), "DTseGy67ppYUinuCV7U6CQ1aJVWphdDPj1xi2xi5MSU9", 255),
+        ("global stats", find_global_stats_address(&PROGRAM_ID), "37cDc7CB6kzzKuKG3MkfWeSN9cGmUR6piKqN9wT8WDFD", 255),
+        (
+            "campaign content",
+            find_campaign_content_address(&PROGRAM_ID, &CAMPAIGN),
+            "7xBRNAiJRe817UkuhsGUs8bF7Rriz7HLUaWXbpeygo1r",
+            255,
+        ),
+        ("update feed", find_update_feed_address(&PROGRAM_ID, &CAMPAIGN), "26Hm6ft2th5ueXA2SRTKcXmRpk7mVB7bCySgskduw6iJ", 255),
+        (
+            "campaign name",
+            find_campaign_name_address(&PROGRAM_ID, &WALLET, &HASH),
+            "GaJEGwqEkwGAYnrSRC5Drh9YKDiSMuBa6hePtzteyYVz",
+            255,
+        ),
+        ("receipt", find_receipt_address(&PROGRAM_ID, &CAMPAIGN, SEQUENCE), "14Y2sjFMzuoQviAhigU4yCRNgXCYnoNCCfPBNHRJ1aZc", 255),
+        (
+            "donation record",
+            find_donation_record_address(&PROGRAM_ID, &CAMPAIGN, &WALLET),
+            "G7dCq9qrDJj5fD4ufcS7k3aCpuxNNJY5zyd36JHabp74",
+            254,
+        ),
+        ("match", find_match_address(&PROGRAM_ID, &CAMPAIGN, &WALLET), "3Znf3N8Mrupe51mMuzAVRAGJf8yKKMbkejc9bSKTgZHp", 254),
+        (
+            "refund claim",
+            find_refund_claim_address(&PROGRAM_ID, &CAMPAIGN, &WALLET),
+            "5AZibycz93FjAwxkGT4Ay15wBbuN7y9LybuLcHPGrZd3",
+            255,
+        ),
+        ("blocklist", find_blocklist_address(&PROGRAM_ID, &CAMPAIGN, &WALLET), "EDei6K7weo1AYXrAEGaduz9cfchARAdNyx1juNnSM35r", 255),
+        (
+            "refund recipient",
+            find_refund_recipient_address(&PROGRAM_ID, &CAMPAIGN, &WALLET),
+            "75E5u7A6SL9GRhA7RvbrFDnefbTa4SsCMFoNCm5Ruq75",
+            255,
+        ),
+        ("sponsor", find_sponsor_address(&PROGRAM_ID, &WALLET), "B2qkgshBEBXWwDNSUg1wPvkct4SBTQg5an8jqHmnN9xr", 253),
+        ("charity", find_charity_address(&PROGRAM_ID, &WALLET), "8Fx5nphDfjxzjDkrLxGQ4V1BnTNoJKKZhmWz3VFNVq7n", 254),
+        ("voucher", find_voucher_address(&PROGRAM_ID, &HASH), "9UuWfuoUXYaxEEzPFPQk3ASGm1Qar6jUCu4u6nyr5N5r", 254),
+    ];
+    for (what, (address, bump), expected, expected_bump) in derived {
+        assert_eq!(address, Pubkey::from_str(expected).unwrap(), "{}", what);
+        assert_eq!(bump, expected_bump, "{}", what);
+    }
+}
+
+// What a client without the crate would write: the seed strings spelled out, in their order.
+#[test]
+fn seeds_are_the_documented_strings() {
+    let (donation_record, bump) = find_donation_record_address(&PROGRAM_ID, &CAMPAIGN, &WALLET);
+    let spelled_out =
+        Pubkey::create_program_address(&[b"donation", CAMPAIGN.as_ref(), WALLET.as_ref(), &[bump]], &PROGRAM_ID).unwrap();
+    assert_eq!(donation_record, spelled_out);
+
+    let (receipt, bump) = find_receipt_address(&PROGRAM_ID, &CAMPAIGN, SEQUENCE);
+    let spelled_out =
+        Pubkey::create_program_address(&[b"receipt", CAMPAIGN.as_ref(), &SEQUENCE.to_le_bytes(), &[bump]], &PROGRAM_ID)
+            .unwrap();
+    assert_eq!(receipt, spelled_out);
+
+    let (name, bump) = find_campaign_name_address(&PROGRAM_ID, &WALLET, &HASH);
+    let spelled_out = Pubkey::create_program_address(&[b"campaign_name", WALLET.as_ref(), &HASH, &[bump]], &PROGRAM_ID).unwrap();
+    assert_eq!(name, spelled_out);
+}
@@ -0,0 +1,69 @@
+// Snapshots of a campaign: the hash recorded on the campaign and in the event matches a copy of the
+// account taken at that slot, any other state or slot doesn't, and snapshots are rate limited.
+mod common;
+
+use borsh::BorshSerialize;
+use common::*;
+use program::{instruction, verify_snapshot, CampaignDetails, FundError};
+use solana_program_test::tokio;
+use solana_sdk::pubkey::Pubkey;
+
+const SNAPSHOT_INTERVAL_SLOTS: u64 = 150;
+
+async fn donated_campaign(env: &mut Env) -> Pubkey {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    campaign
+}
+
+#[tokio::test]
+async fn snapshot_verifies_against_a_copy_of_the_account() {
+    let mut env = start().await;
+    let campaign = donated_campaign(&mut env).await;
+
+    let snapshot = instruction::snapshot_campaign(&env.program_id, &campaign);
+    let (result, logs) = env.process_with_logs(&[snapshot], &[]).await;
+    assert_eq!(result, Ok(()));
+    let copy = env.account(&campaign).await.data;
+    let mut campaign_data = env.campaign(&campaign).await;
+    let (slot, hash) = (campaign_data.snapshot_slot, campaign_data.snapshot_hash);
+    assert_ne!(slot, 0);
+    let event = vec![b"snapshot".to_vec(), campaign.to_bytes().to_vec(), slot.to_le_bytes().to_vec(), hash.to_vec()];
+    assert_eq!(events(&logs), [event]);
+
+    assert!(verify_snapshot(&copy, slot, &hash));
+    assert!(!verify_snapshot(&copy, slot + 1, &hash));
+
+    // One lamport more in the copy and it is no longer the recorded state.
+    campaign_data.amount_donated += 1;
+    let mut tampered_copy = copy.clone();
+    campaign_data.serialize(&mut &mut tampered_copy[..]).unwrap();
+    assert!(!verify_snapshot(&tampered_copy, slot, &hash));
+    assert!(!verify_snapshot(&copy[..40], slot, &hash));
+
+    // Neither is the campaign after another donation, the copy still is.
+    let donor = env.new_wallet(LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    assert!(!verify_snapshot(&env.account(&campaign).await.data, slot, &hash));
+    assert!(verify_snapshot(&copy, slot, &hash));
+}
+
+#[tokio::test]
+async fn snapshots_are_rate_limited() {
+    let mut env = start().await;
+    let campaign = donated_campaign(&mut env).await;
+    let snapshot = || instruction::snapshot_campaign(&program_id(), &campaign);
+    env.process(&[snapshot()], &[]).await.unwrap();
+    let first: CampaignDetails = env.campaign(&campaign).await;
+
+    let err = instruction_error(env.process(&[snapshot()], &[]).await);
+    assert_eq!(err, fund_error(FundError::SnapshotTooSoon));
+
+    env.context.warp_to_slot(first.snapshot_slot + SNAPSHOT_INTERVAL_SLOTS).unwrap();
+    env.process(&[snapshot()], &[]).await.unwrap();
+    let second = env.campaign(&campaign).await;
+    assert!(second.snapshot_slot >= first.snapshot_slot + SNAPSHOT_INTERVAL_SLOTS);
+    assert_ne!(second.snapshot_hash, first.snapshot_hash);
+}
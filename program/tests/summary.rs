@@ -0,0 +1,84 @@
+// get_campaign_summary: the status bits and the counters of a campaign in a single simulated call,
+// decoded from the return data.
+mod common;
+
+use common::*;
+use program::{
+    instruction, CampaignState, SUMMARY_ACTIVE, SUMMARY_CLOSED, SUMMARY_DISPUTED, SUMMARY_FROZEN, SUMMARY_PAUSED,
+    SUMMARY_VERIFIED,
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+use std::convert::TryInto;
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    status: u8,
+    state: u8,
+    amount_donated: u64,
+    goal: u64,
+    end_timestamp: i64,
+    donor_count: u32,
+}
+
+async fn summary(env: &mut Env, campaign: &Pubkey) -> Summary {
+    let query = instruction::get_campaign_summary(&env.program_id, campaign);
+    let simulation = env.simulate(query, &[]).await;
+    simulation.result.unwrap();
+    let data = simulation.return_data;
+    assert_eq!(data.len(), 30);
+    Summary {
+        status: data[0],
+        state: data[1],
+        amount_donated: u64::from_le_bytes(data[2..10].try_into().unwrap()),
+        goal: u64::from_le_bytes(data[10..18].try_into().unwrap()),
+        end_timestamp: i64::from_le_bytes(data[18..26].try_into().unwrap()),
+        donor_count: u32::from_le_bytes(data[26..30].try_into().unwrap()),
+    }
+}
+
+#[tokio::test]
+async fn summary_follows_the_campaign_state() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let donor = env.new_wallet(2 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    let active = Summary {
+        status: SUMMARY_ACTIVE,
+        state: CampaignState::Active as u8,
+        amount_donated: LAMPORTS_PER_SOL,
+        goal: 10 * LAMPORTS_PER_SOL,
+        end_timestamp: end,
+        donor_count: 1,
+    };
+    assert_eq!(summary(&mut env, &campaign).await, active);
+
+    // An admin pause counts until it expires.
+    let pause_until = env.now().await + 3_600;
+    let pause = instruction::pause_campaign(&env.program_id, &campaign, &admin.pubkey(), true, pause_until);
+    env.process(&[pause], &[&admin]).await.unwrap();
+    assert_eq!(summary(&mut env, &campaign).await.status, SUMMARY_ACTIVE | SUMMARY_PAUSED);
+    env.warp_to_timestamp(pause_until).await;
+    assert_eq!(summary(&mut env, &campaign).await.status, SUMMARY_ACTIVE);
+
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.disputed = true;
+    campaign_data.fee_exempt = true;
+    env.set_campaign(&campaign, &campaign_data).await;
+    assert_eq!(summary(&mut env, &campaign).await.status, SUMMARY_ACTIVE | SUMMARY_DISPUTED | SUMMARY_VERIFIED);
+
+    campaign_data.disputed = false;
+    campaign_data.state = CampaignState::Liquidated;
+    env.set_campaign(&campaign, &campaign_data).await;
+    let liquidated = summary(&mut env, &campaign).await;
+    assert_eq!(liquidated.status, SUMMARY_FROZEN | SUMMARY_VERIFIED);
+    assert_eq!(liquidated.state, CampaignState::Liquidated as u8);
+
+    campaign_data.state = CampaignState::Closed;
+    env.set_campaign(&campaign, &campaign_data).await;
+    let closed = summary(&mut env, &campaign).await;
+    assert_eq!(closed.status, SUMMARY_CLOSED | SUMMARY_VERIFIED);
+    assert_eq!((closed.amount_donated, closed.donor_count), (LAMPORTS_PER_SOL, 1));
+}
@@ -0,0 +1,51 @@
+// A sunset program: no new campaigns or donations, while admins still withdraw, donors of failed
+// campaigns still get refunds and expired campaigns are still closed.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignState, FundError};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+#[tokio::test]
+async fn sunset_stops_new_money_but_not_the_way_out() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let active = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let failing = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let empty = env.create_campaign(&admin, campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, end)).await.unwrap();
+    let donor = env.new_wallet(4 * LAMPORTS_PER_SOL).await;
+    env.donate(&active, &donor, LAMPORTS_PER_SOL).await.unwrap();
+    env.donate(&failing, &donor, LAMPORTS_PER_SOL).await.unwrap();
+
+    let authority = env.authority.insecure_clone();
+    let sunset = instruction::set_sunset(&env.program_id, &authority.pubkey(), true);
+    env.process(&[sunset], &[&authority]).await.unwrap();
+
+    let err = instruction_error(env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.map(|_| ()));
+    assert_eq!(err, fund_error(FundError::ProgramSunset));
+    let err = instruction_error(env.donate(&active, &donor, LAMPORTS_PER_SOL).await);
+    assert_eq!(err, fund_error(FundError::ProgramSunset));
+    assert_eq!(env.campaign(&active).await.amount_donated, LAMPORTS_PER_SOL);
+
+    let admin_before = env.balance(&admin.pubkey()).await;
+    let treasury = env.treasury.pubkey();
+    let withdraw = instruction::withdraw(&env.program_id, &active, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + LAMPORTS_PER_SOL);
+
+    env.warp_to_timestamp(end + 1).await;
+    let finalize = instruction::finalize_campaign(&env.program_id, &failing);
+    env.process(&[finalize], &[]).await.unwrap();
+    assert_eq!(env.campaign(&failing).await.state, CampaignState::Failed);
+    let donor_before = env.balance(&donor.pubkey()).await;
+    let claim = instruction::claim_refund(&env.program_id, &failing, &donor.pubkey(), None);
+    env.process(&[claim], &[&donor]).await.unwrap();
+    assert_eq!(env.balance(&donor.pubkey()).await, donor_before + LAMPORTS_PER_SOL);
+
+    let name_hash = env.campaign(&empty).await.name_hash;
+    let close = instruction::close_expired_campaigns(&env.program_id, &authority.pubkey(), &[(empty, admin.pubkey(), name_hash)]);
+    env.process(&[close], &[&authority]).await.unwrap();
+    assert_eq!(env.context.banks_client.get_account(empty).await.unwrap(), None);
+}
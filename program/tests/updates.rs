@@ -0,0 +1,41 @@
+// The update feed of a campaign: the admin posts short announcements to it and donors' clients
+// read them back from the feed PDA.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_update_feed_address, FundError, UpdateFeed};
+use solana_program_test::tokio;
+use solana_sdk::signature::Signer;
+
+const MAX_UPDATES: usize = 16;
+
+#[tokio::test]
+async fn updates_are_appended_until_the_feed_is_full() {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, LAMPORTS_PER_SOL, 0)).await.unwrap();
+    let (feed, _) = find_update_feed_address(&env.program_id, &campaign);
+
+    let now = env.now().await;
+    for message in ["Wells are dug", "Pumps are in"] {
+        let post = instruction::post_update(&env.program_id, &campaign, &admin.pubkey(), message);
+        env.process(&[post], &[&admin]).await.unwrap();
+    }
+    let update_feed: UpdateFeed = env.borsh_account(&feed).await;
+    assert_eq!(update_feed.campaign, campaign);
+    let messages: Vec<&str> = update_feed.updates.iter().map(|update| update.message.as_str()).collect();
+    assert_eq!(messages, ["Wells are dug", "Pumps are in"]);
+    assert!(update_feed.updates.iter().all(|update| update.posted_at >= now));
+
+    for i in 2..MAX_UPDATES {
+        let post = instruction::post_update(&env.program_id, &campaign, &admin.pubkey(), &format!("Update {}", i));
+        env.process(&[post], &[&admin]).await.unwrap();
+    }
+    let post = instruction::post_update(&env.program_id, &campaign, &admin.pubkey(), "One too many");
+    let err = instruction_error(env.process(&[post], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::TooManyEntries));
+
+    let update_feed: UpdateFeed = env.borsh_account(&feed).await;
+    assert_eq!(update_feed.updates.len(), MAX_UPDATES);
+    assert_eq!(update_feed.updates[MAX_UPDATES - 1].message, format!("Update {}", MAX_UPDATES - 1));
+}
@@ -0,0 +1,146 @@
+// Vouchers a sponsor funds with a secret code, redeemed as a donation to a campaign.
+mod common;
+
+use common::*;
+use program::{instruction, seeds::find_voucher_address, FundError};
+use solana_program_test::{tokio, BanksClientError};
+use solana_sdk::{
+    hash::hash,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const VOUCHER_LIFETIME: i64 = 86_400;
+
+// An active campaign with a voucher of `amount` for `code`, and a wallet to redeem it.
+async fn voucher_campaign(code: &[u8], amount: u64) -> (Env, Pubkey, Keypair) {
+    let mut env = start().await;
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, campaign_settings(&admin, 100 * LAMPORTS_PER_SOL, 0)).await.unwrap();
+
+    create_voucher(&mut env, code, amount).await;
+    let redeemer = env.new_wallet(LAMPORTS_PER_SOL).await;
+    (env, campaign, redeemer)
+}
+
+// A voucher of `amount` for `code` from a new sponsor, expiring in a day.
+async fn create_voucher(env: &mut Env, code: &[u8], amount: u64) -> Keypair {
+    let sponsor = env.new_wallet(amount + LAMPORTS_PER_SOL).await;
+    let expires_at = env.now().await + VOUCHER_LIFETIME;
+    let create = instruction::create_voucher(&env.program_id, &sponsor.pubkey(), hash(code).to_bytes(), amount, expires_at);
+    env.process(&[create], &[&sponsor]).await.unwrap();
+    sponsor
+}
+
+async fn redeem(env: &mut Env, campaign: &Pubkey, redeemer: &Keypair, code: &[u8]) -> Result<(), BanksClientError> {
+    let sequence = env.campaign(campaign).await.donation_sequence;
+    let redeem = instruction::redeem_voucher(&env.program_id, campaign, &redeemer.pubkey(), sequence, code);
+    env.process(&[redeem], &[redeemer]).await
+}
+
+#[tokio::test]
+async fn redeeming_donates_the_voucher() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", LAMPORTS_PER_SOL).await;
+    let before = env.balance(&campaign).await;
+    redeem(&mut env, &campaign, &redeemer, b"welcome").await.unwrap();
+
+    assert_eq!(env.balance(&campaign).await - before, LAMPORTS_PER_SOL);
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.donor_count, 1);
+}
+
+#[tokio::test]
+async fn no_redeeming_once_the_program_is_sunset() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", LAMPORTS_PER_SOL).await;
+    let mut config = env.config().await;
+    config.sunset = true;
+    env.set_config(&config).await;
+
+    let err = instruction_error(redeem(&mut env, &campaign, &redeemer, b"welcome").await);
+    assert_eq!(err, fund_error(FundError::ProgramSunset));
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+}
+
+#[tokio::test]
+async fn a_voucher_over_the_donation_limit_is_rejected() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", 2 * LAMPORTS_PER_SOL).await;
+    let mut campaign_data = env.campaign(&campaign).await;
+    campaign_data.max_donation_per_tx = LAMPORTS_PER_SOL;
+    env.set_campaign(&campaign, &campaign_data).await;
+
+    let err = instruction_error(redeem(&mut env, &campaign, &redeemer, b"welcome").await);
+    assert_eq!(err, fund_error(FundError::DonationTooLarge));
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+}
+
+// A campaign `room` lamports short of its hard cap, filling up to it or not.
+async fn capped(env: &mut Env, campaign: &Pubkey, room: u64, fill_to_cap: bool) {
+    let mut campaign_data = env.campaign(campaign).await;
+    campaign_data.hard_cap = campaign_data.amount_donated + room;
+    campaign_data.fill_to_cap = fill_to_cap;
+    env.set_campaign(campaign, &campaign_data).await;
+}
+
+#[tokio::test]
+async fn a_voucher_fills_the_campaign_up_to_its_hard_cap() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", 2 * LAMPORTS_PER_SOL).await;
+    capped(&mut env, &campaign, LAMPORTS_PER_SOL, true).await;
+    let (voucher, _) = find_voucher_address(&env.program_id, &hash(b"welcome").to_bytes());
+    let before = env.balance(&voucher).await;
+
+    redeem(&mut env, &campaign, &redeemer, b"welcome").await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.amount_donated, LAMPORTS_PER_SOL);
+    // What the cap turned down stays in the voucher for the sponsor to reclaim.
+    assert_eq!(before - env.balance(&voucher).await, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn a_voucher_over_the_hard_cap_is_rejected() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", 2 * LAMPORTS_PER_SOL).await;
+    capped(&mut env, &campaign, LAMPORTS_PER_SOL, false).await;
+
+    let err = instruction_error(redeem(&mut env, &campaign, &redeemer, b"welcome").await);
+    assert_eq!(err, fund_error(FundError::HardCapReached));
+}
+
+#[tokio::test]
+async fn wrong_code_is_rejected() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", LAMPORTS_PER_SOL).await;
+    let (voucher, _) = find_voucher_address(&env.program_id, &hash(b"welcome").to_bytes());
+    let sequence = env.campaign(&campaign).await.donation_sequence;
+    // The right voucher account with the wrong code, the builder would derive another account from it.
+    let mut wrong_code = instruction::redeem_voucher(&env.program_id, &campaign, &redeemer.pubkey(), sequence, b"welcome!");
+    wrong_code.accounts[0].pubkey = voucher;
+    let err = instruction_error(env.process(&[wrong_code], &[&redeemer]).await);
+    assert_eq!(err, InstructionError::InvalidArgument);
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+
+    redeem(&mut env, &campaign, &redeemer, b"welcome").await.unwrap();
+}
+
+#[tokio::test]
+async fn sponsor_reclaims_the_voucher_once_it_expired() {
+    let (mut env, campaign, redeemer) = voucher_campaign(b"welcome", LAMPORTS_PER_SOL).await;
+    let sponsor = create_voucher(&mut env, b"spring", LAMPORTS_PER_SOL).await;
+    let code_hash = hash(b"spring").to_bytes();
+    let (voucher, _) = find_voucher_address(&env.program_id, &code_hash);
+
+    let reclaim = instruction::reclaim_voucher(&env.program_id, &sponsor.pubkey(), code_hash);
+    let err = instruction_error(env.process(&[reclaim], &[&sponsor]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    let now = env.now().await;
+    env.warp_to_timestamp(now + VOUCHER_LIFETIME).await;
+    let err = instruction_error(redeem(&mut env, &campaign, &redeemer, b"spring").await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    let voucher_lamports = env.balance(&voucher).await;
+    let sponsor_before = env.balance(&sponsor.pubkey()).await;
+    let reclaim = instruction::reclaim_voucher(&env.program_id, &sponsor.pubkey(), code_hash);
+    env.process(&[reclaim], &[&sponsor]).await.unwrap();
+    assert_eq!(env.balance(&sponsor.pubkey()).await, sponsor_before + voucher_lamports);
+    assert_eq!(env.context.banks_client.get_account(voucher).await.unwrap(), None);
+    assert_eq!(env.campaign(&campaign).await.amount_donated, 0);
+}
@@ -0,0 +1,68 @@
+// Withdrawing from many campaigns of one admin with withdraw_many: an ineligible campaign is
+// skipped, or fails the whole batch in strict mode.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignDetails, FundError, WithdrawPolicy};
+use solana_program_test::tokio;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+// Three all-or-nothing campaigns of one admin with 1 SOL each. The first two reached their goal,
+// the last one is still short of it.
+async fn three_campaigns(env: &mut Env) -> (Keypair, Vec<Pubkey>) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let donor = env.new_wallet(4 * LAMPORTS_PER_SOL).await;
+    let end = env.now().await + 86_400;
+    let mut campaigns = Vec::new();
+    for goal in [LAMPORTS_PER_SOL, LAMPORTS_PER_SOL, 10 * LAMPORTS_PER_SOL] {
+        let settings = CampaignDetails { withdraw_policy: WithdrawPolicy::GoalOnly, ..campaign_settings(&admin, goal, end) };
+        let campaign = env.create_campaign(&admin, settings).await.unwrap();
+        env.donate(&campaign, &donor, LAMPORTS_PER_SOL).await.unwrap();
+        campaigns.push(campaign);
+    }
+    (admin, campaigns)
+}
+
+#[tokio::test]
+async fn campaign_short_of_its_goal_is_skipped() {
+    let mut env = start().await;
+    let (admin, campaigns) = three_campaigns(&mut env).await;
+    let mut before = Vec::new();
+    for campaign in &campaigns {
+        before.push(env.balance(campaign).await);
+    }
+    let admin_before = env.balance(&admin.pubkey()).await;
+
+    let treasury = env.treasury.pubkey();
+    let withdraw_all = instruction::withdraw_many(&env.program_id, &admin.pubkey(), &treasury, &campaigns, vec![0; 3], false);
+    let (result, logs) = env.process_with_logs(&[withdraw_all], &[&admin]).await;
+    assert_eq!(result, Ok(()));
+    let skipped = format!("Program log: 0xfund:withdraw_many:Skipped {}: Custom({})", campaigns[2], FundError::GoalNotReached as u32);
+    assert!(logs.contains(&skipped), "{:?}", logs);
+
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + 2 * LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&campaigns[0]).await, before[0] - LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&campaigns[1]).await, before[1] - LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&campaigns[2]).await, before[2]);
+}
+
+#[tokio::test]
+async fn strict_batch_fails_on_the_campaign_short_of_its_goal() {
+    let mut env = start().await;
+    let (admin, campaigns) = three_campaigns(&mut env).await;
+    let admin_before = env.balance(&admin.pubkey()).await;
+
+    let treasury = env.treasury.pubkey();
+    let withdraw_all = instruction::withdraw_many(&env.program_id, &admin.pubkey(), &treasury, &campaigns, vec![0; 3], true);
+    let err = instruction_error(env.process(&[withdraw_all], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::GoalNotReached));
+
+    // The two campaigns before it keep their funds too.
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before);
+    for campaign in &campaigns {
+        assert_eq!(env.campaign(campaign).await.fee_assessed_volume, 0);
+    }
+}
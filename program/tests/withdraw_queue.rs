@@ -0,0 +1,79 @@
+// Timelocked campaigns: the admin queues withdrawals, they are executed oldest first once the
+// delay has passed, and a queued one can be cancelled in between.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignDetails, FundError};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const DELAY: u64 = 86_400;
+
+async fn timelocked_campaign(env: &mut Env, donated: u64) -> (Keypair, Pubkey) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let settings = CampaignDetails { withdraw_delay_secs: DELAY, ..campaign_settings(&admin, 10 * LAMPORTS_PER_SOL, 0) };
+    let campaign = env.create_campaign(&admin, settings).await.unwrap();
+    let donor = env.new_wallet(donated + LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, donated).await.unwrap();
+    (admin, campaign)
+}
+
+async fn queued_amounts(env: &mut Env, campaign: &Pubkey) -> Vec<u64> {
+    env.campaign(campaign).await.withdraw_queue.iter().map(|request| request.amount).collect()
+}
+
+#[tokio::test]
+async fn queued_withdrawals_run_oldest_first_after_the_delay() {
+    let mut env = start().await;
+    let (admin, campaign) = timelocked_campaign(&mut env, 3 * LAMPORTS_PER_SOL).await;
+    let treasury = env.treasury.pubkey();
+    let amounts = [LAMPORTS_PER_SOL, LAMPORTS_PER_SOL / 2, LAMPORTS_PER_SOL / 4];
+    for amount in amounts {
+        let request = instruction::request_withdraw(&env.program_id, &campaign, &admin.pubkey(), amount);
+        env.process(&[request], &[&admin]).await.unwrap();
+    }
+    assert_eq!(queued_amounts(&mut env, &campaign).await, amounts);
+
+    let execute = instruction::execute_withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury);
+    let err = instruction_error(env.process(&[execute], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::WithdrawNotMatured));
+
+    // Cancelling the middle one moves the last one up.
+    let cancel = instruction::cancel_withdraw_request(&env.program_id, &campaign, &admin.pubkey(), 1);
+    env.process(&[cancel], &[&admin]).await.unwrap();
+    assert_eq!(queued_amounts(&mut env, &campaign).await, [amounts[0], amounts[2]]);
+
+    let now = env.now().await;
+    env.warp_to_timestamp(now + DELAY as i64 + 1).await;
+    for (executed, remaining) in [(amounts[0], vec![amounts[2]]), (amounts[2], vec![])] {
+        let admin_before = env.balance(&admin.pubkey()).await;
+        let execute = instruction::execute_withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury);
+        env.process(&[execute], &[&admin]).await.unwrap();
+        assert_eq!(env.balance(&admin.pubkey()).await, admin_before + executed);
+        assert_eq!(queued_amounts(&mut env, &campaign).await, remaining);
+    }
+
+    let execute = instruction::execute_withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury);
+    let err = instruction_error(env.process(&[execute], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+}
+
+#[tokio::test]
+async fn queue_cant_be_oversubscribed() {
+    let mut env = start().await;
+    let (admin, campaign) = timelocked_campaign(&mut env, LAMPORTS_PER_SOL).await;
+    let request = instruction::request_withdraw(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL * 3 / 4);
+    env.process(&[request], &[&admin]).await.unwrap();
+
+    // Each one fits on its own, together they are more then the campaign holds.
+    let request = instruction::request_withdraw(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL / 2);
+    let err = instruction_error(env.process(&[request], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    let request = instruction::request_withdraw(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL / 4);
+    env.process(&[request], &[&admin]).await.unwrap();
+    assert_eq!(queued_amounts(&mut env, &campaign).await, [LAMPORTS_PER_SOL * 3 / 4, LAMPORTS_PER_SOL / 4]);
+}
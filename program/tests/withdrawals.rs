@@ -0,0 +1,269 @@
+// Withdrawing from a campaign: where the funds can go, and how much of the balance is the admin's.
+mod common;
+
+use common::*;
+use program::{instruction, CampaignDetails, FundError, Milestone, WithdrawPolicy, ERROR_RETURN_DATA_MARKER};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+use std::convert::TryInto;
+
+// A campaign of a new admin created from `settings`, with `donated` lamports donated.
+async fn funded_campaign(
+    env: &mut Env,
+    settings: impl FnOnce(&Keypair) -> CampaignDetails,
+    donated: u64,
+) -> (Keypair, Pubkey) {
+    let admin = env.new_wallet(LAMPORTS_PER_SOL).await;
+    let campaign = env.create_campaign(&admin, settings(&admin)).await.unwrap();
+    let donor = env.new_wallet(donated + LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, donated).await.unwrap();
+    (admin, campaign)
+}
+
+// A client reads how short the campaign is from the return data of a simulated withdraw.
+#[tokio::test]
+async fn insufficient_funds_come_with_the_amounts() {
+    let mut env = start().await;
+    let settings = |admin: &Keypair| campaign_settings(admin, 0, 0);
+    let (admin, campaign) = funded_campaign(&mut env, settings, LAMPORTS_PER_SOL).await;
+
+    let treasury = env.treasury.pubkey();
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, 2 * LAMPORTS_PER_SOL);
+    let simulation = env.simulate(withdraw, &[&admin]).await;
+    assert_eq!(
+        simulation.result,
+        Err(TransactionError::InstructionError(0, InstructionError::InsufficientFunds))
+    );
+
+    let data = &simulation.return_data;
+    assert_eq!(data.len(), 25);
+    assert_eq!(data[0], ERROR_RETURN_DATA_MARKER);
+    let value = |i: usize| u64::from_le_bytes(data[1 + 8 * i..9 + 8 * i].try_into().unwrap());
+    assert_eq!(value(0), u64::from(ProgramError::InsufficientFunds));
+    assert_eq!((value(1), value(2)), (2 * LAMPORTS_PER_SOL, LAMPORTS_PER_SOL));
+}
+
+// The rent went up since the campaign was funded, or something drained it, and its balance is
+// below what it has to keep. A withdraw says so instead of underflowing.
+#[tokio::test]
+async fn balance_below_the_rent_exemption_is_reported() {
+    let mut env = start().await;
+    let settings = |admin: &Keypair| campaign_settings(admin, 0, 0);
+    let (admin, campaign) = funded_campaign(&mut env, settings, LAMPORTS_PER_SOL).await;
+    let required = env.campaign_min_balance().await;
+    let short = required - 1;
+    let mut account = env.account(&campaign).await;
+    account.lamports = short;
+    env.context.set_account(&campaign, &account.into());
+
+    let treasury = env.treasury.pubkey();
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+    let simulation = env.simulate(withdraw(1), &[&admin]).await;
+    assert_eq!(
+        simulation.result,
+        Err(TransactionError::InstructionError(0, fund_error(FundError::NotRentExempt)))
+    );
+    let context = |i: usize| u64::from_le_bytes(simulation.return_data[9 + 8 * i..17 + 8 * i].try_into().unwrap());
+    assert_eq!((context(0), context(1)), (required, short));
+
+    // Topped up past the minimum the admin can take what is above it.
+    env.airdrop(&campaign, 1 + LAMPORTS_PER_SOL / 2).await;
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 2 + 1)], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&campaign).await, required);
+}
+
+#[tokio::test]
+async fn withdraws_to_an_allowed_recipient() {
+    let mut env = start().await;
+    let recipient = Pubkey::new_unique();
+    let settings = |admin: &Keypair| CampaignDetails { withdraw_recipients: vec![recipient], ..campaign_settings(admin, 0, 0) };
+    let (admin, campaign) = funded_campaign(&mut env, settings, 2 * LAMPORTS_PER_SOL).await;
+
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL);
+    let withdraw = instruction::with_recipient(withdraw, &recipient);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&recipient).await, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn other_recipients_are_rejected() {
+    let mut env = start().await;
+    let recipient = Pubkey::new_unique();
+    let settings = |admin: &Keypair| CampaignDetails { withdraw_recipients: vec![recipient], ..campaign_settings(admin, 0, 0) };
+    let (admin, campaign) = funded_campaign(&mut env, settings, 2 * LAMPORTS_PER_SOL).await;
+    let campaign_before = env.balance(&campaign).await;
+
+    let stranger = Pubkey::new_unique();
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL);
+    let withdraw = instruction::with_recipient(withdraw, &stranger);
+    let err = instruction_error(env.process(&[withdraw], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    // With a list the admin isn't a recipient either, unless it is in it.
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &env.treasury.pubkey(), LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[withdraw], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+    assert_eq!(env.balance(&campaign).await, campaign_before);
+}
+
+// A milestone campaign with a confirmation threshold: 2 SOL confirmed, which reached the first
+// milestone of 1 SOL, and 1.5 SOL still pending. The milestones lock 1 SOL and the pending
+// donations 1.5 SOL, only the 1 SOL left is the admin's.
+async fn milestones_and_pending(env: &mut Env) -> (Keypair, Pubkey) {
+    let settings = |admin: &Keypair| CampaignDetails {
+        confirmation_slots: 1_000,
+        milestones: vec![
+            Milestone { amount: LAMPORTS_PER_SOL, released: false },
+            Milestone { amount: 3 * LAMPORTS_PER_SOL, released: false },
+        ],
+        ..campaign_settings(admin, 3 * LAMPORTS_PER_SOL, 0)
+    };
+    let (admin, campaign) = funded_campaign(env, settings, 2 * LAMPORTS_PER_SOL).await;
+    let slot = env.clock().await.slot;
+    env.context.warp_to_slot(slot + 1_000).unwrap();
+    let confirm = instruction::confirm_donations(&env.program_id, &campaign);
+    env.process(&[confirm], &[]).await.unwrap();
+    let donor = env.new_wallet(3 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, 3 * LAMPORTS_PER_SOL / 2).await.unwrap();
+
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.amount_donated, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.milestone_locked(), LAMPORTS_PER_SOL);
+    assert_eq!(campaign_data.pending_amount(), 3 * LAMPORTS_PER_SOL / 2);
+    (admin, campaign)
+}
+
+#[tokio::test]
+async fn withdraw_leaves_every_reservation() {
+    let mut env = start().await;
+    let (admin, campaign) = milestones_and_pending(&mut env).await;
+
+    let query = instruction::get_withdrawable(&env.program_id, &campaign);
+    let simulation = env.simulate(query, &[]).await;
+    assert_eq!(u64::from_le_bytes(simulation.return_data[..8].try_into().unwrap()), LAMPORTS_PER_SOL);
+
+    // Either reservation alone would leave more then this.
+    let treasury = env.treasury.pubkey();
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL + 1);
+    let err = instruction_error(env.process(&[withdraw], &[&admin]).await);
+    assert_eq!(err, InstructionError::InsufficientFunds);
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+}
+
+#[tokio::test]
+async fn withdrawing_everything_leaves_every_reservation() {
+    let mut env = start().await;
+    let (admin, campaign) = milestones_and_pending(&mut env).await;
+    let admin_before = env.balance(&admin.pubkey()).await;
+    let campaign_before = env.balance(&campaign).await;
+
+    let treasury = env.treasury.pubkey();
+    let withdraw_all = instruction::withdraw_many(&env.program_id, &admin.pubkey(), &treasury, &[campaign], vec![0], true);
+    env.process(&[withdraw_all], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + LAMPORTS_PER_SOL);
+    assert_eq!(env.balance(&campaign).await, campaign_before - LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn resolving_the_dispute_unblocks_withdrawing() {
+    let mut env = start().await;
+    let (admin, campaign) = funded_campaign(&mut env, |admin| campaign_settings(admin, 0, 0), LAMPORTS_PER_SOL).await;
+    let resolver = env.authority.insecure_clone();
+    let dispute = instruction::open_dispute(&env.program_id, &campaign, &resolver.pubkey(), false);
+    env.process(&[dispute], &[&resolver]).await.unwrap();
+
+    let treasury = env.treasury.pubkey();
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    let err = instruction_error(env.process(&[withdraw], &[&admin]).await);
+    assert_eq!(err, InstructionError::InvalidAccountData);
+
+    let resolve = instruction::resolve_dispute(&env.program_id, &campaign, &resolver.pubkey());
+    env.process(&[resolve], &[&resolver]).await.unwrap();
+    assert!(!env.campaign(&campaign).await.disputed);
+    let admin_before = env.balance(&admin.pubkey()).await;
+    let withdraw = instruction::withdraw(&env.program_id, &campaign, &admin.pubkey(), &treasury, LAMPORTS_PER_SOL);
+    env.process(&[withdraw], &[&admin]).await.unwrap();
+    assert_eq!(env.balance(&admin.pubkey()).await, admin_before + LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn epoch_limit_resets_in_the_next_epoch() {
+    let mut env = start().await;
+    let settings = |admin: &Keypair| CampaignDetails { max_withdraw_per_epoch: LAMPORTS_PER_SOL, ..campaign_settings(admin, 0, 0) };
+    let (admin, campaign) = funded_campaign(&mut env, settings, 3 * LAMPORTS_PER_SOL).await;
+    let treasury = env.treasury.pubkey();
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+
+    env.process(&[withdraw(LAMPORTS_PER_SOL * 3 / 5)], &[&admin]).await.unwrap();
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::EpochWithdrawLimitReached));
+    env.process(&[withdraw(LAMPORTS_PER_SOL * 2 / 5)], &[&admin]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.withdrawn_this_epoch, LAMPORTS_PER_SOL);
+
+    let clock = env.clock().await;
+    let next_epoch = env.context.genesis_config().epoch_schedule.get_first_slot_in_epoch(clock.epoch + 1);
+    env.context.warp_to_slot(next_epoch).unwrap();
+    env.process(&[withdraw(LAMPORTS_PER_SOL)], &[&admin]).await.unwrap();
+    let campaign_data = env.campaign(&campaign).await;
+    assert_eq!(campaign_data.withdraw_epoch, clock.epoch + 1);
+    assert_eq!(campaign_data.withdrawn_this_epoch, LAMPORTS_PER_SOL);
+}
+
+#[tokio::test]
+async fn cooldown_holds_back_the_next_withdraw() {
+    let mut env = start().await;
+    let settings = |admin: &Keypair| CampaignDetails { withdraw_cooldown_secs: 3_600, ..campaign_settings(admin, 0, 0) };
+    let (admin, campaign) = funded_campaign(&mut env, settings, 2 * LAMPORTS_PER_SOL).await;
+    let treasury = env.treasury.pubkey();
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await.unwrap();
+    let withdrawn_at = env.campaign(&campaign).await.last_withdraw_ts;
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::WithdrawCooldownActive));
+
+    env.warp_to_timestamp(withdrawn_at + 3_599).await;
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::WithdrawCooldownActive));
+
+    env.warp_to_timestamp(withdrawn_at + 3_600).await;
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await.unwrap();
+    assert_eq!(env.campaign(&campaign).await.last_withdraw_ts, withdrawn_at + 3_600);
+}
+
+#[tokio::test]
+async fn soft_goal_unlocks_withdrawing_before_the_full_goal() {
+    let mut env = start().await;
+    let end = env.now().await + 86_400;
+    let settings = |admin: &Keypair| CampaignDetails {
+        withdraw_policy: WithdrawPolicy::GoalOnly,
+        ..campaign_settings(admin, 4 * LAMPORTS_PER_SOL, end)
+    };
+    let (admin, campaign) = funded_campaign(&mut env, settings, LAMPORTS_PER_SOL / 2).await;
+    let soft_goal = instruction::set_soft_goal(&env.program_id, &campaign, &admin.pubkey(), LAMPORTS_PER_SOL);
+    env.process(&[soft_goal], &[&admin]).await.unwrap();
+    let treasury = env.treasury.pubkey();
+    let withdraw = |amount| instruction::withdraw(&program_id(), &campaign, &admin.pubkey(), &treasury, amount);
+
+    let err = instruction_error(env.process(&[withdraw(LAMPORTS_PER_SOL / 4)], &[&admin]).await);
+    assert_eq!(err, fund_error(FundError::GoalNotReached));
+
+    // At the soft goal withdrawals open, the campaign isn't fully funded yet.
+    let donor = env.new_wallet(4 * LAMPORTS_PER_SOL).await;
+    env.donate(&campaign, &donor, LAMPORTS_PER_SOL / 2).await.unwrap();
+    assert!(!env.campaign(&campaign).await.fully_funded());
+    env.process(&[withdraw(LAMPORTS_PER_SOL / 2)], &[&admin]).await.unwrap();
+
+    env.donate(&campaign, &donor, 3 * LAMPORTS_PER_SOL).await.unwrap();
+    assert!(env.campaign(&campaign).await.fully_funded());
+    env.process(&[withdraw(LAMPORTS_PER_SOL)], &[&admin]).await.unwrap();
+}
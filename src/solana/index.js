@@ -6,7 +6,7 @@ import {
     PublicKey,
     TransactionInstruction
 } from "@solana/web3.js";
-import { deserialize, serialize } from "borsh";
+import { deserializeUnchecked, serialize } from "borsh";
 
 const cluster = "https://api.devnet.solana.com";
 const connection = new Connection(cluster, "confirmed");
@@ -43,6 +43,66 @@ export async function signAndSendTransaction(transaction) {
     }
 }
 
+class CycleSummary {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+// The program keeps up to 8 previous cycles of a campaign, each 20 bytes once serialized,
+// so we allocate room for them when the campaign account is created.
+const MAX_PREVIOUS_CYCLES = 8;
+const CYCLE_SUMMARY_LEN = 20;
+// Largest campaign account the program accepts, MAX_CAMPAIGN_LEN in the program.
+const MAX_CAMPAIGN_LEN = 10240;
+
+class QueuedWithdraw {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class PendingDonation {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class Milestone {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class AccountingCorrection {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+// A timelocked campaign can queue up to 4 withdrawals, 16 bytes each once serialized.
+const MAX_QUEUED_WITHDRAWS = 4;
+const QUEUED_WITHDRAW_LEN = 16;
+// The platform can correct a campaign's total up to 4 times, 26 bytes per correction.
+const MAX_CORRECTIONS = 4;
+const CORRECTION_LEN = 26;
+// With a confirmation threshold up to 8 donations can be pending, 48 bytes each.
+const MAX_PENDING_DONATIONS = 8;
+const PENDING_DONATION_LEN = 48;
+
+// Matches RENT_BUFFER_LAMPORTS in the program.
+const RENT_BUFFER_LAMPORTS = 10000;
+
 class CampaignDetails {
     constructor(properties) {
         Object.keys(properties).forEach((key) => {
@@ -53,21 +113,257 @@ class CampaignDetails {
         kind: 'struct',
         fields: [
             ['admin', [32]],
+            ['name_hash', [32]],
+            ['campaign_id', 'u64'],
             ['name', 'string'],
-            ['description', 'string'],
             ['image_link', 'string'],
-            ['amount_donated', 'u64']
+            ['amount_donated', 'u64'],
+            ['disputed', 'u8'],
+            ['donations_paused', 'u8'],
+            ['goal', 'u64'],
+            ['end_timestamp', 'u64'],
+            ['state', 'u8'],
+            ['donor_count', 'u32'],
+            ['total_refunded', 'u64'],
+            ['cycle', 'u32'],
+            ['currency', [8]],
+            ['amount_matched', 'u64'],
+            ['max_withdraw_per_epoch', 'u64'],
+            ['withdrawn_this_epoch', 'u64'],
+            ['withdraw_epoch', 'u64'],
+            ['withdraw_cooldown_secs', 'u64'],
+            ['last_withdraw_ts', 'u64'],
+            ['fee_exempt', 'u8'],
+            ['fees_reserved', 'u64'],
+            ['fee_assessed_volume', 'u64'],
+            ['refund_deadline', 'u64'],
+            ['attestation', [32]],
+            ['refund_pool', 'u64'],
+            ['refundable_total', 'u64'],
+            ['total_redirected', 'u64'],
+            ['refund_root', [32]],
+            ['hard_cap', 'u64'],
+            ['fill_to_cap', 'u8'],
+            ['soft_goal', 'u64'],
+            ['withdraw_policy', 'u8'],
+            ['max_donation_per_tx', 'u64'],
+            ['non_refundable_total', 'u64'],
+            ['failed_withdrawable', 'u64'],
+            ['content', [32]],
+            ['admin_paused', 'u8'],
+            ['pause_until', 'u64'],
+            ['withdraw_delay_secs', 'u64'],
+            ['snapshot_slot', 'u64'],
+            ['snapshot_hash', [32]],
+            ['donation_sequence', 'u64'],
+            ['donations_in_window', 'u32'],
+            ['donation_window_start', 'u64'],
+            ['confirmation_slots', 'u64'],
+            ['metadata_locked', 'u8'],
+            ['archived', 'u8'],
+            ['max_donors', 'u32'],
+            ['require_full_donor_count', 'u8'],
+            ['featured_until', 'u64'],
+            ['category', 'u8'],
+            ['returned_amount', 'u64'],
+            ['withdraw_recipients', [[32]]],
+            ['withdraw_queue', [QueuedWithdraw]],
+            ['corrections', [AccountingCorrection]],
+            ['pending_donations', [PendingDonation]],
+            ['milestones', [Milestone]],
+            ['previous_cycles', [CycleSummary]]
+        ]
+    }], [AccountingCorrection, {
+        kind: 'struct',
+        fields: [
+            ['old_amount_donated', 'u64'],
+            ['new_amount_donated', 'u64'],
+            ['reason_code', 'u16'],
+            ['corrected_at', 'u64']
+        ]
+    }], [PendingDonation, {
+        kind: 'struct',
+        fields: [
+            ['donor', [32]],
+            ['amount', 'u64'],
+            ['slot', 'u64']
+        ]
+    }], [Milestone, {
+        kind: 'struct',
+        fields: [
+            ['amount', 'u64'],
+            ['released', 'u8']
+        ]
+    }], [QueuedWithdraw, {
+        kind: 'struct',
+        fields: [
+            ['amount', 'u64'],
+            ['requested_at', 'u64']
+        ]
+    }], [CycleSummary, {
+        kind: 'struct',
+        fields: [
+            ['raised', 'u64'],
+            ['donors', 'u32'],
+            ['ended_at', 'u64']
+        ]
+    }]]);
+}
+
+// What create_campaign takes: the campaign and its description, which the program
+// stores in the campaign's content PDA.
+class CreateCampaignRequest {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([...CampaignDetails.schema, [CreateCampaignRequest, {
+        kind: 'struct',
+        fields: [
+            ['campaign', CampaignDetails],
+            ['description', 'string']
+        ]
+    }]]);
+}
+
+class CampaignContent {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[CampaignContent, {
+        kind: 'struct',
+        fields: [
+            ['campaign', [32]],
+            ['description', 'string'],
+            ['last_update_ts', 'u64']
         ]
     }]]);
 }
 
+async function getCampaignContentAccount(campaignPubKey) {
+    const [content] = await PublicKey.findProgramAddress(
+        [Buffer.from("content"), campaignPubKey.toBuffer()],
+        programId
+    );
+    return content;
+}
+
 export async function checkWallet() {
     if (!wallet.connected) {
         await wallet.connect();
     }
 }
 
-export async function createCampaign(name, description, image_link) {
+// The program stores the currency label as 8 ASCII bytes padded with zeros.
+function currencyLabel(currency) {
+    let label = new Uint8Array(8);
+    label.set(Buffer.from(currency, "ascii").slice(0, 8));
+    return label;
+}
+
+// The CampaignDetails create_campaign gets, `options` holds the optional campaign settings,
+// anything left out gets the program's default.
+function newCampaignDetails(name, image_link, admin, options = {}) {
+    const {
+        goal = 0,
+        end_timestamp = 0,
+        currency = "SOL",
+        max_withdraw_per_epoch = 0,
+        withdraw_cooldown_secs = 0,
+        withdraw_recipients = [],
+        attestation = new Uint8Array(32),
+        hard_cap = 0,
+        fill_to_cap = false,
+        soft_goal = 0,
+        // 0 withdraws anytime, 1 only once the soft goal (or the goal) is reached.
+        withdraw_policy = 0,
+        max_donation_per_tx = 0,
+        // Seconds between requesting a withdraw and executing it, 0 withdraws right away.
+        withdraw_delay_secs = 0,
+        // Slots a donation waits before it counts in amount_donated, 0 counts it right away.
+        confirmation_slots = 0,
+        // Freezes the name, image and description once the first donation arrives.
+        metadata_locked = false,
+        // Cumulative amounts, increasing, the funds unlock in these tranches.
+        milestones = [],
+        // Most different donors the campaign takes, 0 for no limit. With require_full_donor_count
+        // the campaign only succeeds once all of them donated.
+        max_donors = 0,
+        require_full_donor_count = false,
+        // Index of the platform category, below MAX_CATEGORIES, 0 is uncategorized.
+        category = 0,
+    } = options;
+
+    return new CampaignDetails({
+        name: name,
+        image_link: image_link,
+        admin: admin.toBuffer(),
+        // The program computes it from the name.
+        name_hash: new Uint8Array(32),
+        campaign_id: 0,
+        amount_donated: 0,
+        disputed: 0,
+        donations_paused: 0,
+        goal: goal,
+        end_timestamp: end_timestamp,
+        state: 0,
+        donor_count: 0,
+        total_refunded: 0,
+        cycle: 0,
+        currency: currencyLabel(currency),
+        amount_matched: 0,
+        max_withdraw_per_epoch: max_withdraw_per_epoch,
+        withdrawn_this_epoch: 0,
+        withdraw_epoch: 0,
+        withdraw_cooldown_secs: withdraw_cooldown_secs,
+        last_withdraw_ts: 0,
+        fee_exempt: 0,
+        fees_reserved: 0,
+        fee_assessed_volume: 0,
+        refund_deadline: 0,
+        attestation: attestation,
+        refund_pool: 0,
+        refundable_total: 0,
+        total_redirected: 0,
+        refund_root: new Uint8Array(32),
+        hard_cap: hard_cap,
+        fill_to_cap: fill_to_cap ? 1 : 0,
+        soft_goal: soft_goal,
+        withdraw_policy: withdraw_policy,
+        max_donation_per_tx: max_donation_per_tx,
+        non_refundable_total: 0,
+        failed_withdrawable: 0,
+        // The program sets it to the content PDA.
+        content: new Uint8Array(32),
+        admin_paused: 0,
+        pause_until: 0,
+        withdraw_delay_secs: withdraw_delay_secs,
+        snapshot_slot: 0,
+        snapshot_hash: new Uint8Array(32),
+        donation_sequence: 0,
+        donations_in_window: 0,
+        donation_window_start: 0,
+        confirmation_slots: confirmation_slots,
+        metadata_locked: metadata_locked ? 1 : 0,
+        archived: 0,
+        max_donors: max_donors,
+        require_full_donor_count: require_full_donor_count ? 1 : 0,
+        featured_until: 0,
+        category: category,
+        returned_amount: 0,
+        withdraw_recipients: withdraw_recipients.map((r) => r.toBuffer()),
+        withdraw_queue: [],
+        corrections: [],
+        pending_donations: [],
+        milestones: milestones.map((amount) => new Milestone({ amount: amount, released: 0 })),
+        previous_cycles: []
+    });
+}
+
+export async function createCampaign(name, description, image_link, options = {}) {
     await checkWallet();
 
     const SEED = "abcdef" + Math.random().toString();
@@ -75,34 +371,47 @@ export async function createCampaign(name, description, image_link) {
         wallet.publicKey, SEED, programId
     );
 
-    let campaign = new CampaignDetails({
-        name: name,
-        description: description,
-        image_link: image_link,
-        admin: wallet.publicKey.toBuffer(),
-        amount_donated: 0
-    })
+    let campaign = newCampaignDetails(name, image_link, wallet.publicKey, options);
 
     let data = serialize(CampaignDetails.schema, campaign);
-    let data_to_send = new Uint8Array([0, ...data]);
+    let request = new CreateCampaignRequest({ campaign: campaign, description: description });
+    let data_to_send = new Uint8Array([0, ...serialize(CreateCampaignRequest.schema, request)]);
 
-    const lamports = (await connection.getMinimumBalanceForRentExemption(data.length));
-    console.log(data.length);
+    const space = data.length + MAX_PREVIOUS_CYCLES * CYCLE_SUMMARY_LEN + MAX_QUEUED_WITHDRAWS * QUEUED_WITHDRAW_LEN
+        + MAX_CORRECTIONS * CORRECTION_LEN + MAX_PENDING_DONATIONS * PENDING_DONATION_LEN;
+    if (space > MAX_CAMPAIGN_LEN) {
+        throw new Error(`Campaign needs ${space} bytes, the program accepts at most ${MAX_CAMPAIGN_LEN}`);
+    }
+    // The program wants a small buffer on top of the rent exemption.
+    const lamports = (await connection.getMinimumBalanceForRentExemption(space)) + RENT_BUFFER_LAMPORTS;
+    console.log(space);
     const createProgramAccount = SystemProgram.createAccountWithSeed({
         fromPubkey: wallet.publicKey,
         basePubkey: wallet.publicKey,
         seed: SEED,
         newAccountPubkey: newAccount,
         lamports: lamports,
-        space: data.length,
+        space: space,
         programId: programId,
     });
 
+    // The creation fee goes to the platform treasury.
+    const { config, treasury } = await getFeeAccounts();
+    const nameAccount = await getCampaignNameAccount(wallet.publicKey, name);
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+    const content = await getCampaignContentAccount(newAccount);
+
     const instructionToOurProgram = new TransactionInstruction({
         // all the account we want to send
         keys: [
             {pubkey: newAccount, isSigner: false, isWritable: true},
-            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: true},
+            {pubkey: config, isSigner: false, isWritable: false},
+            {pubkey: treasury, isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+            {pubkey: nameAccount, isSigner: false, isWritable: true},
+            {pubkey: globalStats, isSigner: false, isWritable: true},
+            {pubkey: content, isSigner: false, isWritable: true},
         ],
         programId: programId,
         // data (instruction_data for our program)
@@ -116,22 +425,180 @@ export async function createCampaign(name, description, image_link) {
     const result = await connection.confirmTransaction(signature);
     console.log("end sendMessage", result);
 
+    // The program hands back the new campaign's addresses and id as return data.
+    const transaction = await connection.getTransaction(signature, { commitment: "confirmed" });
+    const returnData = transaction?.meta?.returnData;
+    return returnData ? decodeCreateCampaignResult(returnData.data[0]) : null;
+}
+
+class CreateCampaignResult {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[CreateCampaignResult,
+        {
+            kind: 'struct',
+            fields: [
+                ['campaign', [32]],
+                ['content', [32]],
+                ['name_record', [32]],
+                ['campaign_id', 'u64'],
+            ]
+        }]]);
+}
+
+// Reads the return data of create_campaign, base64 like the transaction meta has it.
+export function decodeCreateCampaignResult(base64Data) {
+    const result = deserializeUnchecked(
+        CreateCampaignResult.schema, CreateCampaignResult, Buffer.from(base64Data, "base64")
+    );
+    return {
+        campaign: new PublicKey(result.campaign),
+        content: new PublicKey(result.content),
+        name_record: new PublicKey(result.name_record),
+        campaign_id: result.campaign_id,
+    };
+}
+
+class CreatePreview {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[CreatePreview,
+        {
+            kind: 'struct',
+            fields: [
+                ['required_len', 'u64'],
+                ['min_balance', 'u64'],
+                ['creation_fee', 'u64'],
+                ['name_record', [32]],
+            ]
+        }]]);
+}
+
+// Reads the return data of validate_create (instruction 65), base64.
+export function decodeCreatePreview(base64Data) {
+    const preview = deserializeUnchecked(
+        CreatePreview.schema, CreatePreview, Buffer.from(base64Data, "base64")
+    );
+    return {
+        required_len: preview.required_len,
+        min_balance: preview.min_balance,
+        creation_fee: preview.creation_fee,
+        name_record: new PublicKey(preview.name_record),
+    };
+}
+
+// Simulates validate_create with the same arguments as createCampaign, nothing is signed or sent.
+// Returns { ok: true, preview } or { ok: false, err, error, logs } with the program's error payload if it left one.
+export async function previewCreateCampaign(name, description, image_link, options = {}) {
+    await checkWallet();
+    let campaign = newCampaignDetails(name, image_link, wallet.publicKey, options);
+    let request = new CreateCampaignRequest({ campaign: campaign, description: description });
+    let data_to_send = new Uint8Array([65, ...serialize(CreateCampaignRequest.schema, request)]);
+
+    const { config } = await getFeeAccounts();
+    const instruction = new TransactionInstruction({
+        keys: [
+            {pubkey: config, isSigner: false, isWritable: false},
+        ],
+        programId: programId,
+        data: data_to_send,
+    });
+    const trans = await setPayerAndBlockhashTransaction([instruction]);
+    const result = await connection.simulateTransaction(trans);
+    const returnData = result.value.returnData;
+    if (result.value.err) {
+        return {
+            ok: false,
+            err: result.value.err,
+            error: returnData ? decodeErrorReturnData(returnData.data[0]) : null,
+            logs: result.value.logs,
+        };
+    }
+    return { ok: true, preview: decodeCreatePreview(returnData.data[0]) };
+}
+
+
+// Same rule as the program's name_key: lowercased, zero-width characters dropped
+// and whitespace runs collapsed to one space.
+function campaignNameKey(name) {
+    return name
+        .toLowerCase()
+        .replace(/[\u200B\u200C\u200D\u2060\uFEFF]/g, "")
+        .split(/\s+/)
+        .filter((part) => part.length > 0)
+        .join(" ");
+}
+
+// Same rule as the program's campaign_name_hash: sha256 of the name key.
+async function campaignNameHash(name) {
+    const bytes = new TextEncoder().encode(campaignNameKey(name));
+    return Buffer.from(await crypto.subtle.digest("SHA-256", bytes));
+}
+
+async function getCampaignNameAccount(admin, name) {
+    const [nameAccount] = await PublicKey.findProgramAddress(
+        [Buffer.from("campaign_name"), admin.toBuffer(), await campaignNameHash(name)],
+        programId
+    );
+    return nameAccount;
+}
+
+// Looks up the campaign `admin` created under `name` without scanning all campaigns.
+// Returns null when there is none.
+export async function findCampaignByName(admin, name) {
+    const info = await connection.getAccountInfo(await getCampaignNameAccount(admin, name));
+    if (info === null) {
+        return null;
+    }
+    return new PublicKey(info.data.slice(0, 32));
 }
 
 
-export async function getAllCampaigns() {
+// Archived campaigns are left out unless `includeArchived` is set.
+export async function getAllCampaigns(includeArchived = false) {
     let accounts = await connection.getProgramAccounts(programId);
+    // Descriptions live in the content PDAs, which are program accounts too.
+    let byKey = new Map(accounts.map((e) => [e.pubkey.toBase58(), e.account.data]));
     let campaigns = []
     accounts.forEach((e) => {
         try {
-            let campData = deserialize(CampaignDetails.schema, CampaignDetails, e.account.data);
+            let campData = deserializeUnchecked(CampaignDetails.schema, CampaignDetails, e.account.data);
+            if (campData.archived === 1 && !includeArchived) {
+                return;
+            }
+            let contentData = byKey.get(new PublicKey(campData.content).toBase58());
+            let description = contentData
+                ? deserializeUnchecked(CampaignContent.schema, CampaignContent, contentData).description
+                : "";
             campaigns.push({
                 pubId: e.pubkey,
                 name: campData.name,
-                description: campData.description,
+                campaign_id: campData.campaign_id,
+                description: description,
                 image_link: campData.image_link,
                 amount_donated: campData.amount_donated,
                 admin: campData.admin,
+                disputed: campData.disputed === 1,
+                donations_paused: campData.donations_paused === 1,
+                admin_paused: campData.admin_paused === 1,
+                pause_until: campData.pause_until,
+                goal: campData.goal,
+                soft_goal: campData.soft_goal,
+                end_timestamp: campData.end_timestamp,
+                state: campData.state,
+                donor_count: campData.donor_count,
+                currency: Buffer.from(campData.currency).toString("ascii").replace(/\0+$/, ""),
+                attestation: Buffer.from(campData.attestation).toString("hex"),
+                snapshot_slot: campData.snapshot_slot,
+                snapshot_hash: Buffer.from(campData.snapshot_hash).toString("hex"),
+                donation_sequence: campData.donation_sequence,
+                archived: campData.archived === 1,
             });
         } catch (err) {
             console.log(err);
@@ -141,75 +608,770 @@ export async function getAllCampaigns() {
 }
 
 
-export async function donateToCampaign(campaignPubKey, amount) {
-    await checkWallet();
-
-    const SEED = "abcd" + Math.random().toString();
-    let newAccount = await PublicKey.createWithSeed(
-        wallet.publicKey,
-        SEED,
-        programId
-    );
+class UpdateCampaignRequest {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[UpdateCampaignRequest,
+        {
+            kind: 'struct',
+            fields: [
+                ['description', 'string']
+            ]
+        }]]);
+}
 
-    const createProgramAccount = SystemProgram.createAccountWithSeed({
-        fromPubkey: wallet.publicKey,
-        basePubkey: wallet.publicKey,
-        seed: SEED,
-        newAccountPubkey: newAccount,
-        lamports: amount,
-        space: 1,
-        programId: programId,
-    });
+// Changes the description of a campaign, only its admin can do it.
+export async function updateCampaign(campaignPubKey, description) {
+    await checkWallet();
+    const content = await getCampaignContentAccount(campaignPubKey);
+    let data = serialize(UpdateCampaignRequest.schema, new UpdateCampaignRequest({ description: description }));
+    let data_to_send = new Uint8Array([37, ...data]);
 
-    // create instruction
     const instructionToOurProgram = new TransactionInstruction({
         keys: [
-            { pubkey: campaignPubKey, isSigner: false, isWritable: true },
-            { pubkey: newAccount, isSigner: false},
-            {pubkey: wallet.publicKey, isSigner: true},
+            {pubkey: campaignPubKey, isSigner: false, isWritable: false},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+            {pubkey: content, isSigner: false, isWritable: true},
         ],
         programId: programId,
-        data: new Uint8Array([2])
+        data: data_to_send,
     });
 
-    const trans = await setPayerAndBlockhashTransaction([createProgramAccount, instructionToOurProgram]);
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
     const signature = await signAndSendTransaction(trans);
     const result = await connection.confirmTransaction(signature);
     console.log("end sendMessage", result);
 }
 
-class WithdrawRequest {
+
+class PauseCampaignRequest {
     constructor(properties) {
         Object.keys(properties).forEach((key) => {
             this[key] = properties[key];
         });
     }
-    static schema = new Map([[WithdrawRequest,
+    static schema = new Map([[PauseCampaignRequest,
         {
             kind: 'struct',
             fields: [
-                ['amount', 'u64'],
+                ['paused', 'u8'],
+                ['pause_until', 'u64']
             ]
         }]]);
 }
 
-export async function withdraw(campaignPubKey, amount) {
+// Pauses or resumes donations to a campaign. `pauseUntil` is a unix timestamp
+// after which the pause ends by itself, 0 keeps it until the admin resumes.
+export async function pauseCampaign(campaignPubKey, paused, pauseUntil = 0) {
     await checkWallet();
-    let withdrawRequest = new WithdrawRequest({amount: amount});
-    let data = serialize(WithdrawRequest.schema, withdrawRequest)
-    let data_to_send = new Uint8Array([1, ...data]);
+    let request = new PauseCampaignRequest({ paused: paused ? 1 : 0, pause_until: paused ? pauseUntil : 0 });
+    let data_to_send = new Uint8Array([39, ...serialize(PauseCampaignRequest.schema, request)]);
 
     const instructionToOurProgram = new TransactionInstruction({
         keys: [
-            { pubkey: campaignPubKey, isSigner: false, isWritable: true},
-            { pubkey: wallet.publicKey, isSigner: true}
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
         ],
         programId: programId,
-        data: data_to_send
+        data: data_to_send,
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The admin changes the goal and withdraw policy (0 anytime, 1 goal only). Once the campaign got
+// donations the policy can't change, and a goal only campaign can only lower its goal.
+// The goal can't be under the platform's min_goal_lamports.
+export async function setGoal(campaignPubKey, goal, withdrawPolicy) {
+    await checkWallet();
+    const data_to_send = Buffer.alloc(10);
+    data_to_send[0] = 63;
+    data_to_send.writeBigUInt64LE(BigInt(goal), 1);
+    data_to_send[9] = withdrawPolicy;
+
+    const { config } = await getFeeAccounts();
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+            {pubkey: config, isSigner: false, isWritable: false},
+        ],
+        programId: programId,
+        data: data_to_send,
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The admin pays the treasury to feature the campaign for `durationSecs` more, priced by the
+// config's feature_fee_per_day.
+export async function featureCampaign(campaignPubKey, durationSecs) {
+    await checkWallet();
+    const data_to_send = Buffer.alloc(9);
+    data_to_send[0] = 66;
+    data_to_send.writeBigUInt64LE(BigInt(durationSecs), 1);
+
+    const { config, treasury } = await getFeeAccounts();
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: true},
+            {pubkey: config, isSigner: false, isWritable: false},
+            {pubkey: treasury, isSigner: false, isWritable: true},
+            {pubkey: SystemProgram.programId, isSigner: false, isWritable: false},
+        ],
+        programId: programId,
+        data: data_to_send,
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The admin moves the campaign to another category, only possible before its first donation.
+export async function setCategory(campaignPubKey, category) {
+    await checkWallet();
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+            {pubkey: globalStats, isSigner: false, isWritable: true},
+        ],
+        programId: programId,
+        data: new Uint8Array([68, category]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+export const MAX_CATEGORIES = 8;
+
+// Per-category totals from the global stats account: campaigns created, lamports raised (donated on
+// every path and matched, less returned and reclaimed donations) and successful finalizations,
+// indexed by category.
+export async function getCategoryStats() {
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+    const account = await connection.getAccountInfo(globalStats);
+    const stats = [];
+    // next_campaign_id and total_tips come first. An account that wasn't grown yet reads as zeros.
+    for (let i = 0; i < MAX_CATEGORIES; i++) {
+        const offset = 16 + i * 24;
+        const read = (at) => (account.data.length >= at + 8 ? account.data.readBigUInt64LE(at) : 0n);
+        stats.push({
+            campaigns_created: read(offset),
+            amount_raised: read(offset + 8),
+            successful_campaigns: read(offset + 16),
+        });
+    }
+    return stats;
+}
+
+// Whether a deserialized campaign is featured at `now` (unix seconds).
+export function isFeatured(campaign, now = Math.floor(Date.now() / 1000)) {
+    return Number(campaign.featured_until) > now;
+}
+
+// Records the hash of the campaign at the current slot, anyone can call it
+// but only once every 150 slots per campaign.
+export async function snapshotCampaign(campaignPubKey) {
+    await checkWallet();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+        ],
+        programId: programId,
+        data: new Uint8Array([43]),
     });
 
     const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
     const signature = await signAndSendTransaction(trans);
     const result = await connection.confirmTransaction(signature);
     console.log("end sendMessage", result);
-}
\ No newline at end of file
+}
+
+// Anyone can fold the donations that waited their confirmation slots into the campaign's total.
+export async function confirmDonations(campaignPubKey) {
+    await checkWallet();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+        ],
+        programId: programId,
+        data: new Uint8Array([51]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The donor takes back their donations to the campaign that stayed pending for about an hour.
+export async function reclaimPending(campaignPubKey) {
+    await checkWallet();
+
+    const [donationRecord] = await PublicKey.findProgramAddress(
+        [Buffer.from("donation"), campaignPubKey.toBuffer(), wallet.publicKey.toBuffer()],
+        programId
+    );
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: true},
+            {pubkey: donationRecord, isSigner: false, isWritable: true},
+            {pubkey: globalStats, isSigner: false, isWritable: true},
+        ],
+        programId: programId,
+        data: new Uint8Array([53]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The campaign admin gives `donorPubKey` back everything they donated in the current cycle.
+export async function returnDonation(campaignPubKey, donorPubKey) {
+    await checkWallet();
+
+    const [donationRecord] = await PublicKey.findProgramAddress(
+        [Buffer.from("donation"), campaignPubKey.toBuffer(), donorPubKey.toBuffer()],
+        programId
+    );
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+            {pubkey: donationRecord, isSigner: false, isWritable: true},
+            {pubkey: donorPubKey, isSigner: false, isWritable: true},
+            {pubkey: globalStats, isSigner: false, isWritable: true},
+        ],
+        programId: programId,
+        data: new Uint8Array([69, ...donorPubKey.toBuffer()]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// The campaign admin hides the campaign from default listings, or brings it back.
+export async function archiveCampaign(campaignPubKey, archived) {
+    await checkWallet();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+        ],
+        programId: programId,
+        data: new Uint8Array([58, archived ? 1 : 0]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+class SetImageRequest {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[SetImageRequest,
+        {
+            kind: 'struct',
+            fields: [
+                ['image_link', 'string'],
+            ]
+        }]]);
+}
+
+// The campaign admin changes only the image of the campaign.
+export async function setCampaignImage(campaignPubKey, imageLink) {
+    await checkWallet();
+
+    const data = serialize(SetImageRequest.schema, new SetImageRequest({ image_link: imageLink }));
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            {pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            {pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+        ],
+        programId: programId,
+        data: new Uint8Array([52, ...data]),
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+class DonateDirectRequest {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[DonateDirectRequest,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'u64'],
+                ['non_refundable', 'u8'],
+                ['tip_bps', 'u16'],
+                ['tip_lamports', 'u64'],
+            ]
+        }]]);
+}
+
+// Where the receipt of donation number `sequence` (counting from 0) to a campaign is.
+export async function findReceiptAddress(campaignPubKey, sequence) {
+    // `sequence` can be a number or the BN borsh gives back for a u64.
+    const sequenceBytes = Buffer.alloc(8);
+    sequenceBytes.writeBigUInt64LE(BigInt(sequence.toString()));
+    const [receipt] = await PublicKey.findProgramAddress(
+        [Buffer.from("receipt"), campaignPubKey.toBuffer(), sequenceBytes],
+        programId
+    );
+    return receipt;
+}
+
+async function findBlocklistAddress(campaignPubKey, walletPubKey) {
+    const [blocklist] = await PublicKey.findProgramAddress(
+        [Buffer.from("blocked"), campaignPubKey.toBuffer(), walletPubKey.toBuffer()],
+        programId
+    );
+    return blocklist;
+}
+
+// The campaign admin refuses (or accepts again) donations from `walletPubKey`.
+export async function setDonorBlocked(campaignPubKey, walletPubKey, blocked) {
+    await checkWallet();
+    const blocklist = await findBlocklistAddress(campaignPubKey, walletPubKey);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: false },
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: walletPubKey, isSigner: false, isWritable: false },
+            { pubkey: blocklist, isSigner: false, isWritable: true },
+            ...(blocked ? [{ pubkey: SystemProgram.programId, isSigner: false, isWritable: false }] : []),
+        ],
+        programId: programId,
+        data: new Uint8Array([blocked ? 47 : 48])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+async function findRefundRecipientAddress(campaignPubKey, donorPubKey) {
+    const [refundRecipient] = await PublicKey.findProgramAddress(
+        [Buffer.from("refund_to"), campaignPubKey.toBuffer(), donorPubKey.toBuffer()],
+        programId
+    );
+    return refundRecipient;
+}
+
+// The donor has their refunds from this campaign paid to `recipientPubKey` instead of this wallet.
+// Passing the wallet itself undoes it.
+export async function setRefundRecipient(campaignPubKey, recipientPubKey) {
+    await checkWallet();
+    const refundRecipient = await findRefundRecipientAddress(campaignPubKey, wallet.publicKey);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: false },
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: refundRecipient, isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+        ],
+        programId: programId,
+        data: new Uint8Array([62, ...recipientPubKey.toBuffer()])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// Gets back the lamports of a staging account from the old two-step donate flow, when its donate
+// transaction never landed. `seed` is the seed the staging account was created with.
+export async function reclaimStaged(stagingPubKey, seed) {
+    await checkWallet();
+    const seedBytes = Buffer.from(seed);
+    const length = Buffer.alloc(4);
+    length.writeUInt32LE(seedBytes.length);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: stagingPubKey, isSigner: false, isWritable: true },
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+        ],
+        programId: programId,
+        data: new Uint8Array([64, ...length, ...seedBytes])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// Donates straight from the wallet (donate_direct), no staging account is needed anymore.
+// A non-refundable donation stays with the campaign even if it fails.
+// `tipBps` adds an optional tip to the platform on top of the donation, in basis points of it,
+// and `tipLamports` a fixed one.
+export async function donateToCampaign(campaignPubKey, amount, nonRefundable = false, tipBps = 0, tipLamports = 0) {
+    await checkWallet();
+
+    // The receipt goes at the campaign's next donation sequence.
+    const campaignAccount = await connection.getAccountInfo(campaignPubKey);
+    const campaign = deserializeUnchecked(CampaignDetails.schema, CampaignDetails, campaignAccount.data);
+    const receipt = await findReceiptAddress(campaignPubKey, campaign.donation_sequence);
+    // Always passed, the program checks it to know whether the wallet is blocked.
+    const blocklist = await findBlocklistAddress(campaignPubKey, wallet.publicKey);
+    const { config, treasury } = await getFeeAccounts();
+    const [globalStats] = await PublicKey.findProgramAddress([Buffer.from("stats")], programId);
+
+    const [donationRecord] = await PublicKey.findProgramAddress(
+        [Buffer.from("donation"), campaignPubKey.toBuffer(), wallet.publicKey.toBuffer()],
+        programId
+    );
+
+    let data = serialize(
+        DonateDirectRequest.schema,
+        new DonateDirectRequest({amount: amount, non_refundable: nonRefundable ? 1 : 0, tip_bps: tipBps, tip_lamports: tipLamports})
+    );
+
+    // create instruction
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: true },
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+            { pubkey: donationRecord, isSigner: false, isWritable: true },
+            { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+            { pubkey: receipt, isSigner: false, isWritable: true },
+            { pubkey: blocklist, isSigner: false, isWritable: false },
+            { pubkey: config, isSigner: false, isWritable: false },
+            { pubkey: treasury, isSigner: false, isWritable: true },
+            { pubkey: globalStats, isSigner: false, isWritable: true },
+        ],
+        programId: programId,
+        data: new Uint8Array([35, ...data])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+class FeeTier {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class PlatformConfig {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[PlatformConfig,
+        {
+            kind: 'struct',
+            fields: [
+                ['authority', [32]],
+                ['dispute_resolver', [32]],
+                ['treasury', [32]],
+                ['fee_bps', 'u16'],
+                ['insurance_bps', 'u16'],
+                ['creation_fee', 'u64'],
+                ['refund_fees', 'u8'],
+                ['refund_window_secs', 'u64'],
+                ['legacy_donate_disabled', 'u8'],
+                ['fee_tiers', [FeeTier]],
+                ['cpi_callers', [[32]]],
+                ['sunset', 'u8'],
+                // 0 floor, 1 ceil, 2 nearest.
+                ['rounding', 'u8'],
+                ['suggested_tip_bps', 'u16'],
+                ['loyalty_min_donated', 'u64'],
+                ['loyalty_discount_bps', 'u16'],
+                ['feature_fee_per_day', 'u64'],
+                ['min_goal_lamports', 'u64'],
+                ['min_donation_lamports', 'u64'],
+                ['max_donations_per_window', 'u32'],
+                ['donation_window_secs', 'u64'],
+            ]
+        }], [FeeTier,
+        {
+            kind: 'struct',
+            fields: [
+                ['up_to_amount', 'u64'],
+                ['fee_bps', 'u16'],
+            ]
+        }]]);
+}
+
+// Withdraw pays the platform fee, so it needs the config, the treasury and the insurance pool.
+async function getFeeAccounts() {
+    const [config] = await PublicKey.findProgramAddress([Buffer.from("config")], programId);
+    const [insurancePool] = await PublicKey.findProgramAddress([Buffer.from("insurance")], programId);
+    const configAccount = await connection.getAccountInfo(config);
+    const configData = deserializeUnchecked(PlatformConfig.schema, PlatformConfig, configAccount.data);
+    return { config, treasury: new PublicKey(configData.treasury), insurancePool };
+}
+
+class WithdrawRequest {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[WithdrawRequest,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'u64'],
+            ]
+        }]]);
+}
+
+// `recipient` has to be one of the campaign's withdraw recipients, by default the admin gets the funds.
+export async function withdraw(campaignPubKey, amount, recipient = null) {
+    await checkWallet();
+    let withdrawRequest = new WithdrawRequest({amount: amount});
+    let data = serialize(WithdrawRequest.schema, withdrawRequest)
+    let data_to_send = new Uint8Array([1, ...data]);
+    const { config, treasury, insurancePool } = await getFeeAccounts();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true},
+            { pubkey: config, isSigner: false, isWritable: false },
+            { pubkey: treasury, isSigner: false, isWritable: true },
+            { pubkey: insurancePool, isSigner: false, isWritable: true },
+            ...(recipient ? [{ pubkey: recipient, isSigner: false, isWritable: true }] : []),
+        ],
+        programId: programId,
+        data: data_to_send
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// Timelocked campaigns queue a withdraw first and execute it once the delay has passed.
+export async function requestWithdraw(campaignPubKey, amount) {
+    await checkWallet();
+    let data = serialize(WithdrawRequest.schema, new WithdrawRequest({amount: amount}));
+    let data_to_send = new Uint8Array([40, ...data]);
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+        ],
+        programId: programId,
+        data: data_to_send
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// Executes the oldest queued withdraw.
+export async function executeWithdraw(campaignPubKey, recipient = null) {
+    await checkWallet();
+    const { config, treasury, insurancePool } = await getFeeAccounts();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: true},
+            { pubkey: config, isSigner: false, isWritable: false },
+            { pubkey: treasury, isSigner: false, isWritable: true },
+            { pubkey: insurancePool, isSigner: false, isWritable: true },
+            ...(recipient ? [{ pubkey: recipient, isSigner: false, isWritable: true }] : []),
+        ],
+        programId: programId,
+        data: new Uint8Array([41])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+
+// Drops the queued withdraw at `index`, 0 being the oldest.
+export async function cancelWithdrawRequest(campaignPubKey, index) {
+    await checkWallet();
+
+    const instructionToOurProgram = new TransactionInstruction({
+        keys: [
+            { pubkey: campaignPubKey, isSigner: false, isWritable: true},
+            { pubkey: wallet.publicKey, isSigner: true, isWritable: false},
+        ],
+        programId: programId,
+        data: new Uint8Array([42, index])
+    });
+
+    const trans = await setPayerAndBlockhashTransaction([instructionToOurProgram]);
+    const signature = await signAndSendTransaction(trans);
+    const result = await connection.confirmTransaction(signature);
+    console.log("end sendMessage", result);
+}
+class CampaignUpdate {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+}
+
+class UpdateFeed {
+    constructor(properties) {
+        Object.keys(properties).forEach((key) => {
+            this[key] = properties[key];
+        });
+    }
+    static schema = new Map([[UpdateFeed, {
+        kind: 'struct',
+        fields: [
+            ['campaign', [32]],
+            ['updates', [CampaignUpdate]]
+        ]
+    }], [CampaignUpdate, {
+        kind: 'struct',
+        fields: [
+            ['posted_at', 'u64'],
+            ['message', 'string']
+        ]
+    }]]);
+}
+
+// Updates the admin posted on a campaign, oldest first.
+export async function getCampaignUpdates(campaignPubKey) {
+    const [updateFeed] = await PublicKey.findProgramAddress(
+        [Buffer.from("updates"), campaignPubKey.toBuffer()],
+        programId
+    );
+    const account = await connection.getAccountInfo(updateFeed);
+    if (account === null) {
+        return [];
+    }
+    const feed = deserializeUnchecked(UpdateFeed.schema, UpdateFeed, account.data);
+    return feed.updates.map((u) => ({ posted_at: u.posted_at, message: u.message }));
+}
+
+// Reads the error payload a failed instruction can leave in its return data
+// (`returnData.data[0]` of a simulated transaction, base64). Returns null when there is none.
+// For insufficient funds the context is [required, available] in lamports.
+export function decodeErrorReturnData(base64Data) {
+    const data = Buffer.from(base64Data, "base64");
+    if (data.length < 9 || data[0] !== 0xee) {
+        return null;
+    }
+    const context = [];
+    for (let offset = 9; offset + 8 <= data.length; offset += 8) {
+        context.push(data.readBigUInt64LE(offset));
+    }
+    return { code: data.readBigUInt64LE(1), context };
+}
+
+// Reads the return data of get_campaign_summary (instruction 50), base64 like above.
+export function decodeCampaignSummary(base64Data) {
+    const data = Buffer.from(base64Data, "base64");
+    const status = data[0];
+    return {
+        active: (status & 1) !== 0,
+        paused: (status & 2) !== 0,
+        closed: (status & 4) !== 0,
+        frozen: (status & 8) !== 0,
+        disputed: (status & 16) !== 0,
+        verified: (status & 32) !== 0,
+        state: data[1],
+        amount_donated: data.readBigUInt64LE(2),
+        goal: data.readBigUInt64LE(10),
+        end_timestamp: data.readBigInt64LE(18),
+        donor_count: data.readUInt32LE(26),
+    };
+}
+
+// Reads the return data of get_progress (instruction 61), base64 like above.
+export function decodeCampaignProgress(base64Data) {
+    const data = Buffer.from(base64Data, "base64");
+    return {
+        amount_donated: data.readBigUInt64LE(0),
+        goal: data.readBigUInt64LE(8),
+    };
+}
+
+// Same two values read straight from the campaign account's data, without decoding the rest:
+// skip the admin, name hash and id, then the name and image strings, and amount_donated, the
+// disputed and paused flags and the goal follow. Matches the program's CampaignCounters.
+export function readCampaignProgress(data) {
+    let offset = 32 + 32 + 8;
+    for (let i = 0; i < 2; i++) {
+        offset += 4 + data.readUInt32LE(offset);
+    }
+    return {
+        amount_donated: data.readBigUInt64LE(offset),
+        goal: data.readBigUInt64LE(offset + 10),
+    };
+}
+
+// Reads the return data of get_donor_standing (instruction 56), base64 like above.
+export function decodeDonorStanding(base64Data) {
+    const data = Buffer.from(base64Data, "base64");
+    const status = data[8];
+    return {
+        total: data.readBigUInt64LE(0),
+        refundable: (status & 1) !== 0,
+        refunded: (status & 2) !== 0,
+        non_refundable: (status & 4) !== 0,
+        // 1 once the donor gave enough for the loyalty discount on the suggested tip.
+        tier: data[9],
+    };
+}